@@ -1,19 +1,28 @@
-use std::sync::Arc;
+use std::{
+    io::Read,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use anyhow::anyhow;
 use benihime_loader::paths;
 use benihime_renderer::{
-    Renderer,
+    CursorIcon, Renderer,
     event::{InputEvent, Key, ScrollDelta},
 };
 use thiserror::Error;
 
 use crate::{
-    command::{self, command_registry::CommandRegistry},
-    editor::{Editor, EditorConfig, Mode},
+    args::Args,
+    command::{self, CommandArg, command_registry::CommandRegistry},
+    config::Config,
+    editor::{Editor, Mode},
+    encoding::Encoding,
     graphics::Rect,
+    help, hex,
     input_handler::InputHandler,
     keymap::{
-        self, Keymap,
+        self, Keymap, KeymapResult,
         key_chord::{KeyChord, KeyModifiers},
     },
     project::project_manager::ProjectManager,
@@ -21,10 +30,12 @@ use crate::{
     ui::{
         components::{
             buffer_line::BufferLine, cursor::CursorComponent, mini_buffer::MiniBufferComponent,
-            status_line::StatusLine,
+            status_line::StatusLine, tab_bar::TabBar, which_key::WhichKey,
         },
         composer::{Composer, Context, Event},
         editor_view::EditorView,
+        file_watcher,
+        idle_timer::IdleTimer,
         job::Jobs,
     },
 };
@@ -52,11 +63,17 @@ impl PartialEq for HandleKeyError {
     }
 }
 
+/// How often buffers are compared with their files.
+const FILE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
 pub struct Application {
     pub composer: Composer,
     pub jobs: Jobs,
     pub editor: Editor,
     pub input_handler: InputHandler,
+    key_timer: IdleTimer,
+    auto_save_timer: IdleTimer,
+    text_cursor_icon: bool,
 
     scroll_lerp_factor: f32,
     scroll_min_step_lines: f32,
@@ -72,7 +89,7 @@ pub struct Application {
 }
 
 impl Application {
-    pub fn new() -> Self {
+    pub fn new(args: &Args) -> anyhow::Result<Self> {
         let loader = benihime_loader::Loader::new().unwrap();
 
         let mut command_registry = CommandRegistry::new();
@@ -80,8 +97,31 @@ impl Application {
 
         let theme_loader = ThemeLoader::new(loader.paths.themes_dir());
 
+        let (mut config, mut warnings) = match &args.config {
+            Some(path) if !path.is_file() => {
+                return Err(anyhow!("--config: {} doesn't exist", path.display()));
+            }
+            Some(path) => Config::load(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?,
+            None => Config::load(&loader.paths.config.join("config.toml"))
+                .unwrap_or_else(|e| (Config::default(), vec![format!("config.toml: {}", e)])),
+        };
+
+        if args.headless {
+            // A batch run leaves no trace besides the files it saves, and
+            // loads every file before the next command runs.
+            config.editor.swap_files = false;
+            config.editor.restore_session = false;
+            config.editor.save_session_on_exit = false;
+            config.editor.large_file_threshold_mb = 0;
+        }
+
         let mut keymap = Keymap::new();
-        keymap::default_keymap::register_default_keymap(&mut keymap);
+        keymap::default_keymap::register_default_keymap(&mut keymap, config.editor.keymap_preset);
+        warnings.extend(config.apply_keys(&mut keymap));
+
+        for warning in &warnings {
+            log::warn!("{}", warning);
+        }
 
         let area = Rect::new(0, 0, 120, 40);
         let mut project_manager = ProjectManager::new(area);
@@ -94,10 +134,16 @@ impl Application {
         let mut composer = Composer::new(area);
 
         composer.push(Box::new(BufferLine::new()));
+        composer.push(Box::new(TabBar::new()));
         composer.push(Box::new(EditorView::new()));
         composer.push(Box::new(StatusLine::new()));
         composer.push(Box::new(CursorComponent::new()));
         composer.push(Box::new(MiniBufferComponent::new()));
+        composer.push(Box::new(WhichKey::new()));
+
+        // Before the editor, so the files it opens can run jobs.
+        let mut jobs = Jobs::new();
+        jobs.spawn(file_watcher::tick(FILE_CHECK_INTERVAL));
 
         let mut editor = Editor::new(
             area,
@@ -105,24 +151,49 @@ impl Application {
             project_manager,
             keymap,
             Arc::new(command_registry),
-            Arc::new(EditorConfig::default()),
+            Arc::new(config.editor),
+            &loader.paths,
         );
+        // Until the window reports its size, so commands run before the
+        // first frame can scroll.
+        editor.screen_height = area.height as usize;
+        editor.screen_width = area.width as usize;
+
+        if let Some(theme) = &args.theme {
+            editor
+                .load_theme(theme)
+                .map_err(|e| anyhow!("--theme {}: {}", theme, e))?;
+        }
 
-        let first_id = editor.new_buffer_from_text(
-            "[No Name]",
-            "Welcome to Benihime!\n\nType something here...",
-            None,
-        );
-        editor.focus_buf(first_id);
+        if !Self::open_args(&mut editor, args) {
+            let first_id = editor.new_buffer_from_text(
+                "[No Name]",
+                "Welcome to Benihime!\n\nType something here...",
+                None,
+            );
+            editor.focus_buf(first_id);
+
+            if editor.config.restore_session && !args.opens_anything() {
+                let name = editor.session_name.clone();
+                if editor.session_path(&name).is_file()
+                    && let Err(e) = editor.load_session(&name)
+                {
+                    log::error!("session: {}", e);
+                }
+            }
+        }
 
         let mode = editor.mode();
 
         let config = Arc::clone(&editor.config);
 
-        Self {
+        let mut input_handler = InputHandler::new(mode);
+        input_handler.set_key_matching(config.key_matching);
+
+        let mut app = Self {
             editor,
             composer,
-            jobs: Jobs::new(),
+            jobs,
 
             scroll_lerp_factor: config.scroll_lerp_factor,
             scroll_min_step_lines: config.scroll_min_step_lines,
@@ -134,7 +205,138 @@ impl Application {
             trackpad_scroll_cols: 0.0,
             last_frame_time: std::time::Instant::now(),
 
-            input_handler: InputHandler::new(mode),
+            input_handler,
+            key_timer: IdleTimer::new(),
+            auto_save_timer: IdleTimer::new(),
+            text_cursor_icon: false,
+        };
+
+        app.run_startup_commands(args)?;
+        Ok(app)
+    }
+
+    /// Opens the files, directories and standard input given on the command
+    /// line, in order, and whether a buffer was opened. A directory becomes
+    /// the current project and the files after it open in it.
+    fn open_args(editor: &mut Editor, args: &Args) -> bool {
+        let mut first = None;
+
+        for (path, position) in &args.paths {
+            if path.is_dir() {
+                match path.canonicalize() {
+                    Ok(root) => {
+                        let id = editor.project_manager.open_dir(root);
+                        editor.switch_project(id);
+                    }
+                    Err(e) => editor.set_error(format!("{}: {}", path.display(), e)),
+                }
+                continue;
+            }
+
+            let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+            let id = match editor.open_file(&path) {
+                Ok(id) => id,
+                Err(e) => {
+                    editor.set_error(e.to_string());
+                    continue;
+                }
+            };
+            editor.focus_buf(id);
+            if let Some((row, col)) = *position
+                && let Err(e) = editor.exec(
+                    "goto-position",
+                    Some(vec![CommandArg::Position { row, col }]),
+                )
+            {
+                editor.set_error(format!("{}: {}", path.display(), e));
+            }
+            first.get_or_insert((editor.project_manager.current_id(), id));
+        }
+
+        if args.stdin {
+            match Self::read_stdin() {
+                Ok(text) => {
+                    let id = editor.new_buffer_from_text("*stdin*", &text, None);
+                    editor.project_manager.add_buffer_to_current(id);
+                    first.get_or_insert((editor.project_manager.current_id(), id));
+                }
+                Err(e) => editor.set_error(format!("stdin: {}", e)),
+            }
+        }
+
+        match first {
+            Some((project, id)) => {
+                editor.switch_project(project);
+                editor.focus_buf(id);
+                true
+            }
+            // A directory alone leaves its project showing an empty buffer.
+            None => editor.tree().window_count() > 0,
+        }
+    }
+
+    fn read_stdin() -> anyhow::Result<String> {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        Ok(Encoding::decode(&bytes)?.0)
+    }
+
+    /// Runs the `+cmd` lines, then the lines of the script, skipping blank
+    /// ones and `#` comments. A failing command is shown as an error, or
+    /// ends a headless run.
+    fn run_startup_commands(&mut self, args: &Args) -> anyhow::Result<()> {
+        if args.headless
+            && let Some(error) = self.editor.error_message.take()
+        {
+            return Err(anyhow!(error));
+        }
+
+        let mut lines: Vec<(String, String)> = args
+            .commands
+            .iter()
+            .map(|command| (format!("+{}", command), command.clone()))
+            .collect();
+
+        if let Some(script) = &args.script {
+            let text = std::fs::read_to_string(script)
+                .map_err(|e| anyhow!("--script {}: {}", script.display(), e))?;
+            lines.extend(text.lines().enumerate().filter_map(|(i, line)| {
+                let line = line.trim();
+                (!line.is_empty() && !line.starts_with('#'))
+                    .then(|| (format!("{}:{}", script.display(), i + 1), line.to_string()))
+            }));
+        }
+
+        for (origin, line) in lines {
+            if self.editor.should_exit {
+                break;
+            }
+            let result = self.editor.run_command_line(&line);
+            let error = match result {
+                Err(e) => Some(e.to_string()),
+                Ok(()) if args.headless => self.editor.error_message.take(),
+                Ok(()) => None,
+            };
+            if let Some(error) = error {
+                if args.headless {
+                    return Err(anyhow!("{}: {}", origin, error));
+                }
+                self.editor.set_error(format!("{}: {}", origin, error));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ends a headless run once its commands are done, waiting for the
+    /// files they saved to be written.
+    pub fn finish_headless(&mut self) -> anyhow::Result<()> {
+        if !self.editor.should_exit {
+            self.editor.exit();
+        }
+        match self.editor.error_message.take() {
+            Some(error) if !self.editor.should_exit => Err(anyhow!(error)),
+            _ => Ok(()),
         }
     }
 
@@ -142,17 +344,37 @@ impl Application {
         let state = &mut self.editor;
         let mode = state.focus_ref().0.mode;
 
-        self.handle_key_with_mode(key, modifiers, mode);
+        self.handle_key_with_mode(
+            KeyChord::new(key).with_modifiers(
+                modifiers.control,
+                modifiers.shift,
+                modifiers.alt,
+                modifiers.super_key,
+            ),
+            mode,
+        );
     }
 
-    pub fn handle_key_with_mode(&mut self, key: Key, modifiers: KeyModifiers, mode: Mode) {
+    pub fn handle_key_with_mode(&mut self, chord: KeyChord, mode: Mode) {
         let state = &mut self.editor;
         let buf_mode = mode;
 
-        let chord = KeyChord {
-            code: key,
-            modifiers,
-        };
+        if let Some(mut seq) = state.describe_key.take() {
+            seq.chords.push(chord);
+            let buf_id = state.focus_ref().1.id;
+            if state.keymap.lookup(buf_id, buf_mode, &seq).is_none()
+                && state.keymap.is_prefix(buf_id, buf_mode, &seq)
+            {
+                state.message = Some(format!("Describe key: {}", seq.to_string()));
+                state.describe_key = Some(seq);
+            } else {
+                let text = help::describe_key(state, buf_mode, &seq);
+                state.message = None;
+                state.show_help(&text);
+            }
+            state.needs_redraw = true;
+            return;
+        }
 
         if buf_mode == Mode::Normal
             && let Some(digit) = chord.as_digit()
@@ -161,23 +383,93 @@ impl Application {
             return;
         }
 
-        if let Some((command_name, args)) = state.keymap.push_key(buf_mode, &chord) {
-            if let Err(e) = state.exec(&command_name, args) {
-                log::error!("handle_key: exec error={}", e);
+        let buf_id = state.focus_ref().1.id;
+        let results = state.keymap.push_key(buf_id, buf_mode, &chord);
+        self.apply_key_results(buf_mode, results);
+    }
+
+    /// Shows a text beam while the pointer is over buffer text.
+    fn update_cursor_icon(&mut self, position: (f32, f32), renderer: &mut Renderer) {
+        let editor = &self.editor;
+        let over_text = self
+            .composer
+            .find::<EditorView>()
+            .and_then(|view| view.position_at(editor, position, true))
+            .is_some();
+
+        if over_text != self.text_cursor_icon {
+            self.text_cursor_icon = over_text;
+            if over_text {
+                renderer.set_cursor_icon(CursorIcon::Text);
+            } else {
+                renderer.reset_cursor_icon();
             }
-            state.clear_prefix();
-            state.needs_redraw = true;
+        }
+    }
+
+    fn apply_key_results(&mut self, mode: Mode, results: Vec<KeymapResult>) {
+        for result in results {
+            match result {
+                KeymapResult::Matched(binding) => {
+                    let state = &mut self.editor;
+                    if let Err(e) = state.exec(&binding.command, binding.args) {
+                        log::error!("handle_key: exec error={}", e);
+                    }
+                    state.clear_prefix();
+                    state.needs_redraw = true;
+                }
+                KeymapResult::Pending => {}
+                KeymapResult::NotFound(chord) => self.handle_unbound_key(mode, chord),
+            }
+        }
+
+        let timeout = Duration::from_millis(self.editor.config.key_timeout_ms);
+        if let Some(deadline) = self.editor.keymap.deadline(timeout) {
+            self.key_timer.schedule(deadline);
+        }
+    }
+
+    fn handle_key_timeout(&mut self) {
+        let timeout = Duration::from_millis(self.editor.config.key_timeout_ms);
+        let Some(deadline) = self.editor.keymap.deadline(timeout) else {
+            return;
+        };
+
+        if Instant::now() < deadline {
             return;
         }
 
-        match buf_mode {
+        let mode = self.editor.mode();
+        let results = self.editor.keymap.timeout();
+        self.apply_key_results(mode, results);
+    }
+
+    fn handle_unbound_key(&mut self, mode: Mode, chord: KeyChord) {
+        let state = &mut self.editor;
+
+        match mode {
             Mode::Insert => {
+                state.mark_undo_cursor();
                 let (window, buf) = state.focus();
-                let result = if chord.code == Key::Backspace {
+                let result = if buf.is_hex() {
+                    // Hex buffers are overwritten a nibble at a time.
+                    let len = buf.bytes().map_or(0, <[u8]>::len);
+                    match chord.as_char().and_then(|c| c.to_digit(16)) {
+                        Some(digit) => buf.hex_overwrite(&mut window.cursor, digit as u8),
+                        None if chord.code == Key::Backspace => {
+                            window.cursor = hex::step_nibble(window.cursor, len, false);
+                            Ok(())
+                        }
+                        None => Err(anyhow!(
+                            "Type a hex digit to overwrite the one under the cursor"
+                        )),
+                    }
+                } else if chord.code == Key::Backspace {
                     buf.delete_char_before_cursor(&mut window.cursor);
                     Ok(())
                 } else if chord.code == Key::Enter {
-                    buf.insert_char('\n', &mut window.cursor)
+                    let line_ending = buf.line_ending.as_str();
+                    buf.insert_str(&mut window.cursor, line_ending)
                 } else if let Some(c) = chord.as_char() {
                     buf.insert_char(c, &mut window.cursor)
                 } else {
@@ -188,6 +480,9 @@ impl Application {
                     state.error_message = Some(err.to_string());
                 }
 
+                let focus = state.tree().focus;
+                state.sync_changes(Some(focus));
+                state.mark_undo_cursor();
                 state.update_scroll();
             }
             Mode::Command => {
@@ -207,6 +502,12 @@ impl Application {
                     }
                     mini.filter_items();
                     state.needs_redraw = true;
+
+                    if mini.should_accept()
+                        && let Err(e) = state.exec("minibuffer-accept", None)
+                    {
+                        log::error!("handle_key: exec error={}", e);
+                    }
                 }
             }
             _ => {}
@@ -226,11 +527,22 @@ impl benihime_renderer::Application for Application {
     fn render(&mut self, renderer: &mut Renderer) {
         benihime_event::start_frame();
 
+        self.handle_key_timeout();
+
         self.editor.needs_redraw = false;
 
         while let Ok(status) = self.jobs.status_messages.try_recv() {
             self.editor.set_status(status.message.to_string());
         }
+        while let Ok(callback) = self.jobs.callbacks.try_recv() {
+            self.jobs
+                .handle_callback(&mut self.editor, &mut self.composer, Ok(Some(callback)));
+        }
+        self.editor.poll_writes();
+        if let Some(deadline) = self.editor.auto_save_idle() {
+            self.auto_save_timer.schedule(deadline);
+        }
+        self.editor.poll_swap_prompts();
 
         let now = std::time::Instant::now();
         let dt = now.duration_since(self.last_frame_time).as_secs_f32();
@@ -249,7 +561,7 @@ impl benihime_renderer::Application for Application {
         }
     }
 
-    fn handle_event(&mut self, event: InputEvent, _renderer: &mut Renderer) -> bool {
+    fn handle_event(&mut self, event: InputEvent, renderer: &mut Renderer) -> bool {
         // let pending_char = self.composer.layers.iter().any(|layer| {
         //     layer
         //         .as_any()
@@ -261,9 +573,26 @@ impl benihime_renderer::Application for Application {
         //     self.input_handler.set_pending_char();
         // }
 
+        if let InputEvent::Focus(focused) = event {
+            let event = if focused {
+                Event::FocusGained
+            } else {
+                Event::FocusLost
+            };
+            let mut cx = Context {
+                editor: &mut self.editor,
+                scroll: None,
+                jobs: &mut self.jobs,
+                dt: 0.0,
+            };
+            return self.composer.handle_event(&event, &mut cx);
+        }
+
         let result = self.input_handler.handle_input(event.clone());
 
         self.input_handler.set_mode(self.editor.mode());
+        self.input_handler
+            .set_key_matching(self.editor.config.key_matching);
 
         if result.cancelled {
             self.input_handler.clear_pending_char();
@@ -321,7 +650,7 @@ impl benihime_renderer::Application for Application {
 
             if !self.composer.handle_event(&event, &mut cx) {
                 let mode_before = self.editor.mode();
-                self.handle_key_with_mode(binding.code, binding.modifiers, mode_before);
+                self.handle_key_with_mode(binding, mode_before);
             }
             return true;
         }
@@ -337,14 +666,14 @@ impl benihime_renderer::Application for Application {
             let handled = self.composer.handle_event(&event, &mut cx);
 
             if !handled {
-                let needs_immediate_redraw = self.handle_scroll(scroll, _renderer);
+                let needs_immediate_redraw = self.handle_scroll(scroll, renderer);
                 return needs_immediate_redraw;
             }
             return true;
         }
 
         if let Some(mouse) = result.mouse {
-            let event = Event::Mouse(mouse);
+            let event = Event::Mouse(mouse.clone());
 
             let mut cx = Context {
                 editor: &mut self.editor,
@@ -353,7 +682,11 @@ impl benihime_renderer::Application for Application {
                 dt: 0.0,
             };
 
-            return self.composer.handle_event(&event, &mut cx);
+            let consumed = self.composer.handle_event(&event, &mut cx);
+
+            self.update_cursor_icon(mouse.position, renderer);
+            self.input_handler.set_mode(self.editor.mode());
+            return consumed;
         }
 
         if let Some(keys) = result.keys
@@ -371,8 +704,7 @@ impl benihime_renderer::Application for Application {
             };
 
             if !self.composer.handle_event(&event, &mut cx) {
-                let binding = binding.clone();
-                self.handle_key_with_mode(binding.code, binding.modifiers, mode_before);
+                self.handle_key_with_mode(binding.clone(), mode_before);
             }
             return true;
         }
@@ -419,6 +751,18 @@ impl benihime_renderer::Application for Application {
         self.editor.screen_width = width as usize / cell_width;
     }
 
+    fn close_requested(&mut self, _renderer: &mut Renderer) -> bool {
+        if let Err(e) = self.editor.exec("quit", None) {
+            log::error!("close_requested: exec error={}", e);
+        }
+        self.editor.needs_redraw = true;
+        self.editor.should_exit
+    }
+
+    fn should_exit(&self) -> bool {
+        self.editor.should_exit
+    }
+
     fn wants_redraw(&self) -> bool {
         if self.editor.needs_redraw {
             return true;
@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+
+pub const USAGE: &str = "\
+Usage: benihime [OPTIONS] [FILE[:LINE[:COL]] | DIR | -]...
+
+Arguments:
+  FILE[:LINE[:COL]]   Open a file, with the cursor at a 1-based line and column
+  DIR                 Open a directory as the current project
+  -                   Read standard input into a scratch buffer
+  +CMD                Run a command line after startup, as typed after `:`
+
+Options:
+  --config <FILE>     Read options from FILE instead of config.toml
+  --theme <NAME>      Start with theme NAME
+  --script <FILE>     Run each line of FILE as a command after startup
+  --headless          Run without a window and exit when the commands are done
+  -h, --help          Print this help
+  -V, --version       Print the version
+";
+
+#[derive(Debug, Default)]
+pub struct Args {
+    pub help: bool,
+    pub version: bool,
+    pub headless: bool,
+    pub config: Option<PathBuf>,
+    pub theme: Option<String>,
+    pub script: Option<PathBuf>,
+    /// Files and directories in the order given, files with the 1-based
+    /// line and column to put the cursor on.
+    pub paths: Vec<(PathBuf, Option<(usize, usize)>)>,
+    pub stdin: bool,
+    /// Command lines given as `+cmd`.
+    pub commands: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        let mut only_paths = false;
+
+        while let Some(arg) = args.next() {
+            if only_paths {
+                parsed.paths.push(parse_path(&arg));
+                continue;
+            }
+
+            match arg.as_str() {
+                "--" => only_paths = true,
+                "-h" | "--help" => parsed.help = true,
+                "-V" | "--version" => parsed.version = true,
+                "--headless" => parsed.headless = true,
+                "--config" => parsed.config = Some(value(&mut args, &arg)?.into()),
+                "--theme" => parsed.theme = Some(value(&mut args, &arg)?),
+                "--script" => parsed.script = Some(value(&mut args, &arg)?.into()),
+                "-" => parsed.stdin = true,
+                _ if arg.starts_with('+') => parsed.commands.push(arg[1..].to_string()),
+                _ if arg.starts_with('-') => {
+                    return Err(anyhow!("unknown option '{}'\n\n{}", arg, USAGE));
+                }
+                _ => parsed.paths.push(parse_path(&arg)),
+            }
+        }
+
+        if parsed.headless && parsed.script.is_none() && parsed.commands.is_empty() {
+            return Err(anyhow!("--headless needs --script <FILE> or +CMD to run"));
+        }
+        Ok(parsed)
+    }
+
+    /// Whether anything to open was given, in which case the welcome
+    /// buffer and the saved session are skipped.
+    pub fn opens_anything(&self) -> bool {
+        !self.paths.is_empty() || self.stdin
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String> {
+    args.next()
+        .ok_or_else(|| anyhow!("{} needs a value\n\n{}", option, USAGE))
+}
+
+/// Splits `file.rs:120:5` or `file.rs:120` into the path and position. A
+/// path that exists as given is taken whole, colons and all.
+fn parse_path(arg: &str) -> (PathBuf, Option<(usize, usize)>) {
+    if Path::new(arg).exists() {
+        return (arg.into(), None);
+    }
+
+    let mut parts = arg.rsplitn(3, ':');
+    let numbers: Vec<&str> = parts.by_ref().take(2).collect();
+    let rest = parts.next();
+
+    match (rest, numbers.as_slice()) {
+        (Some(path), [col, line]) if !path.is_empty() => {
+            if let (Ok(line), Ok(col)) = (line.parse(), col.parse()) {
+                return (path.into(), Some((line, col)));
+            }
+        }
+        _ => {}
+    }
+    match arg.rsplit_once(':') {
+        Some((path, line)) if !path.is_empty() => match line.parse() {
+            Ok(line) => (path.into(), Some((line, 1))),
+            Err(_) => (arg.into(), None),
+        },
+        _ => (arg.into(), None),
+    }
+}
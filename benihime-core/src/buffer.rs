@@ -1,13 +1,17 @@
 use anyhow::{Ok, anyhow};
 use ropey::{Rope, RopeSlice, iter::Lines};
-use std::{io::Write, path::PathBuf};
+use std::path::PathBuf;
 
 use crate::{
+    encoding::{Encoding, LineEnding},
+    hex,
     movement::selection::Range,
-    undotree::{Edit, UndoEntry, UndoTree},
+    transaction::{ChangeSet, Transaction},
+    undotree::{UndoCursor, UndoStep, UndoTree},
+    writer::FileStamp,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, PartialOrd, Default)]
 pub struct Position {
     pub row: usize,
     pub col: usize,
@@ -47,6 +51,14 @@ impl std::fmt::Display for BufferId {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub enum BufferKind {
+    #[default]
+    Text,
+    /// A binary file; the text is a dump of its bytes.
+    Hex(Vec<u8>),
+}
+
 #[derive(Debug, Clone)]
 pub struct Buffer {
     pub id: BufferId,
@@ -56,8 +68,30 @@ pub struct Buffer {
     pub selection: Option<Selection>,
     pub range: Option<Range>,
     pub undo_tree: UndoTree,
+    /// The text before the changes windows haven't been moved through yet,
+    /// and those changes.
+    changes: Option<(Rope, ChangeSet)>,
+    /// How the text is written to its file.
+    pub encoding: Encoding,
+    /// The line break inserted for new lines.
+    pub line_ending: LineEnding,
+    /// Whether the text used both LF and CRLF when it was read.
+    pub mixed_line_endings: bool,
+    /// The file as it was when last read or written.
+    pub disk_stamp: Option<FileStamp>,
+    /// The file's stamp when the user was last asked about it changing.
+    pub disk_conflict: Option<FileStamp>,
+    /// The file was gone at the last check, which was reported.
+    pub disk_missing: bool,
+    /// Bumped on every change to what would be written.
+    version: u64,
+    /// Opened in large-file mode, with live-grep, reloading, diffing and
+    /// persistent undo off. There's no syntax highlighting to turn off yet.
+    pub large: bool,
+    /// Percent of the file read so far while it loads in the background.
+    pub load_progress: Option<u8>,
+    pub kind: BufferKind,
     dirty: bool,
-    undo_recording: bool,
     read_only: bool,
 }
 
@@ -71,7 +105,17 @@ impl Buffer {
             selection: None,
             range: None,
             undo_tree: UndoTree::new(),
-            undo_recording: true,
+            changes: None,
+            encoding: Encoding::default(),
+            line_ending: LineEnding::default(),
+            mixed_line_endings: false,
+            disk_stamp: None,
+            disk_conflict: None,
+            disk_missing: false,
+            version: 0,
+            large: false,
+            load_progress: None,
+            kind: BufferKind::Text,
             dirty: false,
             read_only,
         }
@@ -84,6 +128,8 @@ impl Buffer {
         file_path: Option<PathBuf>,
         read_only: bool,
     ) -> Self {
+        let (line_ending, mixed_line_endings) = LineEnding::detect(text);
+
         Self {
             id,
             name: name.to_string(),
@@ -92,12 +138,26 @@ impl Buffer {
             selection: None,
             range: None,
             undo_tree: UndoTree::new(),
-            undo_recording: true,
+            changes: None,
+            encoding: Encoding::default(),
+            line_ending,
+            mixed_line_endings,
+            disk_stamp: None,
+            disk_conflict: None,
+            disk_missing: false,
+            version: 0,
+            large: false,
+            load_progress: None,
+            kind: BufferKind::Text,
             dirty: false,
             read_only,
         }
     }
 
+    pub fn text(&self) -> &Rope {
+        &self.lines
+    }
+
     pub fn to_string(&self) -> String {
         self.lines.to_string()
     }
@@ -135,9 +195,7 @@ impl Buffer {
     }
 
     pub fn insert_idx(&mut self, idx: usize, text: &str) -> anyhow::Result<()> {
-        if self.read_only {
-            return Err(anyhow!("Buffer is read only"));
-        }
+        self.check_editable()?;
         self.insert(idx, text);
         Ok(())
     }
@@ -173,18 +231,14 @@ impl Buffer {
     }
 
     pub fn insert_char(&mut self, c: char, cursor: &mut Position) -> anyhow::Result<()> {
-        if self.read_only {
-            return Err(anyhow!("Buffer is read only"));
-        }
+        self.check_editable()?;
         let mut s = String::new();
         s.push(c);
         self.insert_str(cursor, &s)
     }
 
     pub fn insert_str(&mut self, cursor: &mut Position, s: &str) -> anyhow::Result<()> {
-        if self.read_only {
-            return Err(anyhow!("Buffer is read only"));
-        }
+        self.check_editable()?;
         let row = cursor.row;
         let col = cursor.col;
 
@@ -236,7 +290,9 @@ impl Buffer {
             let prev_line_len = self.line_len(row - 1);
             let char_idx = self.lines.line_to_char(row);
             if char_idx > 0 {
-                self.remove(char_idx - 1..char_idx);
+                let crlf = char_idx >= 2 && self.lines.char(char_idx - 2) == '\r';
+                let start = if crlf { char_idx - 2 } else { char_idx - 1 };
+                self.remove(start..char_idx);
                 cursor.row -= 1;
                 cursor.col = prev_line_len;
             }
@@ -263,114 +319,432 @@ impl Buffer {
     }
 
     fn insert(&mut self, at: usize, text: &str) {
-        if self.undo_recording {
-            self.undo_tree.record(Edit::Insert {
-                at,
-                text: text.to_string(),
-            });
-        }
-        self.dirty = true;
-        self.lines.insert(at, text);
+        self.apply(&Transaction::insert(&self.lines, at, text));
     }
 
     fn remove(&mut self, range: std::ops::Range<usize>) {
-        let deleted = self.lines.slice(range.clone()).to_string();
+        self.apply(&Transaction::delete(&self.lines, range));
+    }
 
-        if self.undo_recording {
-            self.undo_tree.record(Edit::Delete {
-                at: range.start,
-                text: deleted,
-            });
+    /// Applies `transaction` and records it in the undo history.
+    pub fn apply(&mut self, transaction: &Transaction) {
+        let changes = transaction.changes();
+        // The dump of a hex buffer only changes with its bytes.
+        if changes.is_empty() || self.is_hex() {
+            return;
+        }
+
+        let inverse = changes.invert(&self.lines);
+        if self.apply_changes(changes) {
+            self.undo_tree.record(changes.clone(), inverse);
+        }
+    }
+
+    fn apply_changes(&mut self, changes: &ChangeSet) -> bool {
+        let original = self.lines.clone();
+        if !changes.apply(&mut self.lines) {
+            log::error!(
+                "buffer {}: changes for {} chars applied to {}",
+                self.name,
+                changes.len(),
+                original.len_chars()
+            );
+            return false;
         }
 
         self.dirty = true;
-        self.lines.remove(range);
+        self.version += 1;
+        self.changes = Some(match self.changes.take() {
+            Some((before, pending)) => (before, pending.compose(changes.clone())),
+            None => (original, changes.clone()),
+        });
+        true
     }
 
-    pub fn undo(&mut self) {
-        if let Some(entry) = self.undo_tree.undo() {
-            self.undo_recording = false;
+    /// The text as it was before the changes made since the last call, and
+    /// those changes, for moving positions kept elsewhere through them.
+    pub fn take_changes(&mut self) -> Option<(Rope, ChangeSet)> {
+        self.changes.take()
+    }
 
-            match entry {
-                UndoEntry::Single(edit) => {
-                    self.apply_inverse_edit(&edit);
-                }
-                UndoEntry::Group(edits) => {
-                    for edit in edits.iter().rev() {
-                        self.apply_inverse_edit(edit);
-                    }
-                }
-            }
+    /// Reverts the last change, returning where the cursor was before it.
+    pub fn undo(&mut self) -> Option<UndoCursor> {
+        let step = self.undo_tree.undo()?;
+        self.apply_steps(vec![step])
+    }
+
+    /// Reapplies the last undone change, returning where the cursor was
+    /// after it.
+    pub fn redo(&mut self) -> Option<UndoCursor> {
+        let step = self.undo_tree.redo()?;
+        self.apply_steps(vec![step])
+    }
 
+    /// Moves the text to undo tree node `seq`, which may be on another branch,
+    /// returning the cursor recorded for that state. `None` when there is no
+    /// such node or the text is already there.
+    pub fn undo_goto(&mut self, seq: usize) -> Option<UndoCursor> {
+        let steps = self.undo_tree.goto(seq)?;
+        self.apply_steps(steps)
+    }
+
+    fn apply_steps(&mut self, steps: Vec<UndoStep>) -> Option<UndoCursor> {
+        let cursor = steps.last()?.cursor();
+
+        for step in steps {
+            match step {
+                UndoStep::Undo(entry, _) => self.apply_changes(&entry.inverse),
+                UndoStep::Redo(entry, _) => self.apply_changes(&entry.changes),
+            };
+        }
+        if let BufferKind::Hex(bytes) = &mut self.kind {
+            *bytes = hex::parse(&self.lines);
+        }
+
+        self.selection = None;
+
+        Some(cursor)
+    }
+
+    /// Writes the buffer as `encoding` from now on, failing when its text
+    /// has characters the encoding can't represent.
+    pub fn set_encoding(&mut self, encoding: Encoding) -> anyhow::Result<()> {
+        if encoding != self.encoding {
+            encoding.encode(&self.lines.to_string())?;
+            self.encoding = encoding;
             self.dirty = true;
-            self.undo_recording = true;
+            self.version += 1;
         }
+        Ok(())
     }
 
-    pub fn redo(&mut self) {
-        if let Some(entry) = self.undo_tree.redo() {
-            self.undo_recording = false;
+    /// Converts every line break to `ending`, as one change.
+    pub fn set_line_ending(&mut self, ending: LineEnding) -> anyhow::Result<()> {
+        if self.read_only {
+            return Err(anyhow!("Buffer is read only"));
+        }
 
-            match entry {
-                UndoEntry::Single(edit) => {
-                    self.apply_edit(&edit);
-                }
-                UndoEntry::Group(edits) => {
-                    for edit in edits {
-                        self.apply_edit(&edit);
-                    }
+        let mut changes = Vec::new();
+        let mut prev = None;
+        for (i, c) in self.lines.chars().enumerate() {
+            if c == '\n' {
+                match (ending, prev == Some('\r')) {
+                    (LineEnding::Lf, true) => changes.push((i - 1, i, None)),
+                    (LineEnding::Crlf, false) => changes.push((i, i, Some("\r".to_string()))),
+                    _ => {}
                 }
             }
-
-            self.dirty = true;
-            self.undo_recording = true;
+            prev = Some(c);
         }
+
+        self.apply(&Transaction::change(&self.lines, changes));
+        self.line_ending = ending;
+        self.mixed_line_endings = false;
+        Ok(())
     }
 
-    fn apply_edit(&mut self, edit: &Edit) {
-        match edit {
-            Edit::Insert { at, text } => {
-                self.lines.insert(*at, text);
-            }
-            Edit::Delete { at, text } => {
-                self.lines.remove(*at..(*at + text.len()));
-            }
+    /// The line ending as shown in the status line.
+    pub fn line_ending_name(&self) -> &'static str {
+        if self.mixed_line_endings {
+            "mixed"
+        } else {
+            self.line_ending.name()
         }
     }
 
-    fn apply_inverse_edit(&mut self, edit: &Edit) {
-        match edit {
-            Edit::Insert { at, text } => {
-                self.lines.remove(*at..(*at + text.len()));
-            }
-            Edit::Delete { at, text } => {
-                self.lines.insert(*at, text);
-            }
+    /// Replaces the whole text of a generated buffer, bypassing read-only and
+    /// undo history.
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = Rope::from_str(text);
+        self.selection = None;
+        self.changes = None;
+    }
+
+    /// Takes the text of a file loaded in the background.
+    pub fn set_loaded_text(&mut self, text: Rope) {
+        self.lines = text;
+        self.selection = None;
+        self.range = None;
+        self.changes = None;
+        self.undo_tree = UndoTree::new();
+    }
+
+    fn check_editable(&self) -> anyhow::Result<()> {
+        if self.read_only {
+            return Err(anyhow!("Buffer is read only"));
         }
+        if self.is_hex() {
+            return Err(anyhow!(
+                "Hex buffers are edited by typing over their hex digits"
+            ));
+        }
+        Ok(())
     }
 
-    pub fn save(&mut self) -> anyhow::Result<()> {
-        let path = self
-            .file_path
-            .as_ref()
-            .ok_or_else(|| anyhow!("No file path set for buffer '{}'", self.name))?;
+    pub fn is_hex(&self) -> bool {
+        matches!(self.kind, BufferKind::Hex(_))
+    }
 
-        let mut file = std::fs::File::create(path)
-            .map_err(|e| anyhow!("Failed to open file {:?}: {}", path, e))?;
+    /// The bytes of a hex buffer.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match &self.kind {
+            BufferKind::Hex(bytes) => Some(bytes),
+            BufferKind::Text => None,
+        }
+    }
 
-        file.write_all(self.lines.to_string().as_bytes())
-            .map_err(|e| anyhow!("Failed to write to {:?}: {}", path, e))?;
+    /// Shows `bytes` as a hex buffer, starting a new undo history.
+    pub fn set_hex(&mut self, bytes: Vec<u8>) {
+        self.set_loaded_text(Rope::from_str(&hex::dump(&bytes)));
+        self.kind = BufferKind::Hex(bytes);
+    }
 
-        self.mark_clean();
+    /// Shows `text` as a text buffer again, starting a new undo history.
+    pub fn set_text_kind(&mut self, text: &str) {
+        self.set_loaded_text(Rope::from_str(text));
+        self.kind = BufferKind::Text;
+        (self.line_ending, self.mixed_line_endings) = LineEnding::detect(text);
+    }
 
+    /// Overwrites the nibble of a hex buffer under `cursor` with `digit`
+    /// and moves to the next nibble.
+    pub fn hex_overwrite(&mut self, cursor: &mut Position, digit: u8) -> anyhow::Result<()> {
+        if self.read_only {
+            return Err(anyhow!("Buffer is read only"));
+        }
+        let BufferKind::Hex(bytes) = &mut self.kind else {
+            return Err(anyhow!("Not a hex buffer"));
+        };
+        let (i, nibble) =
+            hex::byte_at(*cursor, bytes.len()).ok_or_else(|| anyhow!("No bytes to overwrite"))?;
+
+        bytes[i] = match nibble {
+            0 => (bytes[i] & 0x0f) | digit << 4,
+            _ => (bytes[i] & 0xf0) | digit,
+        };
+        let row = i / hex::BYTES_PER_ROW;
+        let text = hex::row(bytes, row);
+        *cursor = hex::step_nibble(hex::position(i, nibble), bytes.len(), true);
+
+        // Replaced as an edit, so it can be undone.
+        let start = self.lines.line_to_char(row);
+        let end = start + self.line_len(row);
+        let transaction = Transaction::change(&self.lines, [(start, end, Some(text))]);
+        let changes = transaction.changes();
+        let inverse = changes.invert(&self.lines);
+        if self.apply_changes(changes) {
+            self.undo_tree.record(changes.clone(), inverse);
+        }
         Ok(())
     }
 
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
     pub fn is_modified(&self) -> bool {
         self.dirty
     }
 
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     pub fn mark_clean(&mut self) {
         self.dirty = false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// One edit at a char position, and whether it joins the previous edit's
+    /// undo group.
+    #[derive(Debug, Clone)]
+    enum Edit {
+        Insert {
+            at: usize,
+            text: String,
+            group: bool,
+        },
+        Delete {
+            at: usize,
+            len: usize,
+            group: bool,
+        },
+    }
+
+    fn mixed_text(max: usize) -> impl Strategy<Value = String> {
+        let chars = prop::sample::select(vec![
+            'a', 'Z', ' ', '\n', '日', '本', '語', '漢', 'é', '😀', '🎉', '👍',
+        ]);
+        prop::collection::vec(chars, 0..max).prop_map(|chars| chars.into_iter().collect())
+    }
+
+    fn edit() -> impl Strategy<Value = Edit> {
+        prop_oneof![
+            (any::<usize>(), mixed_text(6), any::<bool>())
+                .prop_map(|(at, text, group)| Edit::Insert { at, text, group }),
+            (any::<usize>(), 0..5usize, any::<bool>()).prop_map(|(at, len, group)| Edit::Delete {
+                at,
+                len,
+                group
+            }),
+        ]
+    }
+
+    fn position(text: &Rope, at: usize) -> Position {
+        let row = text.char_to_line(at);
+        Position {
+            row,
+            col: at - text.line_to_char(row),
+        }
+    }
+
+    fn undo_cursor(cursor: Position) -> UndoCursor {
+        UndoCursor {
+            cursor,
+            anchor: None,
+        }
+    }
+
+    /// A committed undo state: the text, and the cursor before and after
+    /// the group of edits that led to it.
+    struct State {
+        text: Rope,
+        before: UndoCursor,
+        after: UndoCursor,
+    }
+
+    /// Applies `edits` as the editor does, marking the cursor around each
+    /// one and committing a group whenever the next edit doesn't join it.
+    /// Returns every committed state.
+    fn edit_buffer(buf: &mut Buffer, edits: &[Edit]) -> Vec<State> {
+        let mut states = Vec::new();
+        // The cursors around the group still open, once it has a change.
+        let mut open: Option<(UndoCursor, UndoCursor)> = None;
+
+        for edit in edits {
+            let len = buf.lines.len_chars();
+            let (at, group) = match edit {
+                Edit::Insert { at, group, .. } | Edit::Delete { at, group, .. } => {
+                    (at % (len + 1), *group)
+                }
+            };
+            if !group && let Some((before, after)) = open.take() {
+                buf.undo_tree.commit_group();
+                states.push(State {
+                    text: buf.lines.clone(),
+                    before,
+                    after,
+                });
+            }
+
+            let old = buf.lines.clone();
+            let mut cursor = position(&buf.lines, at);
+            let before = undo_cursor(cursor);
+            buf.undo_tree.set_cursor(before);
+            match edit {
+                Edit::Insert { text, .. } => buf.insert_str(&mut cursor, text).unwrap(),
+                Edit::Delete { len: n, .. } => buf.remove(at..(at + n).min(len)),
+            }
+            let after = undo_cursor(cursor);
+            buf.undo_tree.set_cursor(after);
+
+            // An open group ends where the cursor last was, edited or not.
+            if buf.lines != old || open.is_some() {
+                open = Some((open.map_or(before, |(before, _)| before), after));
+            }
+        }
+
+        if let Some((before, after)) = open {
+            buf.undo_tree.commit_group();
+            states.push(State {
+                text: buf.lines.clone(),
+                before,
+                after,
+            });
+        }
+        states
+    }
+
+    proptest! {
+        #[test]
+        fn undo_and_redo_restore_text_and_cursor(
+            initial in mixed_text(20),
+            edits in prop::collection::vec(edit(), 1..30),
+        ) {
+            let mut buf = Buffer::from(BufferId(1), "test", &initial, None, false);
+            let initial = buf.lines.clone();
+            let states = edit_buffer(&mut buf, &edits);
+
+            for (i, state) in states.iter().enumerate().rev() {
+                prop_assert_eq!(buf.undo(), Some(state.before));
+                let previous = i.checked_sub(1).map_or(&initial, |i| &states[i].text);
+                prop_assert_eq!(&buf.lines, previous);
+            }
+            prop_assert_eq!(buf.undo(), None);
+
+            for state in &states {
+                prop_assert_eq!(buf.redo(), Some(state.after));
+                prop_assert_eq!(&buf.lines, &state.text);
+            }
+            prop_assert_eq!(buf.redo(), None);
+        }
+
+        #[test]
+        fn undo_goto_restores_other_branches(
+            initial in mixed_text(20),
+            edits in prop::collection::vec(edit(), 1..20),
+            branch in prop::collection::vec(edit(), 1..10),
+            undone in any::<usize>(),
+        ) {
+            let mut buf = Buffer::from(BufferId(1), "test", &initial, None, false);
+            let initial = buf.lines.clone();
+            let states = edit_buffer(&mut buf, &edits);
+            for _ in 0..=undone % (states.len() + 1) {
+                buf.undo();
+            }
+
+            let branch_states = edit_buffer(&mut buf, &branch);
+            buf.undo_goto(0);
+            prop_assert_eq!(&buf.lines, &initial);
+
+            // Nodes are numbered in creation order after the root, so each
+            // target is reached by redoing it last.
+            for (i, state) in states.iter().enumerate() {
+                prop_assert_eq!(buf.undo_goto(i + 1), Some(state.after));
+                prop_assert_eq!(&buf.lines, &state.text);
+            }
+            for (i, state) in branch_states.iter().enumerate() {
+                prop_assert_eq!(buf.undo_goto(states.len() + i + 1), Some(state.after));
+                prop_assert_eq!(&buf.lines, &state.text);
+            }
+        }
+    }
+
+    #[test]
+    fn hex_edits_are_undone_and_redone() {
+        let bytes: Vec<u8> = (0..40).collect();
+        let mut buf = Buffer::new(BufferId(1), "test", None, false);
+        buf.set_hex(bytes.clone());
+
+        let mut cursor = hex::position(20, 0);
+        buf.undo_tree.set_cursor(undo_cursor(cursor));
+        buf.hex_overwrite(&mut cursor, 0xa).unwrap();
+        buf.hex_overwrite(&mut cursor, 0xb).unwrap();
+        buf.undo_tree.commit_group();
+        let mut edited = bytes.clone();
+        edited[20] = 0xab;
+        assert_eq!(buf.bytes(), Some(&edited[..]));
+
+        assert!(buf.undo().is_some());
+        assert_eq!(buf.bytes(), Some(&bytes[..]));
+        assert_eq!(buf.lines, Rope::from_str(&hex::dump(&bytes)));
+        assert!(buf.redo().is_some());
+        assert_eq!(buf.bytes(), Some(&edited[..]));
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{Result, anyhow};
+
+pub struct Tokenized {
+    pub tokens: Vec<String>,
+    pub trailing_space: bool,
+    pub unterminated: Option<char>,
+}
+
+pub fn tokenize(line: &str) -> Result<Vec<String>> {
+    let tokenized = tokenize_partial(line);
+
+    if let Some(quote) = tokenized.unterminated {
+        return Err(anyhow!("unterminated {} quote", quote_name(quote)));
+    }
+
+    Ok(tokenized.tokens)
+}
+
+/// Splits a command line into words, honouring `"double"` and `'single'` quotes
+/// and backslash escapes. Never fails, so it can be used on half-typed input.
+pub fn tokenize_partial(line: &str) -> Tokenized {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(ch) = chars.next() {
+        match (quote, ch) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => current.push(ch),
+            (Some('"'), '"') => quote = None,
+            (_, '\\') => {
+                in_token = true;
+                match chars.next() {
+                    Some('n') => current.push('\n'),
+                    Some('t') => current.push('\t'),
+                    Some(escaped) => current.push(escaped),
+                    None => current.push('\\'),
+                }
+            }
+            (Some(_), _) => current.push(ch),
+            (None, '"' | '\'') => {
+                in_token = true;
+                quote = Some(ch);
+            }
+            (None, _) if ch.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            (None, _) => {
+                in_token = true;
+                current.push(ch);
+            }
+        }
+    }
+
+    let trailing_space = !in_token && quote.is_none();
+    if in_token {
+        tokens.push(current);
+    }
+
+    Tokenized {
+        tokens,
+        trailing_space,
+        unterminated: quote,
+    }
+}
+
+pub fn quote(token: &str) -> String {
+    let needs_quotes = token.is_empty()
+        || token
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'));
+
+    if !needs_quotes {
+        return token.to_string();
+    }
+
+    let mut quoted = String::with_capacity(token.len() + 2);
+    quoted.push('"');
+    for ch in token.chars() {
+        match ch {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(ch);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn quote_name(quote: char) -> &'static str {
+    if quote == '"' { "double" } else { "single" }
+}
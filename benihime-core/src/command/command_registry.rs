@@ -3,10 +3,62 @@ use std::collections::HashMap;
 
 use crate::application::HandleKeyError;
 
-use crate::command::{Cmd, CommandArg, CommandContext, CommandFn};
+use crate::command::{
+    Cmd, CommandArg, CommandCategory, CommandContext, CommandFn,
+    signature::{ArgSpec, Signature},
+};
+
+pub struct CommandEntry {
+    pub cmd: Cmd,
+    pub signature: Signature,
+    pub doc: String,
+    pub category: CommandCategory,
+}
+
+impl CommandEntry {
+    pub fn new(cmd: Cmd) -> Self {
+        let category = match cmd {
+            Cmd::Motion(_) | Cmd::Navigation(_) => CommandCategory::Movement,
+            Cmd::Operator(_) => CommandCategory::Editing,
+            Cmd::Plain(_) | Cmd::System(_) => CommandCategory::Misc,
+        };
+
+        Self {
+            cmd,
+            signature: Signature::default(),
+            doc: String::new(),
+            category,
+        }
+    }
+
+    pub fn arg(&mut self, spec: ArgSpec) -> &mut Self {
+        self.signature.args.push(spec);
+        self
+    }
+
+    pub fn doc(&mut self, doc: &str) -> &mut Self {
+        self.doc = doc.to_string();
+        self
+    }
+
+    pub fn category(&mut self, category: CommandCategory) -> &mut Self {
+        self.category = category;
+        self
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self.cmd {
+            Cmd::Plain(_) => "command",
+            Cmd::Motion(_) => "motion",
+            Cmd::Operator(_) => "operator",
+            Cmd::Navigation(_) => "navigation",
+            Cmd::System(_) => "system",
+        }
+    }
+}
 
 pub struct CommandRegistry {
-    pub commands: HashMap<String, Cmd>,
+    pub commands: HashMap<String, CommandEntry>,
 }
 
 impl CommandRegistry {
@@ -16,29 +68,41 @@ impl CommandRegistry {
         }
     }
 
-    pub fn register(&mut self, name: &str, cmd: CommandFn) {
-        self.commands.insert(name.to_string(), Cmd::Plain(cmd));
+    pub fn register(&mut self, name: &str, cmd: CommandFn) -> &mut CommandEntry {
+        self.insert(name, Cmd::Plain(cmd))
+    }
+
+    pub fn register_motion(&mut self, name: &str, cmd: CommandFn) -> &mut CommandEntry {
+        self.insert(name, Cmd::Motion(cmd))
+    }
+
+    pub fn register_operator(&mut self, name: &str, cmd: CommandFn) -> &mut CommandEntry {
+        self.insert(name, Cmd::Operator(cmd))
     }
 
-    pub fn register_motion(&mut self, name: &str, cmd: CommandFn) {
-        self.commands.insert(name.to_string(), Cmd::Motion(cmd));
+    pub fn register_navigation(&mut self, name: &str, cmd: CommandFn) -> &mut CommandEntry {
+        self.insert(name, Cmd::Navigation(cmd))
     }
 
-    pub fn register_operator(&mut self, name: &str, cmd: CommandFn) {
-        self.commands.insert(name.to_string(), Cmd::Operator(cmd));
+    pub fn register_system(&mut self, name: &str, cmd: CommandFn) -> &mut CommandEntry {
+        self.insert(name, Cmd::System(cmd))
     }
 
-    pub fn register_navigation(&mut self, name: &str, cmd: CommandFn) {
-        self.commands.insert(name.to_string(), Cmd::Navigation(cmd));
+    fn insert(&mut self, name: &str, cmd: Cmd) -> &mut CommandEntry {
+        self.commands
+            .insert(name.to_string(), CommandEntry::new(cmd));
+        self.commands
+            .get_mut(name)
+            .expect("command was just inserted")
     }
 
-    pub fn register_system(&mut self, name: &str, cmd: CommandFn) {
-        self.commands.insert(name.to_string(), Cmd::System(cmd));
+    pub fn signature(&self, name: &str) -> Option<&Signature> {
+        self.commands.get(name).map(|entry| &entry.signature)
     }
 
     pub fn execute(&self, name: &str, ctx: &mut CommandContext) -> Result<(), HandleKeyError> {
-        if let Some(cmd) = self.commands.get(name) {
-            match cmd {
+        if let Some(entry) = self.commands.get(name) {
+            match &entry.cmd {
                 Cmd::Plain(f) => {
                     ctx.editor.focused_buf_mut().range = None;
                     self.call_cmd(f, ctx, name)
@@ -2,796 +2,1781 @@ use std::{
     cmp::{Ordering, min},
     fs,
     path::PathBuf,
+    sync::Arc,
 };
 
 use anyhow::{Ok, anyhow};
 use ignore::Walk;
 
 use crate::{
-    application::HandleKeyError,
-    buffer::{Buffer, Position, Selection},
+    buffer::{Buffer, BufferId, Position, Selection},
     editor::{Editor, Mode},
-    mini_buffer::{MiniBuffer, MinibufferCallbackResult},
+    encoding::{Encoding, LineEnding},
+    help::{self, CommandItem},
+    hex,
+    input_handler::KeyMatching,
+    keymap::KeySequence,
+    mini_buffer::{MiniBuffer, MinibufferCallbackResult, Prompt, complete_path},
     movement::movement_commands,
     project::Project,
     tree::{Direction, Layout},
+    undo_view::{self, UndoMotion},
+    undotree::TimeOffset,
 };
 
 use super::{
+    command_line,
     command_registry::CommandRegistry,
-    {CommandArg, CommandContext},
+    signature::{ArgSpec, ArgType, CompletionKind},
+    {CommandArg, CommandCategory, CommandContext},
 };
 
 pub fn register_default_commands(registry: &mut CommandRegistry) {
-    registry.register("move-left", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        for _ in 0..ctx.count {
-            window.cursor.col = window.cursor.col.saturating_sub(1);
-        }
-        ctx.editor.update_scroll();
-        Ok(())
-    });
+    registry
+        .register("move-left", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            for _ in 0..ctx.count {
+                window.cursor.col = window.cursor.col.saturating_sub(1);
+            }
+            ctx.editor.update_scroll();
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor one character left.");
+
+    registry
+        .register("move-down", |ctx: &mut CommandContext| {
+            let screen_height = ctx.editor.screen_height;
+            let scroll_offset = ctx.editor.config.scroll_offset;
+            let (window, buf) = ctx.editor.focus();
+
+            for _ in 0..ctx.count {
+                window.cursor.row = min(window.cursor.row + 1, buf.line_count() - 1);
+                window.cursor.col = min(window.cursor.col, buf.line_len(window.cursor.row));
+            }
 
-    registry.register("move-down", |ctx: &mut CommandContext| {
-        let screen_height = ctx.editor.screen_height;
-        let scroll_offset = ctx.editor.config.scroll_offset;
-        let (window, buf) = ctx.editor.focus();
+            if window.cursor.row >= window.scroll_offset + screen_height - scroll_offset {
+                window.scroll_offset = window.cursor.row + scroll_offset + 1 - screen_height;
+            }
 
-        for _ in 0..ctx.count {
-            window.cursor.row = min(window.cursor.row + 1, buf.line_count() - 1);
-            window.cursor.col = min(window.cursor.col, buf.line_len(window.cursor.row));
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor one line down.");
+
+    registry
+        .register("move-up", |ctx: &mut CommandContext| {
+            let scroll_offset = ctx.editor.config.scroll_offset;
+            let (window, buf) = ctx.editor.focus();
+
+            for _ in 0..ctx.count {
+                window.cursor.row = window.cursor.row.saturating_sub(1);
+                window.cursor.col = min(window.cursor.col, buf.line_len(window.cursor.row));
+            }
 
-        if window.cursor.row >= window.scroll_offset + screen_height - scroll_offset {
-            window.scroll_offset = window.cursor.row + scroll_offset + 1 - screen_height;
-        }
+            if window.cursor.row < window.scroll_offset + scroll_offset {
+                window.scroll_offset = window.cursor.row.saturating_sub(scroll_offset);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor one line up.");
+
+    registry
+        .register("move-right", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            for _ in 0..ctx.count {
+                window.cursor.col = min(window.cursor.col + 1, buf.line_len(window.cursor.row));
+            }
+            ctx.editor.update_scroll();
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor one character right.");
+
+    registry
+        .register("set-mode", |ctx: &mut CommandContext| {
+            let mode: Mode = ctx.get_arg(0)?;
+            if mode == Mode::Command {
+                ctx.editor.command_buffer.clear();
+            }
 
-    registry.register("move-up", |ctx: &mut CommandContext| {
-        let scroll_offset = ctx.editor.config.scroll_offset;
-        let (window, buf) = ctx.editor.focus();
+            let (window, _) = ctx.editor.focus();
+            window.mode = mode;
 
-        for _ in 0..ctx.count {
+            Ok(())
+        })
+        .category(CommandCategory::Mode)
+        .doc("Switch the focused window to the given mode.")
+        .arg(ArgSpec::new("mode", ArgType::Mode));
+
+    registry
+        .register("beginning-of-line", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            window.cursor.col = 0;
+            ctx.editor.update_scroll();
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor to the start of the line.");
+
+    registry
+        .register("end-of-line", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            window.cursor.col = buf.line_len(window.cursor.row);
+            ctx.editor.update_scroll();
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor to the end of the line.");
+
+    registry
+        .register("first-non-blank", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            let line = buf.line(window.cursor.row);
+            let mut i = 0;
+            for (idx, char) in line.chars().enumerate() {
+                if !char.is_whitespace() {
+                    i = idx;
+                    break;
+                }
+                i = idx + 1;
+            }
+            window.cursor.col = i;
+            ctx.editor.update_scroll();
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor to the first non-blank character of the line.");
+
+    registry
+        .register("open-above", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            let char_idx = buf.get_line_to_char(window.cursor.row);
+            buf.insert_idx(char_idx, buf.line_ending.as_str())?;
             window.cursor.row = window.cursor.row.saturating_sub(1);
-            window.cursor.col = min(window.cursor.col, buf.line_len(window.cursor.row));
-        }
-
-        if window.cursor.row < window.scroll_offset + scroll_offset {
-            window.scroll_offset = window.cursor.row.saturating_sub(scroll_offset);
-        }
-
-        Ok(())
-    });
-
-    registry.register("move-right", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        for _ in 0..ctx.count {
-            window.cursor.col = min(window.cursor.col + 1, buf.line_len(window.cursor.row));
-        }
-        ctx.editor.update_scroll();
-        Ok(())
-    });
-
-    registry.register("set-mode", |ctx: &mut CommandContext| {
-        let mode: Mode = ctx.get_arg(0)?;
-        if mode == Mode::Command {
-            ctx.editor.command_buffer.clear();
-        }
-
-        let (window, buf) = ctx.editor.focus();
-        if window.mode != Mode::Insert && mode == Mode::Insert {
-            buf.undo_tree.commit_group();
-        }
-
-        window.mode = mode;
-
-        Ok(())
-    });
+            window.cursor.col = 0;
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Insert)]))?;
+            Ok(())
+        })
+        .category(CommandCategory::Editing)
+        .doc("Open a new line above the cursor and enter Insert mode.");
 
-    registry.register("beginning-of-line", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        window.cursor.col = 0;
-        ctx.editor.update_scroll();
-        Ok(())
-    });
+    registry
+        .register("open-below", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            let char_idx = buf.get_line_to_char(window.cursor.row + 1);
+            buf.insert_idx(char_idx, buf.line_ending.as_str())?;
+            window.cursor.row += 1;
+            window.cursor.col = 0;
 
-    registry.register("end-of-line", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        window.cursor.col = buf.line_len(window.cursor.row);
-        ctx.editor.update_scroll();
-        Ok(())
-    });
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Insert)]))?;
 
-    registry.register("first-non-blank", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        let line = buf.line(window.cursor.row);
-        let mut i = 0;
-        for (idx, char) in line.chars().enumerate() {
-            if !char.is_whitespace() {
-                i = idx;
-                break;
+            Ok(())
+        })
+        .category(CommandCategory::Editing)
+        .doc("Open a new line below the cursor and enter Insert mode.");
+
+    registry
+        .register_motion("word-forward", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_next_word_start(ctx);
             }
-            i = idx + 1;
-        }
-        window.cursor.col = i;
-        ctx.editor.update_scroll();
-        Ok(())
-    });
-
-    registry.register("open-above", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        let char_idx = buf.get_line_to_char(window.cursor.row);
-        buf.insert_idx(char_idx, "\n")?;
-        window.cursor.row = window.cursor.row.saturating_sub(1);
-        window.cursor.col = 0;
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Insert)]))?;
-        Ok(())
-    });
-
-    registry.register("open-below", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        let char_idx = buf.get_line_to_char(window.cursor.row + 1);
-        buf.insert_idx(char_idx, "\n")?;
-        window.cursor.row += 1;
-        window.cursor.col = 0;
-
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Insert)]))?;
-
-        Ok(())
-    });
-
-    registry.register_motion("word-forward", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_next_word_start(ctx);
-        }
-
-        Ok(())
-    });
-
-    registry.register_motion("word-backward", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_prev_word_start(ctx);
-        }
-
-        Ok(())
-    });
-
-    registry.register_motion("word-end", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_next_word_end(ctx);
-        }
 
-        Ok(())
-    });
-
-    registry.register_motion("word-backward-end", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_prev_word_end(ctx);
-        }
-
-        Ok(())
-    });
-
-    registry.register_motion("long-word-forward", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_next_long_word_start(ctx);
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the start of the next word.");
+
+    registry
+        .register_motion("word-backward", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_prev_word_start(ctx);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the start of the previous word.");
+
+    registry
+        .register_motion("word-end", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_next_word_end(ctx);
+            }
 
-    registry.register_motion("long-word-forward-end", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_next_long_word_end(ctx);
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the end of the next word.");
+
+    registry
+        .register_motion("word-backward-end", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_prev_word_end(ctx);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the end of the previous word.");
+
+    registry
+        .register_motion("long-word-forward", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_next_long_word_start(ctx);
+            }
 
-    registry.register_motion("long-word-backward", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_prev_long_word_start(ctx);
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the start of the next WORD.");
+
+    registry
+        .register_motion("long-word-forward-end", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_next_long_word_end(ctx);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the end of the next WORD.");
+
+    registry
+        .register_motion("long-word-backward", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_prev_long_word_start(ctx);
+            }
 
-    registry.register_motion("long-word-backward-end", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_prev_long_word_end(ctx);
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the start of the previous WORD.");
+
+    registry
+        .register_motion("long-word-backward-end", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_prev_long_word_end(ctx);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the end of the previous WORD.");
+
+    registry
+        .register_motion("sub-word-forward", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_next_sub_word_start(ctx);
+            }
 
-    registry.register_motion("sub-word-forward", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_next_sub_word_start(ctx);
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the start of the next sub-word.");
+
+    registry
+        .register_motion("sub-word-forward-end", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_next_sub_word_end(ctx);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the end of the next sub-word.");
+
+    registry
+        .register_motion("sub-word-backward", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_prev_sub_word_start(ctx);
+            }
 
-    registry.register_motion("sub-word-forward-end", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_next_sub_word_end(ctx);
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the start of the previous sub-word.");
+
+    registry
+        .register_motion("sub-word-backward-end", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                movement_commands::move_prev_sub_word_end(ctx);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Select to the end of the previous sub-word.");
+
+    registry
+        .register("execute-command-buffer", |ctx: &mut CommandContext| {
+            ctx.editor.exec(
+                "set-mode",
+                Some(vec![CommandArg::Mode(ctx.editor.base_mode())]),
+            )?;
+
+            let line = ctx.editor.command_buffer.clone();
+            ctx.editor.command_buffer.clear();
 
-    registry.register_motion("sub-word-backward", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_prev_sub_word_start(ctx);
-        }
+            ctx.editor.run_command_line(&line)?;
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Run the command typed on the `:` line.");
+
+    registry
+        .register("complete-command-line", |ctx: &mut CommandContext| {
+            let line = ctx.editor.command_buffer.clone();
+            let tokenized = command_line::tokenize_partial(&line);
+
+            let mut tokens = tokenized.tokens;
+            if tokenized.trailing_space || tokens.is_empty() {
+                tokens.push(String::new());
+            }
 
-        Ok(())
-    });
+            let index = tokens.len() - 1;
+            let prefix = tokens[index].clone();
+
+            let kind = if index == 0 {
+                CompletionKind::Command
+            } else {
+                ctx.editor
+                    .registry
+                    .signature(&tokens[0])
+                    .and_then(|signature| signature.spec_at(index - 1))
+                    .map_or(CompletionKind::None, |spec| spec.completion)
+            };
+
+            let candidates = ctx.editor.completions(kind, &prefix);
+            let Some(first) = candidates.first() else {
+                return Err(anyhow!("No completions for `{}`", prefix));
+            };
+
+            let common = candidates.iter().fold(first.clone(), |acc, candidate| {
+                acc.chars()
+                    .zip(candidate.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            });
 
-    registry.register_motion("sub-word-backward-end", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            movement_commands::move_prev_sub_word_end(ctx);
-        }
+            tokens[index] = common;
+            let mut completed = tokens
+                .iter()
+                .map(|t| command_line::quote(t))
+                .collect::<Vec<_>>()
+                .join(" ");
 
-        Ok(())
-    });
+            if candidates.len() == 1 {
+                if !completed.ends_with('/') {
+                    completed.push(' ');
+                }
+            } else {
+                let shown: Vec<&str> = candidates.iter().take(10).map(String::as_str).collect();
+                let more = if candidates.len() > shown.len() {
+                    " ..."
+                } else {
+                    ""
+                };
+                ctx.editor.message = Some(format!("{}{}", shown.join("  "), more));
+            }
 
-    registry.register("execute-command-buffer", |ctx: &mut CommandContext| {
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Normal)]))?;
+            ctx.editor.command_buffer = completed;
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Complete the command name or argument under the cursor on the `:` line.");
+
+    registry
+        .register("open-file", |ctx: &mut CommandContext| {
+            if let Some(path) = ctx.get_opt_arg::<PathBuf>(0)? {
+                let id = ctx.editor.open_file(&path)?;
+                ctx.editor.focus_buf(id);
+                return Ok(());
+            }
 
-        let line = ctx.editor.command_buffer.clone();
-        ctx.editor.command_buffer.clear();
+            let cwd = match ctx.editor.cwd() {
+                Some(v) => v,
+                None => return Err(anyhow!("Not in a Project")),
+            };
 
-        if line.is_empty() {
-            return Ok(());
-        }
+            let mut files: Vec<PathBuf> = fs::read_dir(cwd)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect();
 
-        let mut parts = line.split_whitespace();
-        let command_name = parts
-            .next()
-            .ok_or_else(|| HandleKeyError::ExecutionFailed(anyhow::anyhow!("Empty command")))?;
+            files.sort_by(|a, b| {
+                if a.is_dir() && !b.is_dir() {
+                    return Ordering::Less;
+                }
+                if !a.is_dir() && b.is_dir() {
+                    return Ordering::Greater;
+                }
+                Ordering::Equal
+            });
 
-        let args: Vec<CommandArg> = parts.map(CommandArg::parse_arg).collect();
+            let minibuffer: MiniBuffer<PathBuf> = MiniBuffer::new(
+                "Open File: ",
+                files,
+                |state: &mut Editor, path: &PathBuf| {
+                    if path.is_dir() {
+                        let mut new_items: Vec<PathBuf> = fs::read_dir(path)?
+                            .filter_map(|e| e.ok().map(|e| e.path()))
+                            .collect();
+
+                        new_items.sort_by(|a, b| {
+                            if a.is_dir() && !b.is_dir() {
+                                return Ordering::Less;
+                            }
+                            if !a.is_dir() && b.is_dir() {
+                                return Ordering::Greater;
+                            }
+                            Ordering::Equal
+                        });
+
+                        return Ok(Some(new_items));
+                    } else {
+                        let id = state.open_file(&path.clone())?;
+                        state.focus_buf(id);
+                        println!("Opened file: {}", path.display());
+                    }
+                    Ok(None)
+                },
+            );
 
-        ctx.editor.exec(command_name, Some(args))?;
-        Ok(())
-    });
+            ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
 
-    registry.register("open-file", |ctx: &mut CommandContext| {
-        let cwd = match ctx.editor.cwd() {
-            Some(v) => v,
-            None => return Err(anyhow!("Not in a Project")),
-        };
-
-        let mut files: Vec<PathBuf> = fs::read_dir(cwd)?
-            .filter_map(|e| e.ok().map(|e| e.path()))
-            .collect();
-
-        files.sort_by(|a, b| {
-            if a.is_dir() && !b.is_dir() {
-                return Ordering::Less;
-            }
-            if !a.is_dir() && b.is_dir() {
-                return Ordering::Greater;
-            }
-            Ordering::Equal
-        });
-
-        let minibuffer: MiniBuffer<PathBuf> = MiniBuffer::new(
-            "Open File: ",
-            files,
-            |state: &mut Editor, path: &PathBuf| {
-                if path.is_dir() {
-                    let mut new_items: Vec<PathBuf> = fs::read_dir(path)?
-                        .filter_map(|e| e.ok().map(|e| e.path()))
-                        .collect();
-
-                    new_items.sort_by(|a, b| {
-                        if a.is_dir() && !b.is_dir() {
-                            return Ordering::Less;
-                        }
-                        if !a.is_dir() && b.is_dir() {
-                            return Ordering::Greater;
-                        }
-                        Ordering::Equal
-                    });
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
 
-                    return Ok(Some(new_items));
-                } else {
-                    let id = state.open_file(&path.clone());
+            Ok(())
+        })
+        .category(CommandCategory::File)
+        .doc("Open a file, browsing the project directory when no path is given.")
+        .arg(ArgSpec::new("path", ArgType::Path).optional());
+
+    registry
+        .register("find-file", |ctx: &mut CommandContext| {
+            let cwd = match ctx.editor.cwd() {
+                Some(v) => v,
+                None => return Err(anyhow!("Not in a Project")),
+            };
+
+            let files: Vec<PathBuf> = Walk::new(cwd)
+                .filter_map(Result::ok)
+                .filter(|e| e.file_type().unwrap().is_file())
+                .map(|x| x.path().to_owned())
+                .collect();
+
+            let minibuffer: MiniBuffer<PathBuf> = MiniBuffer::new(
+                "Find File: ",
+                files,
+                |state: &mut Editor, path: &PathBuf| {
+                    let id = state.open_file(&path.clone())?;
                     state.focus_buf(id);
-                    println!("Opened file: {}", path.display());
-                }
-                Ok(None)
-            },
-        );
+                    Ok(None)
+                },
+            );
 
-        ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
+            ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
 
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::File)
+        .doc("Find a file anywhere in the current project.");
 
-    registry.register("find-file", |ctx: &mut CommandContext| {
-        let cwd = match ctx.editor.cwd() {
-            Some(v) => v,
-            None => return Err(anyhow!("Not in a Project")),
-        };
-
-        let files: Vec<PathBuf> = Walk::new(cwd)
-            .filter_map(Result::ok)
-            .filter(|e| e.file_type().unwrap().is_file())
-            .map(|x| x.path().to_owned())
-            .collect();
-
-        let minibuffer: MiniBuffer<PathBuf> = MiniBuffer::new(
-            "Find File: ",
-            files,
-            |state: &mut Editor, path: &PathBuf| {
-                let id = state.open_file(&path.clone());
-                state.focus_buf(id);
-                Ok(None)
-            },
-        );
+    registry
+        .register("echo", |ctx: &mut CommandContext| {
+            let text = ctx.get_args_from::<String>(0)?.join(" ");
+            let state = &mut ctx.editor;
+            state.message = Some(text);
+            state.error_message = None;
 
-        ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Show a message in the status line.")
+        .arg(ArgSpec::new("text", ArgType::Str).variadic());
+
+    registry
+        .register("error-message", |ctx: &mut CommandContext| {
+            let text = ctx.get_args_from::<String>(0)?.join(" ");
+            let state = &mut ctx.editor;
+            state.message = None;
+            state.error_message = Some(text);
 
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Show an error message in the status line.")
+        .arg(ArgSpec::new("text", ArgType::Str).variadic());
+
+    registry
+        .register("set-theme", |ctx: &mut CommandContext| {
+            let name: String = ctx.get_arg(0)?;
+            ctx.editor.load_theme(&name)
+        })
+        .category(CommandCategory::Misc)
+        .doc("Load a theme by name.")
+        .arg(ArgSpec::new("theme", ArgType::Str).complete(CompletionKind::Theme));
+
+    registry
+        .register("switch-to-buffer", |ctx: &mut CommandContext| {
+            let name: String = ctx.get_arg(0)?;
+            let id = ctx
+                .editor
+                .buffer_id_by_name(&name)
+                .ok_or_else(|| anyhow!("No buffer named '{}'", name))?;
+            ctx.editor.focus_buf(id);
+            Ok(())
+        })
+        .category(CommandCategory::Buffer)
+        .doc("Focus the buffer with the given name.")
+        .arg(ArgSpec::new("buffer", ArgType::Str).complete(CompletionKind::Buffer));
 
-        Ok(())
-    });
+    registry
+        .register_system("clear-error-message", |ctx: &mut CommandContext| {
+            let state = &mut ctx.editor;
+            state.error_message = None;
 
-    registry.register("echo", |ctx: &mut CommandContext| {
-        let text: String = ctx.get_arg(0)?;
-        let state = &mut ctx.editor;
-        state.message = Some(text);
-        state.error_message = None;
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Clear the error shown in the status line.");
+
+    registry
+        .register("next-buffer", |ctx: &mut CommandContext| {
+            let state = &mut ctx.editor;
+            let focused_id = state.focused_buf_id;
+            let ids = &state.project_manager.current().buffers;
+
+            if ids.is_empty() {
+                return Ok(());
+            }
 
-        Ok(())
-    });
+            let next = match ids.iter().position(|&id| id == focused_id) {
+                Some(i) => ids[(i + 1) % ids.len()],
+                None => ids[0],
+            };
 
-    registry.register("error-message", |ctx: &mut CommandContext| {
-        let text: String = ctx.get_arg(0)?;
-        let state = &mut ctx.editor;
-        state.message = None;
-        state.error_message = Some(text);
+            state.focus_buf(next);
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Buffer)
+        .doc("Focus the next buffer of the current project.");
+
+    registry
+        .register("previous-buffer", |ctx: &mut CommandContext| {
+            let state = &mut ctx.editor;
+            let focused_id = state.focused_buf_id;
+            let ids = &state.project_manager.current().buffers;
+
+            if ids.is_empty() {
+                return Ok(());
+            }
 
-    registry.register_system("clear-error-message", |ctx: &mut CommandContext| {
-        let state = &mut ctx.editor;
-        state.error_message = None;
+            let prev = match ids.iter().position(|&id| id == focused_id) {
+                Some(i) => ids[(i + ids.len() - 1) % ids.len()],
+                None => ids[0],
+            };
 
-        Ok(())
-    });
+            state.focus_buf(prev);
 
-    registry.register("next-buffer", |ctx: &mut CommandContext| {
-        let state = &mut ctx.editor;
-        let focused_id = state.focused_buf_id;
-        let ids = &state.project_manager.current().buffers;
+            Ok(())
+        })
+        .category(CommandCategory::Buffer)
+        .doc("Focus the previous buffer of the current project.");
 
-        if ids.is_empty() {
-            return Ok(());
-        }
+    registry
+        .register("split-window-horizontal", |ctx: &mut CommandContext| {
+            ctx.editor.split_current_buffer(Layout::Horizontal);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Split the focused window into a top and bottom half.");
 
-        let next = match ids.iter().position(|&id| id == focused_id) {
-            Some(i) => ids[(i + 1) % ids.len()],
-            None => ids[0],
-        };
+    registry
+        .register("split-window-vertical", |ctx: &mut CommandContext| {
+            ctx.editor.split_current_buffer(Layout::Vertical);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Split the focused window into a left and right half.");
 
-        state.focus_buf(next);
+    registry
+        .register("focus-left", |ctx: &mut CommandContext| {
+            ctx.editor.focus_move(Direction::Left);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Focus the window to the left.");
 
-        Ok(())
-    });
+    registry
+        .register("focus-down", |ctx: &mut CommandContext| {
+            ctx.editor.focus_move(Direction::Down);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Focus the window below.");
 
-    registry.register("previous-buffer", |ctx: &mut CommandContext| {
-        let state = &mut ctx.editor;
-        let focused_id = state.focused_buf_id;
-        let ids = &state.project_manager.current().buffers;
+    registry
+        .register("focus-up", |ctx: &mut CommandContext| {
+            ctx.editor.focus_move(Direction::Up);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Focus the window above.");
 
-        if ids.is_empty() {
-            return Ok(());
-        }
+    registry
+        .register("focus-right", |ctx: &mut CommandContext| {
+            ctx.editor.focus_move(Direction::Right);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Focus the window to the right.");
 
-        let prev = match ids.iter().position(|&id| id == focused_id) {
-            Some(i) => ids[(i + ids.len() - 1) % ids.len()],
-            None => ids[0],
-        };
+    registry
+        .register("swap-window-left", |ctx: &mut CommandContext| {
+            ctx.editor.tree_mut().swap(Direction::Left);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Swap the focused window with the one to the left.");
 
-        state.focus_buf(prev);
+    registry
+        .register("swap-window-down", |ctx: &mut CommandContext| {
+            ctx.editor.tree_mut().swap(Direction::Down);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Swap the focused window with the one below.");
 
-        Ok(())
-    });
+    registry
+        .register("swap-window-up", |ctx: &mut CommandContext| {
+            ctx.editor.tree_mut().swap(Direction::Up);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Swap the focused window with the one above.");
 
-    registry.register("split-window-horizontal", |ctx: &mut CommandContext| {
-        ctx.editor.split_current_buffer(Layout::Horizontal);
-        Ok(())
-    });
+    registry
+        .register("swap-window-right", |ctx: &mut CommandContext| {
+            ctx.editor.tree_mut().swap(Direction::Right);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Swap the focused window with the one to the right.");
 
-    registry.register("split-window-vertical", |ctx: &mut CommandContext| {
-        ctx.editor.split_current_buffer(Layout::Vertical);
-        Ok(())
-    });
+    registry
+        .register("close-window", |ctx: &mut CommandContext| {
+            ctx.editor.close_window()?;
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Close the focused window. Its buffer stays open.");
 
-    registry.register("focus-left", |ctx: &mut CommandContext| {
-        ctx.editor.focus_move(Direction::Left);
-        Ok(())
-    });
+    registry
+        .register("only-window", |ctx: &mut CommandContext| {
+            ctx.editor.only_window();
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Close every window except the focused one.");
+
+    registry
+        .register("resize-window", |ctx: &mut CommandContext| {
+            let axis: String = ctx.get_arg(0)?;
+            let percent: i64 = ctx.get_arg(1)?;
+            let layout = match axis.as_str() {
+                "width" => Layout::Vertical,
+                "height" => Layout::Horizontal,
+                _ => return Err(anyhow!("Invalid axis: {} (expected width or height)", axis)),
+            };
+
+            let percent = percent * ctx.count as i64;
+            if !ctx.editor.tree_mut().resize_window(layout, percent) {
+                return Err(anyhow!("No split to resize along {}", axis));
+            }
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Grow the focused window's width or height by a percentage of its split; negative shrinks.")
+        .arg(ArgSpec::new("axis", ArgType::Str))
+        .arg(ArgSpec::new("percent", ArgType::Int));
+
+    registry
+        .register("equalize-windows", |ctx: &mut CommandContext| {
+            ctx.editor.tree_mut().equalize();
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Give every window in each split the same size.");
 
-    registry.register("focus-down", |ctx: &mut CommandContext| {
-        ctx.editor.focus_move(Direction::Down);
-        Ok(())
-    });
+    registry
+        .register("rotate-layout", |ctx: &mut CommandContext| {
+            ctx.editor.tree_mut().rotate();
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Switch the focused window's split between side by side and stacked.");
+
+    registry
+        .register("zoom-window", |ctx: &mut CommandContext| {
+            let zoomed = ctx.editor.tree_mut().toggle_zoom();
+            ctx.editor.update_scroll();
+            ctx.editor
+                .set_status(if zoomed { "Zoomed" } else { "Unzoomed" }.to_string());
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Toggle showing only the focused window, keeping the layout to restore.");
+
+    registry
+        .register("split-window-buffer", |ctx: &mut CommandContext| {
+            let layout = match ctx.get_opt_arg::<String>(0)?.as_deref() {
+                None | Some("vertical") => Layout::Vertical,
+                Some("horizontal") => Layout::Horizontal,
+                Some(other) => {
+                    return Err(anyhow!(
+                        "Invalid split: {} (expected horizontal or vertical)",
+                        other
+                    ));
+                }
+            };
 
-    registry.register("focus-up", |ctx: &mut CommandContext| {
-        ctx.editor.focus_move(Direction::Up);
-        Ok(())
-    });
+            let buffers = ctx.editor.get_buffers_cloned();
+            let minibuffer: MiniBuffer<Buffer> = MiniBuffer::new(
+                "Split Buffer: ",
+                buffers,
+                move |state: &mut Editor, buffer: &Buffer| {
+                    state.split_with_buffer(buffer.id, layout);
+                    Ok(None)
+                },
+            );
 
-    registry.register("focus-right", |ctx: &mut CommandContext| {
-        ctx.editor.focus_move(Direction::Right);
-        Ok(())
-    });
+            ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
 
-    registry.register("center-cursor", |ctx: &mut CommandContext| {
-        let screen_height = ctx.editor.screen_height;
-        let (window, buf) = ctx.editor.focus();
-        window.center_cursor(screen_height, buf.line_count());
-        Ok(())
-    });
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
 
-    registry.register("minibuffer-next-completion", |ctx: &mut CommandContext| {
-        if let Some(mini) = ctx.editor.minibuffer_manager.current.as_mut() {
-            mini.move_focus(1);
-        }
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Split the focused window and pick the buffer to show in the new half.")
+        .arg(ArgSpec::new("split", ArgType::Str).optional());
+
+    registry
+        .register("center-cursor", |ctx: &mut CommandContext| {
+            let screen_height = ctx.editor.screen_height;
+            let (window, buf) = ctx.editor.focus();
+            window.center_cursor(screen_height, buf.line_count());
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Scroll so the cursor line is in the middle of the window.");
 
-    registry.register(
-        "minibuffer-previous-completion",
-        |ctx: &mut CommandContext| {
+    registry
+        .register("minibuffer-next-completion", |ctx: &mut CommandContext| {
             if let Some(mini) = ctx.editor.minibuffer_manager.current.as_mut() {
-                mini.move_focus(-1);
+                mini.move_focus(1);
             }
             Ok(())
-        },
-    );
-
-    registry.register("minibuffer-accept", |ctx: &mut CommandContext| {
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Normal)]))?;
-
-        if let Some(mut mini) = ctx.editor.minibuffer_manager.current.take() {
-            let result = mini.run_callback(ctx.editor)?;
-            match result {
-                MinibufferCallbackResult::NewItems => {
-                    ctx.editor.minibuffer_manager.current = Some(mini);
-                    //Early return to be in minibuffer mode
-                    return Ok(());
+        })
+        .category(CommandCategory::Minibuffer)
+        .doc("Select the next minibuffer candidate.");
+
+    registry
+        .register(
+            "minibuffer-previous-completion",
+            |ctx: &mut CommandContext| {
+                if let Some(mini) = ctx.editor.minibuffer_manager.current.as_mut() {
+                    mini.move_focus(-1);
+                }
+                Ok(())
+            },
+        )
+        .category(CommandCategory::Minibuffer)
+        .doc("Select the previous minibuffer candidate.");
+
+    registry
+        .register("minibuffer-accept", |ctx: &mut CommandContext| {
+            ctx.editor.exec(
+                "set-mode",
+                Some(vec![CommandArg::Mode(ctx.editor.base_mode())]),
+            )?;
+
+            if let Some(mut mini) = ctx.editor.minibuffer_manager.current.take() {
+                let result = mini.run_callback(ctx.editor)?;
+                match result {
+                    MinibufferCallbackResult::NewItems => {
+                        ctx.editor.minibuffer_manager.current = Some(mini);
+                        //Early return to be in minibuffer mode
+                        return Ok(());
+                    }
+                    MinibufferCallbackResult::Rejected(reason) => {
+                        ctx.editor.minibuffer_manager.current = Some(mini);
+                        ctx.editor
+                            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
+                        return Err(anyhow!(reason));
+                    }
+                    MinibufferCallbackResult::Executed => {}
                 }
-                MinibufferCallbackResult::Executed => {}
             }
-        }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Minibuffer)
+        .doc("Accept the selected minibuffer candidate or input.");
 
-    registry.register("close-minibuffer", |ctx: &mut CommandContext| {
-        ctx.editor.minibuffer_manager.current = None;
+    registry
+        .register("close-minibuffer", |ctx: &mut CommandContext| {
+            let mini = ctx.editor.minibuffer_manager.current.take();
 
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Normal)]))?;
+            ctx.editor.exec(
+                "set-mode",
+                Some(vec![CommandArg::Mode(ctx.editor.base_mode())]),
+            )?;
 
-        ctx.editor.needs_redraw = true;
+            if let Some(mut mini) = mini {
+                mini.cancel(ctx.editor)?;
+            }
 
-        Ok(())
-    });
+            ctx.editor.needs_redraw = true;
 
-    registry.register("find-command", |ctx: &mut CommandContext| {
-        let commands: Vec<String> = ctx.editor.registry.commands.keys().cloned().collect();
+            Ok(())
+        })
+        .category(CommandCategory::Minibuffer)
+        .doc("Cancel and close the minibuffer.");
+
+    registry
+        .register("find-command", |ctx: &mut CommandContext| {
+            let commands = CommandItem::all(ctx.editor);
+
+            let minibuffer: MiniBuffer<CommandItem> = MiniBuffer::new(
+                "Find Command: ",
+                commands,
+                |state: &mut Editor, command: &CommandItem| {
+                    let _ = state.exec(&command.name, None);
+                    Ok(None)
+                },
+            );
+
+            ctx.editor.activate_minibuffer(Box::new(minibuffer))
+        })
+        .category(CommandCategory::Help)
+        .doc("Pick a command from the command palette and run it.");
+
+    registry
+        .register("describe-command", |ctx: &mut CommandContext| {
+            if let Some(name) = ctx.get_opt_arg::<String>(0)? {
+                let text = help::describe_command(ctx.editor, &name)?;
+                ctx.editor.show_help(&text);
+                return Ok(());
+            }
 
-        let minibuffer: MiniBuffer<String> = MiniBuffer::new(
-            "Find Command: ",
-            commands,
-            |state: &mut Editor, command_name: &String| {
-                let _ = state.exec(command_name, None);
-                Ok(None)
-            },
+            let commands = CommandItem::all(ctx.editor);
+
+            let minibuffer: MiniBuffer<CommandItem> = MiniBuffer::new(
+                "Describe Command: ",
+                commands,
+                |state: &mut Editor, command: &CommandItem| {
+                    let text = help::describe_command(state, &command.name)?;
+                    state.show_help(&text);
+                    Ok(None)
+                },
+            );
+
+            ctx.editor.activate_minibuffer(Box::new(minibuffer))
+        })
+        .category(CommandCategory::Help)
+        .doc("Show the documentation and key bindings of a command.")
+        .arg(
+            ArgSpec::new("command", ArgType::Str)
+                .optional()
+                .complete(CompletionKind::Command),
         );
 
-        ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
-
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
-
-        Ok(())
-    });
-
-    registry.register("kill-this-buffer", |ctx: &mut CommandContext| {
-        let state = &mut ctx.editor;
-        state.kill_active_buffer()?;
-        Ok(())
-    });
-
-    registry.register("enter-visual-mode", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        if window.mode != Mode::Visual {
-            buf.selection = Some(Selection {
-                start: window.cursor,
-            });
-            window.mode = Mode::Visual;
-        }
-        Ok(())
-    });
-
-    registry.register("exit-visual-mode", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        buf.selection = None;
-        window.mode = Mode::Normal;
-        Ok(())
-    });
-
-    registry.register("visual_select_other_end", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        if window.mode == Mode::Visual
-            && let Some(selection) = &mut buf.selection
-        {
-            std::mem::swap(&mut selection.start, &mut window.cursor);
-        }
-        Ok(())
-    });
-
-    registry.register("delete-selection", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        if window.mode == Mode::Visual {
-            buf.delete_selection(&mut window.cursor);
-            window.mode = Mode::Normal;
-        }
-        Ok(())
-    });
+    registry
+        .register("describe-key", |ctx: &mut CommandContext| {
+            ctx.editor.describe_key = Some(KeySequence::default());
+            ctx.editor.message = Some("Describe key: ".to_string());
+            Ok(())
+        })
+        .category(CommandCategory::Help)
+        .doc("Show which command a key sequence runs.");
+
+    registry
+        .register("kill-this-buffer", |ctx: &mut CommandContext| {
+            let state = &mut ctx.editor;
+            state.kill_active_buffer()?;
+            Ok(())
+        })
+        .category(CommandCategory::Buffer)
+        .doc("Kill the focused buffer, asking first if it is modified.");
+
+    registry
+        .register("enter-visual-mode", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            if window.mode != Mode::Visual {
+                buf.selection = Some(Selection {
+                    start: window.cursor,
+                });
+                window.mode = Mode::Visual;
+            }
+            Ok(())
+        })
+        .category(CommandCategory::Selection)
+        .doc("Start a selection at the cursor.");
+
+    registry
+        .register("exit-visual-mode", |ctx: &mut CommandContext| {
+            let base_mode = ctx.editor.base_mode();
+            let (window, buf) = ctx.editor.focus();
+            buf.selection = None;
+            window.mode = base_mode;
+            Ok(())
+        })
+        .category(CommandCategory::Selection)
+        .doc("Drop the selection and return to the base mode.");
+
+    registry
+        .register("visual_select_other_end", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            if window.mode == Mode::Visual
+                && let Some(selection) = &mut buf.selection
+            {
+                std::mem::swap(&mut selection.start, &mut window.cursor);
+            }
+            Ok(())
+        })
+        .category(CommandCategory::Selection)
+        .doc("Move the cursor to the other end of the selection.");
+
+    registry
+        .register("delete-selection", |ctx: &mut CommandContext| {
+            let base_mode = ctx.editor.base_mode();
+            let (window, buf) = ctx.editor.focus();
+            if window.mode == Mode::Visual {
+                buf.delete_selection(&mut window.cursor);
+                window.mode = base_mode;
+            }
+            Ok(())
+        })
+        .category(CommandCategory::Selection)
+        .doc("Delete the selected text.");
+
+    registry
+        .register("change-selection", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            if window.mode == Mode::Visual {
+                buf.delete_selection(&mut window.cursor);
+                window.mode = Mode::Insert;
+            }
+            Ok(())
+        })
+        .category(CommandCategory::Selection)
+        .doc("Delete the selected text and enter Insert mode.");
 
-    registry.register("change-selection", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        if window.mode == Mode::Visual {
-            buf.delete_selection(&mut window.cursor);
-            window.mode = Mode::Insert;
-        }
-        Ok(())
-    });
+    registry
+        .register("find-buffer", |ctx: &mut CommandContext| {
+            let buffers = ctx.editor.get_buffers_cloned();
 
-    registry.register("find-buffer", |ctx: &mut CommandContext| {
-        let buffers = ctx.editor.get_buffers_cloned();
+            let minibuffer: MiniBuffer<Buffer> = MiniBuffer::new(
+                "Find Buffer: ",
+                buffers,
+                |state: &mut Editor, command_name: &Buffer| {
+                    state.focus_buf(command_name.id);
+                    Ok(None)
+                },
+            );
 
-        let minibuffer: MiniBuffer<Buffer> = MiniBuffer::new(
-            "Find Buffer: ",
-            buffers,
-            |state: &mut Editor, command_name: &Buffer| {
-                state.focus_buf(command_name.id);
-                Ok(None)
-            },
-        );
+            ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
 
-        ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
 
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
+            Ok(())
+        })
+        .category(CommandCategory::Buffer)
+        .doc("Pick an open buffer and focus it.");
+
+    registry
+        .register_operator("delete-range", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            let row = window.cursor.row;
+            let line_start = buf.get_line_to_char(row);
+            let line_end = buf.get_line_to_char(row + 1);
+            let line_len = line_end - line_start;
+
+            if let Some(range) = buf.range.take() {
+                let start = range.anchor.min(range.head);
+                let end = range.anchor.max(range.head);
+                let del_start = line_start + start.min(line_len);
+                let del_end = line_start + end.min(line_len);
+                buf.remove_line(del_start, del_end);
+                window.cursor.col = start;
+            } else if line_len > 0 {
+                let col = window.cursor.col.min(line_len.saturating_sub(1));
+                let del_start = line_start + col;
+                let del_end = del_start + 1;
+                buf.remove_line(del_start, del_end);
+            }
 
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Editing)
+        .doc("Delete the current range, or the character under the cursor.");
+
+    registry
+        .register_operator("change-range", |ctx: &mut CommandContext| {
+            let (window, buf) = ctx.editor.focus();
+            let row = window.cursor.row;
+            let line_start = buf.get_line_to_char(row);
+            let line_end = buf.get_line_to_char(row + 1);
+            let line_len = line_end - line_start;
+
+            if let Some(range) = buf.range.take() {
+                let start = range.anchor.min(range.head);
+                let end = range.anchor.max(range.head);
+                let del_start = line_start + start.min(line_len);
+                let del_end = line_start + end.min(line_len);
+                buf.remove_line(del_start, del_end);
+                window.cursor.col = start;
+                window.mode = Mode::Insert;
+            }
 
-    registry.register_operator("delete-range", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        let row = window.cursor.row;
-        let line_start = buf.get_line_to_char(row);
-        let line_end = buf.get_line_to_char(row + 1);
-        let line_len = line_end - line_start;
-
-        if let Some(range) = buf.range.take() {
-            let start = range.anchor.min(range.head);
-            let end = range.anchor.max(range.head);
-            let del_start = line_start + start.min(line_len);
-            let del_end = line_start + end.min(line_len);
-            buf.remove_line(del_start, del_end);
-            window.cursor.col = start;
-        } else if line_len > 0 {
-            let col = window.cursor.col.min(line_len.saturating_sub(1));
-            let del_start = line_start + col;
-            let del_end = del_start + 1;
-            buf.remove_line(del_start, del_end);
-        }
+            Ok(())
+        })
+        .category(CommandCategory::Editing)
+        .doc("Delete the current range and enter Insert mode.");
+
+    registry
+        .register("apply-operator", |ctx: &mut CommandContext| {
+            let operator: String = ctx.get_arg(0)?;
+            let motion: String = ctx.get_arg(1)?;
+
+            ctx.editor.prefix_arg = Some(ctx.count);
+            ctx.editor.exec(&motion, None)?;
+            ctx.editor.exec(&operator, None)?;
+            Ok(())
+        })
+        .category(CommandCategory::Editing)
+        .doc("Run a motion, then apply an operator such as `delete-range` to the text it covered.")
+        .arg(ArgSpec::new("operator", ArgType::Str).complete(CompletionKind::Command))
+        .arg(ArgSpec::new("motion", ArgType::Str).complete(CompletionKind::Command));
+
+    registry
+        .register("undo", |ctx: &mut CommandContext| {
+            ctx.editor.undo(ctx.count);
+            Ok(())
+        })
+        .category(CommandCategory::History)
+        .doc("Undo the last change.");
 
-        Ok(())
-    });
+    registry
+        .register("redo", |ctx: &mut CommandContext| {
+            ctx.editor.redo(ctx.count);
+            Ok(())
+        })
+        .category(CommandCategory::History)
+        .doc("Redo the last undone change.");
+
+    registry
+        .register("undo-tree-show", |ctx| undo_view::open(ctx.editor))
+        .category(CommandCategory::History)
+        .doc("Browse the undo tree of the focused buffer, previewing each state in place.");
+
+    registry
+        .register("undo-tree-older", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                undo_view::select(ctx.editor, UndoMotion::Older)?;
+            }
+            Ok(())
+        })
+        .category(CommandCategory::History)
+        .doc("Select the parent state in the undo tree view.");
+
+    registry
+        .register("undo-tree-newer", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                undo_view::select(ctx.editor, UndoMotion::Newer)?;
+            }
+            Ok(())
+        })
+        .category(CommandCategory::History)
+        .doc("Select the state redo would go to in the undo tree view.");
+
+    registry
+        .register("undo-tree-previous-branch", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                undo_view::select(ctx.editor, UndoMotion::PreviousBranch)?;
+            }
+            Ok(())
+        })
+        .category(CommandCategory::History)
+        .doc("Select the previous sibling branch in the undo tree view.");
+
+    registry
+        .register("undo-tree-next-branch", |ctx: &mut CommandContext| {
+            for _ in 0..ctx.count {
+                undo_view::select(ctx.editor, UndoMotion::NextBranch)?;
+            }
+            Ok(())
+        })
+        .category(CommandCategory::History)
+        .doc("Select the next sibling branch in the undo tree view.");
+
+    registry
+        .register("undo-tree-accept", |ctx| undo_view::accept(ctx.editor))
+        .category(CommandCategory::History)
+        .doc("Keep the state selected in the undo tree view and close it.");
+
+    registry
+        .register("undo-tree-cancel", |ctx| undo_view::cancel(ctx.editor))
+        .category(CommandCategory::History)
+        .doc("Close the undo tree view, returning to the state it was opened on.");
+
+    registry
+        .register("undo-earlier", |ctx: &mut CommandContext| {
+            let offset = match ctx.get_opt_arg::<String>(0)? {
+                Some(amount) => amount.parse::<TimeOffset>()?,
+                None => TimeOffset::Steps(ctx.count as isize),
+            };
+            undo_view::travel(ctx.editor, offset.reversed())
+        })
+        .category(CommandCategory::History)
+        .doc("Go back through the buffer's states in time, across branches, by a count or a duration like 10m.")
+        .arg(ArgSpec::new("amount", ArgType::Str).optional());
+
+    registry
+        .register("undo-later", |ctx: &mut CommandContext| {
+            let offset = match ctx.get_opt_arg::<String>(0)? {
+                Some(amount) => amount.parse::<TimeOffset>()?,
+                None => TimeOffset::Steps(ctx.count as isize),
+            };
+            undo_view::travel(ctx.editor, offset)
+        })
+        .category(CommandCategory::History)
+        .doc("Go forward through the buffer's states in time, across branches, by a count or a duration like 10m.")
+        .arg(ArgSpec::new("amount", ArgType::Str).optional());
+
+    registry
+        .register("scroll-half-down", |ctx| {
+            let screen_height = ctx.editor.screen_height;
+            let scroll_offset = ctx.editor.config.scroll_offset;
+            let (window, buf) = ctx.editor.focus();
+            let h = screen_height / 2;
+            window.scroll_down(buf.line_count(), h, screen_height, scroll_offset);
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Scroll down half a screen.");
+
+    registry
+        .register("scroll-half-up", |ctx| {
+            let screen_height = ctx.editor.screen_height;
+            let scroll_offset = ctx.editor.config.scroll_offset;
+            let window = ctx.editor.focus().0;
+            let h = screen_height / 2;
+            window.scroll_up(h, scroll_offset);
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Scroll up half a screen.");
+
+    registry
+        .register("scroll-full-down", |ctx| {
+            let screen_height = ctx.editor.screen_height;
+            let scroll_offset = ctx.editor.config.scroll_offset;
+            let (window, buf) = ctx.editor.focus();
+            window.scroll_down(
+                buf.line_count(),
+                screen_height,
+                screen_height,
+                scroll_offset,
+            );
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Scroll down a full screen.");
+
+    registry
+        .register("scroll-full-up", |ctx| {
+            let screen_height = ctx.editor.screen_height;
+            let scroll_offset = ctx.editor.config.scroll_offset;
+            let window = ctx.editor.focus().0;
+            window.scroll_up(screen_height, scroll_offset);
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Scroll up a full screen.");
+    registry
+        .register("goto-first-line", |ctx| {
+            let (window, buf) = ctx.editor.focus();
+            window.cursor = Position::start();
+            buf.selection = None;
+            window.scroll_offset = 0;
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor to the first line.");
 
-    registry.register_operator("change-range", |ctx: &mut CommandContext| {
-        let (window, buf) = ctx.editor.focus();
-        let row = window.cursor.row;
-        let line_start = buf.get_line_to_char(row);
-        let line_end = buf.get_line_to_char(row + 1);
-        let line_len = line_end - line_start;
-
-        if let Some(range) = buf.range.take() {
-            let start = range.anchor.min(range.head);
-            let end = range.anchor.max(range.head);
-            let del_start = line_start + start.min(line_len);
-            let del_end = line_start + end.min(line_len);
-            buf.remove_line(del_start, del_end);
-            window.cursor.col = start;
-            window.mode = Mode::Insert;
-        }
+    registry
+        .register("goto-last-line", |ctx| {
+            let (window, buf) = ctx.editor.focus();
+            let last_row = buf.line_count().saturating_sub(1);
 
-        Ok(())
-    });
+            let line_len = buf.line_len(last_row);
 
-    registry.register("undo", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            ctx.editor.focused_buf_mut().undo();
-        }
+            window.cursor.col = window.cursor.col.min(line_len);
+            buf.selection = None;
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor to the last line.");
+
+    registry
+        .register("goto-position", |ctx| {
+            let (row, col): (usize, usize) = ctx.get_arg(0)?;
+            let (window, buf) = ctx.editor.focus();
+            let last_row = buf.line_count().saturating_sub(1);
+
+            window.cursor.row = row.saturating_sub(1).min(last_row);
+            window.cursor.col = col.saturating_sub(1).min(buf.line_len(window.cursor.row));
+            buf.selection = None;
+            ctx.editor.update_scroll();
+            Ok(())
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor to the given 1-based `row,col`.")
+        .arg(ArgSpec::new("position", ArgType::Position));
+
+    registry
+        .register("save-current-buffer", |ctx| {
+            let force = ctx.get_opt_arg::<bool>(0)?.unwrap_or(false);
+            let buf = ctx.editor.focused_buf_mut();
+            let id = buf.id;
+
+            if buf.file_path.is_none() {
+                return save_as_prompt(ctx.editor, id, force);
+            }
+            ctx.editor.save_buffer(id, force)
+        })
+        .category(CommandCategory::File)
+        .doc("Save the focused buffer, asking for a path if it has none.")
+        .arg(ArgSpec::new("force", ArgType::Bool).optional());
+
+    registry
+        .register("save-as", |ctx| {
+            let force = ctx.get_opt_arg::<bool>(1)?.unwrap_or(false);
+            let id = ctx.editor.focused_buf_mut().id;
+
+            match ctx.get_opt_arg::<PathBuf>(0)? {
+                Some(path) => ctx.editor.save_buffer_as(id, path, force),
+                None => save_as_prompt(ctx.editor, id, force),
+            }
+        })
+        .category(CommandCategory::File)
+        .doc("Save the focused buffer to another file and keep editing that one.")
+        .arg(ArgSpec::new("path", ArgType::Path).optional())
+        .arg(ArgSpec::new("force", ArgType::Bool).optional());
+
+    registry
+        .register("save-all", |ctx| {
+            let count = ctx.editor.save_all()?;
+            ctx.editor.message = Some(format!("Saving {} buffer(s)", count));
+            Ok(())
+        })
+        .category(CommandCategory::File)
+        .doc("Save every modified buffer that has a file.");
 
-        Ok(())
-    });
+    registry
+        .register("show-messages", |ctx| {
+            ctx.editor.show_messages();
+            Ok(())
+        })
+        .category(CommandCategory::Help)
+        .doc("Show the status messages and errors of this session.");
+
+    registry
+        .register("reload-buffer", |ctx| {
+            let id = ctx.editor.focused_buf_mut().id;
+            ctx.editor.reload_buffer_from_disk(id)
+        })
+        .category(CommandCategory::File)
+        .doc("Replace the focused buffer with its file, as one undoable edit.");
+
+    registry
+        .register("diff-with-file", |ctx| {
+            let id = ctx.editor.focused_buf_mut().id;
+            ctx.editor.show_disk_diff(id)
+        })
+        .category(CommandCategory::File)
+        .doc("Show how the focused buffer differs from its file on disk.");
+
+    registry
+        .register("recover-swap", |ctx| {
+            let id = ctx.editor.focused_buf_mut().id;
+            ctx.editor.ask_about_swap(id)
+        })
+        .category(CommandCategory::File)
+        .doc("Recover, diff or delete the unsaved changes in the focused buffer's swap file.");
+
+    registry
+        .register("toggle-hex-view", |ctx| {
+            let id = ctx.editor.focused_buf_mut().id;
+            ctx.editor.toggle_hex_view(id)?;
+            ctx.editor.update_scroll();
+            Ok(())
+        })
+        .category(CommandCategory::File)
+        .doc("Switch the focused buffer between its text and a hex view of its bytes.");
+
+    registry
+        .register("hex-next-byte", |ctx| hex_move(ctx, 1))
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor of a hex buffer to the next byte.");
+
+    registry
+        .register("hex-previous-byte", |ctx| hex_move(ctx, -1))
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor of a hex buffer to the previous byte.");
+
+    registry
+        .register("hex-next-row", |ctx| {
+            hex_move(ctx, hex::BYTES_PER_ROW as isize)
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor of a hex buffer sixteen bytes on.");
+
+    registry
+        .register("hex-previous-row", |ctx| {
+            hex_move(ctx, -(hex::BYTES_PER_ROW as isize))
+        })
+        .category(CommandCategory::Movement)
+        .doc("Move the cursor of a hex buffer sixteen bytes back.");
+
+    registry
+        .register("set-encoding", |ctx: &mut CommandContext| {
+            let encoding: Encoding = ctx.get_arg::<String>(0)?.parse()?;
+            ctx.editor.focused_buf_mut().set_encoding(encoding)?;
+            ctx.editor.message = Some(format!("Encoding set to {}; save to convert", encoding));
+            Ok(())
+        })
+        .category(CommandCategory::File)
+        .doc("Write the focused buffer in another encoding from its next save.")
+        .arg(ArgSpec::new("encoding", ArgType::Str).complete(CompletionKind::Encoding));
+
+    registry
+        .register("set-line-ending", |ctx: &mut CommandContext| {
+            let line_ending: LineEnding = ctx.get_arg::<String>(0)?.parse()?;
+            ctx.editor.focused_buf_mut().set_line_ending(line_ending)?;
+            ctx.editor.message = Some(format!("Line endings converted to {}", line_ending));
+            Ok(())
+        })
+        .category(CommandCategory::File)
+        .doc("Convert every line break of the focused buffer to LF or CRLF.")
+        .arg(ArgSpec::new("line-ending", ArgType::Str).complete(CompletionKind::LineEnding));
 
-    registry.register("redo", |ctx: &mut CommandContext| {
-        for _ in 0..ctx.count {
-            ctx.editor.focused_buf_mut().redo();
-        }
+    registry
+        .register("open-project", |ctx: &mut CommandContext| {
+            let projects = ctx.editor.project_manager.get_projects_cloned();
 
-        Ok(())
-    });
+            let minibuffer: MiniBuffer<Project> = MiniBuffer::new(
+                "Open Project: ",
+                projects,
+                |state: &mut Editor, project: &Project| {
+                    state.switch_project(project.id);
+                    Ok(None)
+                },
+            );
 
-    registry.register("undo-tree-show", |ctx| {
-        let tree_text = {
-            let buf = ctx.editor.focus_ref().1;
-            buf.undo_tree.render()
-        };
+            ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
 
-        let id = ctx
-            .editor
-            .new_read_only_buffer_from_text("*undo-tree*", &tree_text);
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
 
-        ctx.editor.focus_buf(id);
-        Ok(())
-    });
+            Ok(())
+        })
+        .category(CommandCategory::Project)
+        .doc("Pick a project and switch to it.");
+
+    registry
+        .register("kill-current-project", |ctx: &mut CommandContext| {
+            ctx.editor.close_current_project()
+        })
+        .category(CommandCategory::Project)
+        .doc("Close the current project and its buffers.");
+
+    registry
+        .register("quit", |ctx: &mut CommandContext| ctx.editor.quit())
+        .category(CommandCategory::Misc)
+        .doc("Quit Benihime, asking to save modified buffers.");
+
+    registry
+        .register("quit-without-saving", |ctx: &mut CommandContext| {
+            ctx.editor.exit();
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Quit Benihime, discarding unsaved changes.");
+
+    registry
+        .register("save-session", |ctx: &mut CommandContext| {
+            let name: Option<String> = ctx.get_opt_arg(0)?;
+            ctx.editor.save_session(name.as_deref())?;
+            let name = ctx.editor.session_name.clone();
+            ctx.editor.message = Some(format!("Saved session '{}'", name));
+            Ok(())
+        })
+        .category(CommandCategory::Project)
+        .doc("Save open projects, buffers and window layouts as a named session.")
+        .arg(
+            ArgSpec::new("name", ArgType::Str)
+                .optional()
+                .complete(CompletionKind::Session),
+        );
 
-    registry.register("scroll-half-down", |ctx| {
-        let screen_height = ctx.editor.screen_height;
-        let scroll_offset = ctx.editor.config.scroll_offset;
-        let (window, buf) = ctx.editor.focus();
-        let h = screen_height / 2;
-        window.scroll_down(buf.line_count(), h, screen_height, scroll_offset);
-        Ok(())
-    });
+    registry
+        .register("load-session", |ctx: &mut CommandContext| {
+            if let Some(name) = ctx.get_opt_arg::<String>(0)? {
+                return ctx.editor.load_session(&name);
+            }
 
-    registry.register("scroll-half-up", |ctx| {
-        let screen_height = ctx.editor.screen_height;
-        let scroll_offset = ctx.editor.config.scroll_offset;
-        let window = ctx.editor.focus().0;
-        let h = screen_height / 2;
-        window.scroll_up(h, scroll_offset);
-        Ok(())
-    });
+            let sessions = ctx.editor.session_names();
+            if sessions.is_empty() {
+                return Err(anyhow!("No saved sessions"));
+            }
 
-    registry.register("scroll-full-down", |ctx| {
-        let screen_height = ctx.editor.screen_height;
-        let scroll_offset = ctx.editor.config.scroll_offset;
-        let (window, buf) = ctx.editor.focus();
-        window.scroll_down(
-            buf.line_count(),
-            screen_height,
-            screen_height,
-            scroll_offset,
+            let minibuffer: MiniBuffer<String> = MiniBuffer::new(
+                "Load Session: ",
+                sessions,
+                |state: &mut Editor, name: &String| {
+                    state.load_session(name)?;
+                    Ok(None)
+                },
+            );
+
+            ctx.editor.activate_minibuffer(Box::new(minibuffer))
+        })
+        .category(CommandCategory::Project)
+        .doc("Restore a saved session, picking one when no name is given.")
+        .arg(
+            ArgSpec::new("name", ArgType::Str)
+                .optional()
+                .complete(CompletionKind::Session),
         );
-        Ok(())
-    });
 
-    registry.register("scroll-full-up", |ctx| {
-        let screen_height = ctx.editor.screen_height;
-        let scroll_offset = ctx.editor.config.scroll_offset;
-        let window = ctx.editor.focus().0;
-        window.scroll_up(screen_height, scroll_offset);
-        Ok(())
-    });
-    registry.register("goto-first-line", |ctx| {
-        let (window, buf) = ctx.editor.focus();
-        window.cursor = Position::start();
-        buf.selection = None;
-        window.scroll_offset = 0;
-        Ok(())
-    });
+    registry
+        .register("next-project", |ctx: &mut CommandContext| {
+            ctx.editor.switch_to_next_project()
+        })
+        .category(CommandCategory::Project)
+        .doc("Switch to the next project.");
 
-    registry.register("goto-last-line", |ctx| {
-        let (window, buf) = ctx.editor.focus();
-        let last_row = buf.line_count().saturating_sub(1);
+    registry
+        .register("previous-project", |ctx: &mut CommandContext| {
+            ctx.editor.switch_to_previous_project()
+        })
+        .category(CommandCategory::Project)
+        .doc("Switch to the previous project.");
 
-        let line_len = buf.line_len(last_row);
+    registry
+        .register("keymap", |ctx| {
+            let buf_id = ctx.editor.focus_ref().1.id;
+            let tree_text = ctx.editor.keymap.render(buf_id, &ctx.editor.registry);
 
-        window.cursor.col = window.cursor.col.min(line_len);
-        buf.selection = None;
-        Ok(())
-    });
+            let id = ctx.editor.new_special_buffer("*keymap*", &tree_text);
 
-    registry.register("save-current-buffer", |ctx| {
-        let buf = ctx.editor.focused_buf_mut();
-        let _ = buf.save();
-        Ok(())
-    });
-
-    registry.register("open-project", |ctx: &mut CommandContext| {
-        let projects = ctx.editor.project_manager.get_projects_cloned();
-
-        let minibuffer: MiniBuffer<Project> = MiniBuffer::new(
-            "Open Project: ",
-            projects,
-            |state: &mut Editor, project: &Project| {
-                state.switch_project(project.id);
-                Ok(None)
-            },
+            ctx.editor.focus_buf(id);
+            Ok(())
+        })
+        .category(CommandCategory::Help)
+        .doc(
+            "Show the key bindings in effect for the focused buffer and the layer each comes from.",
         );
 
-        ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
+    registry
+        .register("bind-key", |ctx: &mut CommandContext| {
+            let mode: Mode = ctx.get_arg(0)?;
+            let keys: String = ctx.get_arg(1)?;
+            let command: Vec<String> = ctx.get_args_from(2)?;
+            let command_line = command
+                .iter()
+                .map(|token| command_line::quote(token))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            ctx.editor.keymap.bind_str(&[mode], &keys, &command_line)?;
+            ctx.editor
+                .set_status(format!("{} bound to {}", keys, command_line));
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Bind keys such as `C-c t` or `<C-w>v` to a command line in the global keymap.")
+        .arg(ArgSpec::new("mode", ArgType::Mode))
+        .arg(ArgSpec::new("keys", ArgType::Str))
+        .arg(ArgSpec::new("command", ArgType::Str).complete(CompletionKind::Command))
+        .arg(ArgSpec::new("args", ArgType::Str).optional().variadic());
+
+    registry
+        .register("bind-key-local", |ctx: &mut CommandContext| {
+            let mode: Mode = ctx.get_arg(0)?;
+            let keys: String = ctx.get_arg(1)?;
+            let command: Vec<String> = ctx.get_args_from(2)?;
+            let command_line = command
+                .iter()
+                .map(|token| command_line::quote(token))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let buf_id = ctx.editor.focus_ref().1.id;
+            ctx.editor
+                .keymap
+                .buffer_local_mut(buf_id)
+                .bind_str(&[mode], &keys, &command_line)?;
+            ctx.editor
+                .set_status(format!("{} bound to {} in this buffer", keys, command_line));
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Bind keys to a command line in the focused buffer's local keymap.")
+        .arg(ArgSpec::new("mode", ArgType::Mode))
+        .arg(ArgSpec::new("keys", ArgType::Str))
+        .arg(ArgSpec::new("command", ArgType::Str).complete(CompletionKind::Command))
+        .arg(ArgSpec::new("args", ArgType::Str).optional().variadic());
+
+    registry
+        .register("set-key-matching", |ctx: &mut CommandContext| {
+            let value: String = ctx.get_arg(0)?;
+            let key_matching: KeyMatching = value.parse()?;
+            Arc::make_mut(&mut ctx.editor.config).key_matching = key_matching;
+            ctx.editor
+                .set_status(format!("Modified chords match {} keys", value.to_lowercase()));
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Match Alt/Super chords on the `physical` key position or the `logical` character the layout produces.")
+        .arg(ArgSpec::new("matching", ArgType::Str));
+
+    registry
+        .register("push-keymap", |ctx: &mut CommandContext| {
+            let name: String = ctx.get_arg(0)?;
+            let buf_id = ctx.editor.focus_ref().1.id;
+            ctx.editor.keymap.push_layer(buf_id, &name)?;
+            ctx.editor.set_status(format!("{} enabled", name));
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Enable a minor-mode keymap in the focused buffer, above its other layers.")
+        .arg(ArgSpec::new("keymap", ArgType::Str).complete(CompletionKind::Keymap));
+
+    registry
+        .register("pop-keymap", |ctx: &mut CommandContext| {
+            let name: Option<String> = ctx.get_opt_arg(0)?;
+            let buf_id = ctx.editor.focus_ref().1.id;
+            let popped = ctx.editor.keymap.pop_layer(buf_id, name.as_deref())?;
+            ctx.editor.set_status(format!("{} disabled", popped));
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Disable a minor-mode keymap in the focused buffer, or the most recently enabled one.")
+        .arg(
+            ArgSpec::new("keymap", ArgType::Str)
+                .optional()
+                .complete(CompletionKind::Keymap),
+        );
 
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
+    registry
+        .register("new-tab", |ctx: &mut CommandContext| {
+            let name: Option<String> = ctx.get_opt_arg(0)?;
+            ctx.editor.new_tab(name.as_deref());
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Open a tab page with its own window layout, showing the focused buffer.")
+        .arg(ArgSpec::new("name", ArgType::Str).optional());
+
+    registry
+        .register("close-tab", |ctx: &mut CommandContext| {
+            ctx.editor.project_manager.current_mut().close_tab()?;
+            ctx.editor.sync_focused_buffer();
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Close the current tab page. Its buffers stay open.");
+
+    registry
+        .register("rename-tab", |ctx: &mut CommandContext| {
+            if let Some(name) = ctx.get_opt_arg::<String>(0)? {
+                ctx.editor.project_manager.current_mut().tab_mut().name = name;
+                return Ok(());
+            }
 
-        Ok(())
-    });
+            let initial = ctx.editor.project_manager.current().tab().name.clone();
+            let prompt = Prompt::new("Rename tab: ", |state: &mut Editor, input: String| {
+                state.project_manager.current_mut().tab_mut().name = input;
+                Ok(())
+            })
+            .with_input(&initial)
+            .with_validator(|input: &str| {
+                if input.trim().is_empty() {
+                    return Err(anyhow!("Tab name can't be empty"));
+                }
+                Ok(())
+            });
 
-    registry.register("kill-current-project", |ctx: &mut CommandContext| {
-        ctx.editor.close_current_project()
-    });
+            ctx.editor.activate_minibuffer(Box::new(prompt))
+        })
+        .category(CommandCategory::Window)
+        .doc("Rename the current tab page, asking for a name if none is given.")
+        .arg(ArgSpec::new("name", ArgType::Str).optional());
+
+    registry
+        .register("move-tab", |ctx: &mut CommandContext| {
+            let offset: i64 = ctx.get_arg(0)?;
+            ctx.editor
+                .project_manager
+                .current_mut()
+                .move_tab(offset as isize * ctx.count as isize);
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Move the current tab page left (negative) or right in the tab bar.")
+        .arg(ArgSpec::new("offset", ArgType::Int));
+
+    registry
+        .register("next-tab", |ctx: &mut CommandContext| {
+            ctx.editor
+                .project_manager
+                .current_mut()
+                .cycle_tab(ctx.count as isize);
+            ctx.editor.sync_focused_buffer();
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Switch to the next tab page.");
+
+    registry
+        .register("previous-tab", |ctx: &mut CommandContext| {
+            ctx.editor
+                .project_manager
+                .current_mut()
+                .cycle_tab(-(ctx.count as isize));
+            ctx.editor.sync_focused_buffer();
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Switch to the previous tab page.");
+
+    registry
+        .register("goto-tab", |ctx: &mut CommandContext| {
+            let index: i64 = ctx.get_arg(0)?;
+            ctx.editor
+                .project_manager
+                .current_mut()
+                .select_tab((index.max(1) - 1) as usize)?;
+            ctx.editor.sync_focused_buffer();
+            Ok(())
+        })
+        .category(CommandCategory::Window)
+        .doc("Switch to the tab page at the given position, counting from 1.")
+        .arg(ArgSpec::new("index", ArgType::Int));
+
+    registry
+        .register("enter-transient", |ctx: &mut CommandContext| {
+            let name: String = ctx.get_arg(0)?;
+            ctx.editor.keymap.enter_transient(&name)?;
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Enter a transient keymap whose keys repeat until an unbound key or Esc.")
+        .arg(ArgSpec::new("keymap", ArgType::Str).complete(CompletionKind::TransientKeymap));
+
+    registry
+        .register("exit-transient", |ctx: &mut CommandContext| {
+            ctx.editor.keymap.exit_transient();
+            Ok(())
+        })
+        .category(CommandCategory::Misc)
+        .doc("Leave the active transient keymap.");
 
-    registry.register("next-project", |ctx: &mut CommandContext| {
-        ctx.editor.switch_to_next_project()
-    });
+    registry
+        .register("live-grep", |ctx| {
+            let buf = ctx.editor.focus_ref().1;
+            if buf.large {
+                return Err(anyhow!("live-grep is off in large-file mode"));
+            }
+            let content = buf.to_string();
+
+            let items: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+
+            let minibuffer: MiniBuffer<String> =
+                MiniBuffer::new("Search: ", items, |state: &mut Editor, line: &String| {
+                    let (window, buf) = state.focus();
+                    for (i, l) in buf.to_string().lines().enumerate() {
+                        if l == line {
+                            window.cursor.row = i;
+                            window.cursor.col = 0;
+                            break;
+                        }
+                    }
+                    Ok(None)
+                });
 
-    registry.register("previous-project", |ctx: &mut CommandContext| {
-        ctx.editor.switch_to_previous_project()
-    });
+            ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
 
-    registry.register("keymap", |ctx| {
-        let tree_text = ctx.editor.keymap.render();
+            ctx.editor
+                .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
 
-        let id = ctx
-            .editor
-            .new_read_only_buffer_from_text("*keymap*", &tree_text);
+            Ok(())
+        })
+        .category(CommandCategory::Search)
+        .doc("Search the lines of the focused buffer.");
+}
 
-        ctx.editor.focus_buf(id);
+/// Asks where to save buffer `id`, starting in the project directory.
+fn save_as_prompt(editor: &mut Editor, id: BufferId, force: bool) -> anyhow::Result<()> {
+    let initial = editor
+        .cwd()
+        .map(|cwd| format!("{}/", cwd.display()))
+        .unwrap_or_default();
+    let prompt = Prompt::new("Save as: ", move |state: &mut Editor, input: String| {
+        if state.focused_buf_mut().id != id {
+            return Err(anyhow!("Buffer changed before saving"));
+        }
+        state.save_buffer_as(id, PathBuf::from(input), force)
+    })
+    .with_input(&initial)
+    .with_completion(complete_path)
+    .with_validator(|input: &str| {
+        if input.trim().is_empty() {
+            return Err(anyhow!("File name can't be empty"));
+        }
+        if input.ends_with('/') {
+            return Err(anyhow!("'{}' is a directory", input));
+        }
         Ok(())
     });
 
-    registry.register("live-grep", |ctx| {
-        let buf = ctx.editor.focus_ref().1;
-        let content = buf.to_string();
-
-        let items: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-
-        let minibuffer: MiniBuffer<String> =
-            MiniBuffer::new("Search: ", items, |state: &mut Editor, line: &String| {
-                let (window, buf) = state.focus();
-                for (i, l) in buf.to_string().lines().enumerate() {
-                    if l == line {
-                        window.cursor.row = i;
-                        window.cursor.col = 0;
-                        break;
-                    }
-                }
-                Ok(None)
-            });
-
-        ctx.editor.minibuffer_manager.activate(Box::new(minibuffer));
-
-        ctx.editor
-            .exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
+    editor.activate_minibuffer(Box::new(prompt))
+}
 
-        Ok(())
-    });
+/// Moves the focused hex buffer's cursor `delta` bytes, `ctx.count` times.
+fn hex_move(ctx: &mut CommandContext, delta: isize) -> anyhow::Result<()> {
+    let (window, buf) = ctx.editor.focus();
+    let len = buf
+        .bytes()
+        .ok_or_else(|| anyhow!("Not a hex buffer"))?
+        .len();
+    window.cursor = hex::step_bytes(window.cursor, len, delta * ctx.count as isize);
+    ctx.editor.update_scroll();
+    Ok(())
 }
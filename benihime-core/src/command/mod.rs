@@ -1,10 +1,15 @@
+pub mod command_line;
 pub mod command_registry;
 pub mod default_commands;
+pub mod signature;
 
 use anyhow::{Result, anyhow};
 use std::path::PathBuf;
 
-use crate::editor::{Editor, Mode};
+use crate::{
+    command::signature::ArgType,
+    editor::{Editor, Mode},
+};
 
 #[derive(Debug, Clone)]
 pub enum CommandArg {
@@ -16,38 +21,6 @@ pub enum CommandArg {
     Path(PathBuf),
 }
 
-impl CommandArg {
-    pub fn parse_arg(token: &str) -> CommandArg {
-        if token.contains('/') || token.contains('.') {
-            return CommandArg::Path(PathBuf::from(token));
-        }
-
-        if let Some((row_str, col_str)) = token.split_once(',')
-            && let (Ok(row), Ok(col)) = (row_str.parse::<usize>(), col_str.parse::<usize>())
-        {
-            return CommandArg::Position { row, col };
-        }
-
-        if let Ok(mode) = token.parse::<Mode>() {
-            return CommandArg::Mode(mode);
-        }
-
-        if let Ok(i) = token.parse::<i64>() {
-            return CommandArg::Int(i);
-        }
-
-        if token.eq_ignore_ascii_case("true") {
-            return CommandArg::Bool(true);
-        }
-
-        if token.eq_ignore_ascii_case("false") {
-            return CommandArg::Bool(false);
-        }
-
-        CommandArg::Str(token.to_string())
-    }
-}
-
 pub struct CommandContext<'a> {
     pub editor: &'a mut Editor,
     pub count: usize,
@@ -70,6 +43,24 @@ impl CommandContext<'_> {
         arg.as_type_owned()
             .ok_or_else(|| anyhow!("Argument '{}' has wrong type", index))
     }
+
+    pub fn get_args_from<T>(&self, index: usize) -> Result<Vec<T>>
+    where
+        CommandArg: ArgAsOwned<T>,
+    {
+        let len = self.args.as_ref().map_or(0, |args| args.len());
+        (index..len).map(|i| self.get_arg(i)).collect()
+    }
+
+    pub fn get_opt_arg<T>(&self, index: usize) -> Result<Option<T>>
+    where
+        CommandArg: ArgAsOwned<T>,
+    {
+        match self.args.as_ref().and_then(|args| args.get(index)) {
+            Some(_) => self.get_arg(index).map(Some),
+            None => Ok(None),
+        }
+    }
 }
 
 pub trait ArgAsOwned<T> {
@@ -89,6 +80,7 @@ impl ArgAsOwned<PathBuf> for CommandArg {
     fn as_type_owned(&self) -> Option<PathBuf> {
         match self {
             CommandArg::Path(p) => Some(p.clone()),
+            CommandArg::Str(s) => ArgType::Path.parse(s)?.as_type_owned(),
             _ => None,
         }
     }
@@ -98,6 +90,7 @@ impl ArgAsOwned<i64> for CommandArg {
     fn as_type_owned(&self) -> Option<i64> {
         match self {
             CommandArg::Int(i) => Some(*i),
+            CommandArg::Str(s) => ArgType::Int.parse(s)?.as_type_owned(),
             _ => None,
         }
     }
@@ -107,6 +100,7 @@ impl ArgAsOwned<bool> for CommandArg {
     fn as_type_owned(&self) -> Option<bool> {
         match self {
             CommandArg::Bool(b) => Some(*b),
+            CommandArg::Str(s) => ArgType::Bool.parse(s)?.as_type_owned(),
             _ => None,
         }
     }
@@ -116,6 +110,7 @@ impl ArgAsOwned<crate::editor::Mode> for CommandArg {
     fn as_type_owned(&self) -> Option<crate::editor::Mode> {
         match self {
             CommandArg::Mode(m) => Some(*m),
+            CommandArg::Str(s) => ArgType::Mode.parse(s)?.as_type_owned(),
             _ => None,
         }
     }
@@ -125,10 +120,49 @@ impl ArgAsOwned<(usize, usize)> for CommandArg {
     fn as_type_owned(&self) -> Option<(usize, usize)> {
         match self {
             CommandArg::Position { row, col } => Some((*row, *col)),
+            CommandArg::Str(s) => ArgType::Position.parse(s)?.as_type_owned(),
             _ => None,
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CommandCategory {
+    Movement,
+    Editing,
+    Selection,
+    Mode,
+    History,
+    Buffer,
+    File,
+    Window,
+    Project,
+    Minibuffer,
+    Search,
+    Help,
+    Misc,
+}
+
+impl CommandCategory {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandCategory::Movement => "movement",
+            CommandCategory::Editing => "editing",
+            CommandCategory::Selection => "selection",
+            CommandCategory::Mode => "mode",
+            CommandCategory::History => "history",
+            CommandCategory::Buffer => "buffer",
+            CommandCategory::File => "file",
+            CommandCategory::Window => "window",
+            CommandCategory::Project => "project",
+            CommandCategory::Minibuffer => "minibuffer",
+            CommandCategory::Search => "search",
+            CommandCategory::Help => "help",
+            CommandCategory::Misc => "misc",
+        }
+    }
+}
+
 pub enum Cmd {
     Plain(CommandFn),
     Motion(CommandFn),
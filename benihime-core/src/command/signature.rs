@@ -0,0 +1,173 @@
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
+
+use crate::{command::CommandArg, editor::Mode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    Str,
+    Int,
+    Bool,
+    Mode,
+    Position,
+    Path,
+}
+
+impl ArgType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArgType::Str => "string",
+            ArgType::Int => "integer",
+            ArgType::Bool => "boolean",
+            ArgType::Mode => "mode",
+            ArgType::Position => "position (row,col)",
+            ArgType::Path => "path",
+        }
+    }
+
+    pub fn parse(&self, token: &str) -> Option<CommandArg> {
+        match self {
+            ArgType::Str => Some(CommandArg::Str(token.to_string())),
+            ArgType::Int => token.parse::<i64>().ok().map(CommandArg::Int),
+            ArgType::Bool => match token.to_lowercase().as_str() {
+                "true" | "yes" | "on" => Some(CommandArg::Bool(true)),
+                "false" | "no" | "off" => Some(CommandArg::Bool(false)),
+                _ => None,
+            },
+            ArgType::Mode => token.parse::<Mode>().ok().map(CommandArg::Mode),
+            ArgType::Position => {
+                let (row, col) = token.split_once(',')?;
+                Some(CommandArg::Position {
+                    row: row.trim().parse().ok()?,
+                    col: col.trim().parse().ok()?,
+                })
+            }
+            ArgType::Path => Some(CommandArg::Path(PathBuf::from(token))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    None,
+    Command,
+    Path,
+    Buffer,
+    Theme,
+    Mode,
+    Keymap,
+    TransientKeymap,
+    Session,
+    Encoding,
+    LineEnding,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArgSpec {
+    pub name: String,
+    pub ty: ArgType,
+    pub optional: bool,
+    pub variadic: bool,
+    pub completion: CompletionKind,
+}
+
+impl ArgSpec {
+    pub fn new(name: &str, ty: ArgType) -> Self {
+        let completion = match ty {
+            ArgType::Path => CompletionKind::Path,
+            ArgType::Mode => CompletionKind::Mode,
+            _ => CompletionKind::None,
+        };
+
+        Self {
+            name: name.to_string(),
+            ty,
+            optional: false,
+            variadic: false,
+            completion,
+        }
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    pub fn variadic(mut self) -> Self {
+        self.variadic = true;
+        self
+    }
+
+    pub fn complete(mut self, completion: CompletionKind) -> Self {
+        self.completion = completion;
+        self
+    }
+
+    pub fn usage(&self) -> String {
+        let dots = if self.variadic { "..." } else { "" };
+        if self.optional {
+            format!("[{}{}]", self.name, dots)
+        } else {
+            format!("<{}{}>", self.name, dots)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Signature {
+    pub args: Vec<ArgSpec>,
+}
+
+impl Signature {
+    pub fn spec_at(&self, index: usize) -> Option<&ArgSpec> {
+        match self.args.get(index) {
+            Some(spec) => Some(spec),
+            None => self.args.last().filter(|spec| spec.variadic),
+        }
+    }
+
+    pub fn usage(&self) -> String {
+        self.args
+            .iter()
+            .map(|spec| spec.usage())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn parse(&self, tokens: &[String]) -> Result<Vec<CommandArg>> {
+        let mut args = Vec::with_capacity(tokens.len());
+
+        for (i, token) in tokens.iter().enumerate() {
+            let spec = self.spec_at(i).ok_or_else(|| match self.args.len() {
+                0 => anyhow!("command takes no arguments"),
+                n => anyhow!("too many arguments: expected at most {}", n),
+            })?;
+
+            let arg = spec.ty.parse(token).ok_or_else(|| {
+                anyhow!(
+                    "expected {} for argument `{}`, got `{}`",
+                    spec.ty.name(),
+                    spec.name,
+                    token
+                )
+            })?;
+
+            args.push(arg);
+        }
+
+        if let Some(missing) = self
+            .args
+            .iter()
+            .skip(tokens.len())
+            .find(|spec| !spec.optional)
+        {
+            return Err(anyhow!(
+                "missing {} argument `{}`",
+                missing.ty.name(),
+                missing.name
+            ));
+        }
+
+        Ok(args)
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+use crate::{editor::EditorConfig, editor::Mode, keymap::Keymap};
+
+/// One entry of a `[keys.<mode>]` table. An empty command unbinds the keys.
+#[derive(Debug, Clone)]
+pub struct KeyOverride {
+    pub mode: Mode,
+    pub keys: String,
+    pub command: String,
+}
+
+/// The user's `config.toml`:
+///
+/// ```toml
+/// [editor]
+/// scroll-offset = 4
+///
+/// [keys]
+/// preset = "emacs"
+///
+/// [projects."/home/me/notes"]
+/// auto-save = "idle"
+///
+/// [keys.insert]
+/// "C-c t" = "set-theme default"
+/// "C-x C-c" = ""
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub editor: EditorConfig,
+    pub keys: Vec<KeyOverride>,
+}
+
+impl Config {
+    /// Reads `path`, returning the defaults when the file doesn't exist.
+    /// Unknown or malformed entries are skipped and reported as warnings.
+    pub fn load(path: &Path) -> Result<(Self, Vec<String>)> {
+        if !path.is_file() {
+            return Ok((Self::default(), Vec::new()));
+        }
+
+        let data = std::fs::read_to_string(path)?;
+        let value: Value = toml::from_str(&data)?;
+
+        Ok(Self::from_toml(value))
+    }
+
+    pub fn from_toml(value: Value) -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let mut warnings = Vec::new();
+
+        let Value::Table(table) = value else {
+            warnings.push("config: expected a table at the top level".to_string());
+            return (config, warnings);
+        };
+
+        for (section, value) in table {
+            match (section.as_str(), value) {
+                ("editor", Value::Table(editor)) => {
+                    for (key, value) in editor {
+                        if let Err(e) = config.set_editor_option(&key, &value) {
+                            warnings.push(format!("config: editor.{}: {}", key, e));
+                        }
+                    }
+                }
+                ("keys", Value::Table(keys)) => {
+                    for (key, value) in keys {
+                        match (key.as_str(), value) {
+                            ("preset", Value::String(preset)) => match preset.parse() {
+                                Ok(preset) => config.editor.keymap_preset = preset,
+                                Err(e) => warnings.push(format!("config: keys.preset: {}", e)),
+                            },
+                            (mode, Value::Table(bindings)) => {
+                                let mode: Mode = match mode.parse() {
+                                    Ok(mode) => mode,
+                                    Err(e) => {
+                                        warnings.push(format!("config: keys.{}: {}", key, e));
+                                        continue;
+                                    }
+                                };
+
+                                for (keys, command) in bindings {
+                                    match command {
+                                        Value::String(command) => {
+                                            config.keys.push(KeyOverride {
+                                                mode,
+                                                keys,
+                                                command,
+                                            });
+                                        }
+                                        _ => warnings.push(format!(
+                                            "config: keys.{}.{}: expected a command string",
+                                            key, keys
+                                        )),
+                                    }
+                                }
+                            }
+                            _ => warnings.push(format!("config: unknown key keys.{}", key)),
+                        }
+                    }
+                }
+                ("projects", Value::Table(projects)) => {
+                    for (dir, value) in projects {
+                        if let Err(e) = config.set_project_option(&dir, &value) {
+                            warnings.push(format!("config: projects.\"{}\": {}", dir, e));
+                        }
+                    }
+                }
+                (section, _) => warnings.push(format!("config: unknown section [{}]", section)),
+            }
+        }
+
+        (config, warnings)
+    }
+
+    fn set_editor_option(&mut self, key: &str, value: &Value) -> Result<()> {
+        let editor = &mut self.editor;
+        match key {
+            "smooth-scroll" => editor.smooth_scroll_enabled = as_bool(value)?,
+            "scroll-lines" => editor.scroll_lines = as_integer(value)? as isize,
+            "scroll-offset" => editor.scroll_offset = as_integer(value)?.max(0) as usize,
+            "which-key-delay-ms" => editor.which_key_delay_ms = as_integer(value)?.max(0) as u64,
+            "key-timeout-ms" => editor.key_timeout_ms = as_integer(value)?.max(0) as u64,
+            "key-matching" => editor.key_matching = as_str(value)?.parse()?,
+            "restore-session" => editor.restore_session = as_bool(value)?,
+            "save-session-on-exit" => editor.save_session_on_exit = as_bool(value)?,
+            "persistent-undo" => editor.persistent_undo = as_bool(value)?,
+            "undo-limit" => editor.undo_limit = as_integer(value)?.max(0) as usize,
+            "backup" => editor.backup = as_bool(value)?,
+            "large-file-threshold-mb" => {
+                editor.large_file_threshold_mb = as_integer(value)?.max(0) as u64
+            }
+            "swap-files" => editor.swap_files = as_bool(value)?,
+            "auto-save" => editor.auto_save = as_str(value)?.parse()?,
+            "auto-save-delay-ms" => editor.auto_save_delay_ms = as_integer(value)?.max(0) as u64,
+            _ => anyhow::bail!("unknown option"),
+        }
+        Ok(())
+    }
+
+    /// Reads a `[projects."<dir>"]` table, for projects under `dir`.
+    fn set_project_option(&mut self, dir: &str, value: &Value) -> Result<()> {
+        let Value::Table(options) = value else {
+            anyhow::bail!("expected a table");
+        };
+        for (key, value) in options {
+            match key.as_str() {
+                "auto-save" => self
+                    .editor
+                    .project_auto_save
+                    .push((PathBuf::from(dir), as_str(value)?.parse()?)),
+                _ => anyhow::bail!("unknown option {}", key),
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies `[keys.<mode>]` overrides on top of the preset's bindings.
+    pub fn apply_keys(&self, keymap: &mut Keymap) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        for KeyOverride {
+            mode,
+            keys,
+            command,
+        } in &self.keys
+        {
+            let result = if command.trim().is_empty() {
+                keymap.unbind_str(&[*mode], keys)
+            } else {
+                keymap.bind_str(&[*mode], keys, command)
+            };
+
+            if let Err(e) = result {
+                warnings.push(format!("config: keys: {}: {}", keys, e));
+            }
+        }
+
+        warnings
+    }
+}
+
+fn as_bool(value: &Value) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| anyhow::anyhow!("expected a boolean"))
+}
+
+fn as_integer(value: &Value) -> Result<i64> {
+    value
+        .as_integer()
+        .ok_or_else(|| anyhow::anyhow!("expected an integer"))
+}
+
+fn as_str(value: &Value) -> Result<&str> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("expected a string"))
+}
@@ -0,0 +1,240 @@
+use std::ops::Range;
+
+use ropey::Rope;
+
+use crate::transaction::Transaction;
+
+/// Beyond this many inserted and deleted lines, everything between the
+/// texts' common start and end is one change.
+const MAX_EDITS: usize = 2000;
+
+/// Lines `old` of the old text replaced by lines `new` of the new one.
+/// Either range may be empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// The changes turning `old` into `new`, in order, found with Myers'
+/// algorithm.
+pub fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Change> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    if a.is_empty() && b.is_empty() {
+        return Vec::new();
+    }
+    let Some(edits) = shortest_edit(a, b) else {
+        return vec![Change {
+            old: prefix..prefix + a.len(),
+            new: prefix..prefix + b.len(),
+        }];
+    };
+
+    let mut changes = Vec::new();
+    let mut pending: Option<Change> = None;
+    let (mut i, mut j) = (prefix, prefix);
+    for edit in edits {
+        match edit {
+            Edit::Equal => {
+                changes.extend(pending.take());
+                i += 1;
+                j += 1;
+            }
+            Edit::Delete => {
+                pending
+                    .get_or_insert(Change {
+                        old: i..i,
+                        new: j..j,
+                    })
+                    .old
+                    .end += 1;
+                i += 1;
+            }
+            Edit::Insert => {
+                pending
+                    .get_or_insert(Change {
+                        old: i..i,
+                        new: j..j,
+                    })
+                    .new
+                    .end += 1;
+                j += 1;
+            }
+        }
+    }
+    changes.extend(pending);
+    changes
+}
+
+/// The edits of a shortest path from `a` to `b`, or `None` when it needs
+/// more than `MAX_EDITS`.
+fn shortest_edit<T: PartialEq>(a: &[T], b: &[T]) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m).min(MAX_EDITS as isize);
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // For each number of edits, the furthest x reached on each diagonal k
+    // before taking it, stored from k = -d at index 0.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    let mut found = None;
+    'search: for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let down =
+                k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]);
+            let mut x = if down {
+                v[(offset + k + 1) as usize]
+            } else {
+                v[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                found = Some(d);
+                break 'search;
+            }
+        }
+    }
+    found?;
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = if d == 0 { 0 } else { at(prev_k) };
+        let prev_y = if d == 0 { 0 } else { prev_x - prev_k };
+
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == prev_x {
+                Edit::Insert
+            } else {
+                Edit::Delete
+            });
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+
+    edits.reverse();
+    Some(edits)
+}
+
+/// Lines of `text`, each with its line break.
+fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// An edit turning `old` into `new` that only touches the lines that differ.
+pub fn transaction(old: &Rope, new: &str) -> Transaction {
+    let old_text = old.to_string();
+    let (old_lines, new_lines) = (lines(&old_text), lines(new));
+
+    // Where each line starts, counted in chars; ropey's own lines also
+    // break at lone carriage returns and so can't be used.
+    let starts: Vec<usize> = std::iter::once(0)
+        .chain(old_lines.iter().scan(0, |at, line| {
+            *at += line.chars().count();
+            Some(*at)
+        }))
+        .collect();
+    let changes = diff(&old_lines, &new_lines).into_iter().map(|change| {
+        (
+            starts[change.old.start],
+            starts[change.old.end],
+            Some(new_lines[change.new].concat()),
+        )
+    });
+
+    Transaction::change(old, changes)
+}
+
+/// The differences between `old` and `new` in unified diff format, with
+/// three lines of context.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    let (old_lines, new_lines) = (lines(old), lines(new));
+    let changes = diff(&old_lines, &new_lines);
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+
+    let mut rest = changes.as_slice();
+    while let Some(first) = rest.first() {
+        // Changes closer than twice the context share a hunk.
+        let len = rest
+            .windows(2)
+            .take_while(|pair| pair[1].old.start - pair[0].old.end <= 2 * CONTEXT)
+            .count()
+            + 1;
+        let (hunk, next) = rest.split_at(len);
+        rest = next;
+        let last = &hunk[len - 1];
+
+        let old_start = first.old.start.saturating_sub(CONTEXT);
+        let new_start = first.new.start - (first.old.start - old_start);
+        let old_end = (last.old.end + CONTEXT).min(old_lines.len());
+        let new_end = last.new.end + (old_end - last.old.end);
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + 1,
+            old_end - old_start,
+            new_start + 1,
+            new_end - new_start
+        ));
+
+        let mut line = old_start;
+        for change in hunk {
+            for l in &old_lines[line..change.old.start] {
+                push_line(&mut out, ' ', l);
+            }
+            for l in &old_lines[change.old.clone()] {
+                push_line(&mut out, '-', l);
+            }
+            for l in &new_lines[change.new.clone()] {
+                push_line(&mut out, '+', l);
+            }
+            line = change.old.end;
+        }
+        for l in &old_lines[line..old_end] {
+            push_line(&mut out, ' ', l);
+        }
+    }
+
+    out
+}
+
+fn push_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line.trim_end_matches(['\n', '\r']));
+    out.push('\n');
+}
@@ -1,20 +1,48 @@
 use anyhow::anyhow;
-use std::{collections::BTreeMap, fs, path::PathBuf, str::FromStr, sync::Arc};
+use benihime_loader::paths::Paths;
+use ropey::Rope;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
     application::HandleKeyError,
-    buffer::{Buffer, BufferId},
-    command::{CommandArg, CommandContext, command_registry::CommandRegistry},
+    buffer::{Buffer, BufferId, Position, Selection},
+    command::{
+        CommandArg, CommandContext, command_line, command_registry::CommandRegistry,
+        signature::CompletionKind,
+    },
+    diff,
+    encoding::{Encoding, LineEnding},
+    file_loader::{self, LoadedFile},
     graphics::Rect,
-    keymap::Keymap,
-    mini_buffer::MiniBufferManager,
+    hex,
+    input_handler::KeyMatching,
+    keymap::{KeySequence, Keymap, preset::KeymapPreset},
+    mini_buffer::{
+        Confirm, Confirmation, MiniBuffer, MiniBufferManager, MiniBufferTrait, complete_path,
+    },
+    movement::selection::Range,
     project::{
         ProjectId,
         project_manager::{DEFAULT_PROJECT_ID, ProjectManager},
     },
+    session,
+    swap::SwapFiles,
     theme::{Theme, theme_loader::ThemeLoader},
+    transaction::Assoc,
     tree::{Direction, Layout, Tree},
-    window::Window,
+    ui::file_watcher::{DiskCheck, FileWatcher, WatchedFile},
+    undo_file::UndoFile,
+    undo_view::UndoView,
+    undotree::UndoCursor,
+    window::{Window, WindowId},
+    writer::{DiskState, FileStamp, WriteRequest, WriteResult, Writer},
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -26,6 +54,33 @@ pub enum Mode {
     Minibuffer,
 }
 
+/// When modified buffers are saved without being asked to.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum AutoSave {
+    #[default]
+    Off,
+    /// Once editing has paused for `auto-save-delay-ms`.
+    Idle,
+    /// When the editor window loses focus.
+    FocusLost,
+}
+
+impl FromStr for AutoSave {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(AutoSave::Off),
+            "idle" => Ok(AutoSave::Idle),
+            "focus-lost" => Ok(AutoSave::FocusLost),
+            _ => Err(anyhow!(
+                "Unknown auto-save mode '{}': expected off, idle or focus-lost",
+                s
+            )),
+        }
+    }
+}
+
 impl FromStr for Mode {
     type Err = anyhow::Error;
 
@@ -35,6 +90,7 @@ impl FromStr for Mode {
             "insert" => Ok(Mode::Insert),
             "visual" => Ok(Mode::Visual),
             "command" => Ok(Mode::Command),
+            "minibuffer" => Ok(Mode::Minibuffer),
             _ => Err(anyhow!("Invalid mode: {}", s)),
         }
     }
@@ -54,13 +110,39 @@ pub struct Editor {
     theme_loader: Arc<ThemeLoader>,
     pub prefix_arg: Option<usize>,
     pub keymap: Keymap,
+    pub describe_key: Option<KeySequence>,
 
     buffers: BTreeMap<BufferId, Buffer>,
     next_buffer_id: BufferId,
 
+    /// Saves run in the background; `write_count` of them haven't finished.
+    writer: Writer,
     write_count: usize,
+    /// Compares buffers with their files in the background.
+    file_watcher: FileWatcher,
+    /// Keeps copies of modified buffers to recover after a crash.
+    swaps: SwapFiles,
+    /// Buffers opened with a newer swap file, waiting to ask about it.
+    swap_prompts: Vec<BufferId>,
+    /// When a buffer was last edited, until auto-save on idle handles it.
+    last_edit: Option<Instant>,
+    /// Status messages and errors so far, shown by `show-messages`.
+    messages: Vec<String>,
+    /// How many commands are running, as commands can run others.
+    exec_depth: usize,
+
+    /// Where named sessions are saved, under `Paths::data`.
+    session_dir: PathBuf,
+    /// The session `save-session` writes to when no name is given.
+    pub session_name: String,
+    /// Where undo histories are kept, under `Paths::cache`.
+    undo_dir: PathBuf,
+    /// Where files are copied before being overwritten, under `Paths::cache`.
+    backup_dir: PathBuf,
+    pub undo_view: Option<UndoView>,
 
     pub needs_redraw: bool,
+    pub should_exit: bool,
     pub config: Arc<EditorConfig>,
 }
 
@@ -73,6 +155,7 @@ impl Editor {
         keymap: Keymap,
         registry: Arc<CommandRegistry>,
         config: Arc<EditorConfig>,
+        paths: &Paths,
     ) -> Self {
         Self {
             focused_buf_id: BufferId(0),
@@ -88,8 +171,22 @@ impl Editor {
             theme_loader: Arc::new(theme_loader),
             prefix_arg: None,
             keymap,
+            describe_key: None,
+            writer: Writer::new(),
             write_count: 0,
+            file_watcher: FileWatcher::new(),
+            swaps: SwapFiles::new(paths.cache.join("swap")),
+            swap_prompts: Vec::new(),
+            last_edit: None,
+            messages: Vec::new(),
+            exec_depth: 0,
+            session_dir: paths.data.join("sessions"),
+            session_name: session::DEFAULT_SESSION.to_string(),
+            undo_dir: paths.cache.join("undo"),
+            backup_dir: paths.cache.join("backup"),
+            undo_view: None,
             needs_redraw: false,
+            should_exit: false,
             config,
             buffers: BTreeMap::new(),
             next_buffer_id: BufferId(1),
@@ -97,11 +194,11 @@ impl Editor {
     }
 
     pub fn tree(&self) -> &Tree {
-        &self.project_manager.current().tree
+        &self.project_manager.current().tab().tree
     }
 
     pub fn tree_mut(&mut self) -> &mut Tree {
-        &mut self.project_manager.current_mut().tree
+        &mut self.project_manager.current_mut().tab_mut().tree
     }
 
     pub fn resize(&mut self, area: Rect) {
@@ -121,7 +218,43 @@ impl Editor {
             Mode::Command => "COMMAND",
             Mode::Minibuffer => "MINIBUFFER",
         };
-        format!("{} {}", mode, buf.id)
+        let mut status = if buf.is_hex() {
+            format!("{} {} hex", mode, buf.id)
+        } else {
+            format!(
+                "{} {} {} {}",
+                mode,
+                buf.id,
+                buf.encoding,
+                buf.line_ending_name()
+            )
+        };
+
+        if let Some(percent) = buf.load_progress {
+            status.push_str(&format!(" [loading {}%]", percent));
+        } else if buf.large {
+            status.push_str(" [large]");
+        }
+
+        if self.tree().is_zoomed() {
+            status.push_str(" [zoomed]");
+        }
+
+        if let Some(transient) = self.keymap.active_transient() {
+            let mut keys = transient
+                .bindings(window.mode)
+                .iter()
+                .map(|(seq, binding)| format!("{}:{}", seq.to_string(), binding.command))
+                .collect::<Vec<_>>();
+            keys.sort();
+            status.push_str(&format!(
+                " [{}] {} Esc:exit",
+                transient.name,
+                keys.join(" ")
+            ));
+        }
+
+        status
     }
 
     pub fn buffer_line(&self) -> Vec<(BufferId, String, bool, bool)> {
@@ -138,45 +271,818 @@ impl Editor {
     }
 
     pub fn kill_active_buffer(&mut self) -> anyhow::Result<()> {
-        let project = self.project_manager.current_mut();
-
-        if project.buffers.is_empty() {
+        if self.project_manager.current().buffers.is_empty() {
             return Ok(());
         }
 
-        let buf_id_to_kill = self.focused_buf_id;
+        self.close_buffer(self.focused_buf_id)
+    }
 
+    /// Kills `buf_id`, asking whether to save it first if it is modified.
+    pub fn close_buffer(&mut self, buf_id_to_kill: BufferId) -> anyhow::Result<()> {
         let buf = self
             .buffers
             .get(&buf_id_to_kill)
             .ok_or_else(|| anyhow!("Buffer not found"))?;
 
         if buf.is_modified() {
+            let confirm = Confirm::new(
+                &format!("Buffer '{}' modified; save before killing? ", buf.name),
+                move |editor: &mut Editor, answer: Confirmation| match answer {
+                    Confirmation::Yes => {
+                        editor.save_buffer(buf_id_to_kill, false)?;
+                        editor
+                            .block_flush_writes()
+                            .map_err(|e| anyhow!("Not killing the buffer: {}", e))?;
+                        editor.kill_buffer(buf_id_to_kill)
+                    }
+                    Confirmation::No => editor.kill_buffer(buf_id_to_kill),
+                    Confirmation::Cancel => Ok(()),
+                },
+            );
+            return self.activate_minibuffer(Box::new(confirm));
+        }
+
+        self.kill_buffer(buf_id_to_kill)
+    }
+
+    pub fn kill_buffer(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        if !self.buffers.contains_key(&buf_id) {
+            return Err(anyhow!("Buffer not found"));
+        }
+
+        self.project_manager
+            .current_mut()
+            .buffers
+            .retain(|&id| id != buf_id);
+
+        self.buffers.remove(&buf_id);
+        self.forget_buffer(buf_id);
+
+        Ok(())
+    }
+
+    /// Drops every reference tabs hold to a removed buffer. Layouts that still
+    /// show it fall back to a single window on another buffer.
+    fn forget_buffer(&mut self, buf_id: BufferId) {
+        self.keymap.remove_buffer(buf_id);
+        if self
+            .undo_view
+            .as_ref()
+            .is_some_and(|view| view.target == buf_id || view.view == buf_id)
+        {
+            self.undo_view = None;
+        }
+
+        let project = self.project_manager.current_mut();
+        for tab in project.tabs.iter_mut() {
+            tab.windows.remove(&buf_id);
+        }
+        if !project.tabs.iter().any(|tab| tab.shows(buf_id)) {
+            return;
+        }
+
+        let base_mode = self.base_mode();
+        let fallback = match self.project_manager.current().buffers.last() {
+            Some(&id) => id,
+            None => self.insert_buffer(Buffer::new(BufferId(0), "[No Name]", None, false)),
+        };
+
+        let project = self.project_manager.current_mut();
+        let current_tab = project.current_tab;
+        for (i, tab) in project.tabs.iter_mut().enumerate() {
+            if i != current_tab && tab.shows(buf_id) {
+                let mut window = Window::new(fallback);
+                window.mode = base_mode;
+                tab.tree.set_single_window(window);
+            }
+        }
+
+        if self
+            .tree()
+            .windows()
+            .any(|(window, _, _)| window.buffer_id == buf_id)
+        {
+            self.focus_buf(fallback);
+        }
+    }
+
+    /// Queues the buffer to be written to its file. Unless `force` is set, a
+    /// file changed by something else since it was read is left alone.
+    pub fn save_buffer(&mut self, buf_id: BufferId, force: bool) -> anyhow::Result<()> {
+        let backup_dir = self.config.backup.then(|| self.backup_dir.clone());
+        let buf = self
+            .buffers
+            .get(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        let path = buf
+            .file_path
+            .clone()
+            .ok_or_else(|| anyhow!("No file path set for buffer '{}'", buf.name))?;
+        if buf.load_progress.is_some() {
+            return Err(anyhow!("{} is still loading", buf.name));
+        }
+
+        if !force
+            && let Some(stamp) = buf.disk_stamp
+            && stamp.is_outdated(&path)
+        {
             return Err(anyhow!(
-                "Cannot kill modified buffer '{}'. Save it first.",
-                buf.name
+                "{} changed on disk since it was read; `save-current-buffer true` overwrites it",
+                path.display()
             ));
         }
 
-        project.buffers.retain(|&id| id != buf_id_to_kill);
-        project.windows.remove(&buf_id_to_kill);
+        let bytes = match buf.bytes() {
+            Some(bytes) => bytes.to_vec(),
+            None => buf.encoding.encode(&buf.to_string())?,
+        };
+        self.writer.queue(WriteRequest {
+            buf_id,
+            version: buf.version(),
+            path,
+            bytes,
+            backup_dir,
+            previous_stamp: buf.disk_stamp,
+        })?;
+        self.write_count += 1;
+        // The write changes the file; its new stamp comes with the result.
+        if let Some(buf) = self.buffers.get_mut(&buf_id) {
+            buf.disk_stamp = None;
+        }
+        self.save_undo(buf_id);
+        Ok(())
+    }
+
+    /// Saves the buffer to `path`, overwriting a file there only with `force`.
+    pub fn save_buffer_as(
+        &mut self,
+        buf_id: BufferId,
+        path: PathBuf,
+        force: bool,
+    ) -> anyhow::Result<()> {
+        let buf = self
+            .buffers
+            .get_mut(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+
+        if buf.file_path.as_ref() != Some(&path) {
+            if !force && path.exists() {
+                return Err(anyhow!(
+                    "{} already exists; `save-as {} true` overwrites it",
+                    path.display(),
+                    path.display()
+                ));
+            }
+            buf.name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default()
+                .to_string();
+            buf.file_path = Some(path);
+            buf.disk_stamp = None;
+        }
+
+        self.save_buffer(buf_id, force)
+    }
+
+    /// Saves every modified buffer that has a file, returning how many were
+    /// queued.
+    pub fn save_all(&mut self) -> anyhow::Result<usize> {
+        let ids: Vec<BufferId> = self
+            .buffers
+            .values()
+            .filter(|buf| buf.is_modified() && buf.file_path.is_some())
+            .map(|buf| buf.id)
+            .collect();
+
+        for id in &ids {
+            self.save_buffer(*id, false)?;
+        }
+        Ok(ids.len())
+    }
+
+    /// How buffers of a project rooted at `root` are auto-saved.
+    fn auto_save_mode(&self, root: Option<&Path>) -> AutoSave {
+        root.and_then(|root| {
+            self.config
+                .project_auto_save
+                .iter()
+                .filter(|(dir, _)| root.starts_with(dir))
+                .max_by_key(|(dir, _)| dir.components().count())
+                .map(|(_, mode)| *mode)
+        })
+        .unwrap_or(self.config.auto_save)
+    }
+
+    /// Saves the modified buffers of every project that auto-saves on
+    /// `trigger`.
+    pub fn auto_save(&mut self, trigger: AutoSave) {
+        let ids: Vec<BufferId> = self
+            .project_manager
+            .get_projects()
+            .into_iter()
+            .filter(|project| self.auto_save_mode(project.root.as_deref()) == trigger)
+            .flat_map(|project| project.buffers.iter().copied())
+            .collect();
+
+        for id in ids {
+            let Some(buf) = self.buffers.get(&id) else {
+                continue;
+            };
+            if !buf.is_modified() || buf.file_path.is_none() || buf.load_progress.is_some() {
+                continue;
+            }
+            if let Err(e) = self.save_buffer(id, false) {
+                self.set_error(format!("auto-save: {}", e));
+            }
+        }
+    }
+
+    /// Auto-saves on idle once editing has paused for `auto-save-delay-ms`,
+    /// otherwise returning when to check again.
+    pub fn auto_save_idle(&mut self) -> Option<Instant> {
+        let deadline = self.last_edit? + Duration::from_millis(self.config.auto_save_delay_ms);
+        if Instant::now() < deadline {
+            return Some(deadline);
+        }
+
+        self.last_edit = None;
+        self.auto_save(AutoSave::Idle);
+        None
+    }
 
-        self.buffers.remove(&buf_id_to_kill);
+    /// Asks the watcher to compare every file-backed buffer with its file.
+    pub fn check_disk_changes(&self) {
+        let files: Vec<WatchedFile> = self
+            .buffers
+            .iter()
+            .filter(|(_, buf)| !buf.large)
+            .filter_map(|(&buf_id, buf)| {
+                Some(WatchedFile {
+                    buf_id,
+                    path: buf.file_path.clone()?,
+                    stamp: buf.disk_stamp?,
+                })
+            })
+            .collect();
+        if !files.is_empty() {
+            self.file_watcher.check(files);
+        }
+    }
+
+    /// Reloads unmodified buffers whose file changed, and asks about the others.
+    pub fn apply_disk_checks(&mut self, checks: Vec<DiskCheck>) {
+        let mut conflict = None;
+        let mut missing = Vec::new();
+
+        for DiskCheck { file, state } in checks {
+            let id = file.buf_id;
+            let Some(buf) = self.buffers.get_mut(&id) else {
+                continue;
+            };
+            // Saved, reloaded or renamed while the check was running.
+            if buf.disk_stamp != Some(file.stamp)
+                || buf.file_path.as_ref() != Some(&file.path)
+                || buf.large
+            {
+                continue;
+            }
+
+            let was_missing =
+                std::mem::replace(&mut buf.disk_missing, matches!(state, DiskState::Missing));
+            match state {
+                DiskState::Unchanged => {}
+                DiskState::Missing => {
+                    if !was_missing {
+                        missing.push(file.path);
+                    }
+                }
+                DiskState::Touched(stamp) => buf.disk_stamp = Some(stamp),
+                DiskState::Changed(bytes, stamp) if !buf.is_modified() => {
+                    if let Err(e) = self.reload_buffer(id, &bytes, stamp) {
+                        self.set_error(e.to_string());
+                    }
+                }
+                DiskState::Changed(_, stamp) => {
+                    if buf.disk_conflict != Some(stamp) && conflict.is_none() {
+                        conflict = Some((id, stamp));
+                    }
+                }
+            }
+        }
+
+        for path in missing {
+            self.set_error(format!("{} was deleted or moved on disk", path.display()));
+        }
+        if let Some((id, stamp)) = conflict
+            && self.minibuffer_manager.current.is_none()
+        {
+            if let Some(buf) = self.buffers.get_mut(&id) {
+                buf.disk_conflict = Some(stamp);
+            }
+            if let Err(e) = self.ask_about_disk_change(id) {
+                self.set_error(e.to_string());
+            }
+        }
+    }
 
-        if let Some(&new_focus) = project.buffers.last() {
-            self.focus_buf(new_focus);
+    /// Replaces the buffer's text with its file's, as one undoable edit.
+    pub fn reload_buffer_from_disk(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let buf = self
+            .buffers
+            .get(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        if buf.large {
+            return Err(anyhow!(
+                "Reloading is off in large-file mode; reopen the file"
+            ));
+        }
+        let path = buf
+            .file_path
+            .clone()
+            .ok_or_else(|| anyhow!("Buffer has no file to reload"))?;
+        let (bytes, stamp) =
+            FileStamp::read(&path).map_err(|e| anyhow!("Can't read {}: {}", path.display(), e))?;
+        self.reload_buffer(buf_id, &bytes, stamp)
+    }
+
+    fn reload_buffer(
+        &mut self,
+        buf_id: BufferId,
+        bytes: &[u8],
+        stamp: FileStamp,
+    ) -> anyhow::Result<()> {
+        let buf = self
+            .buffers
+            .get_mut(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        if buf.is_hex() {
+            buf.set_hex(bytes.to_vec());
+            buf.disk_stamp = Some(stamp);
+            buf.disk_conflict = None;
+            buf.mark_clean();
+            let name = buf.name.clone();
+
+            let len = bytes.len();
+            self.move_cursors(buf_id, |cursor| hex::step_bytes(cursor, len, 0));
+            self.set_status(format!("Reloaded {}", name));
+            return Ok(());
+        }
+        let (text, encoding) =
+            Encoding::decode(bytes).map_err(|e| anyhow!("Can't decode {}: {}", buf.name, e))?;
+
+        let transaction = diff::transaction(buf.text(), &text);
+        buf.undo_tree.commit_group();
+        buf.apply(&transaction);
+        buf.undo_tree.commit_group();
+
+        (buf.line_ending, buf.mixed_line_endings) = LineEnding::detect(&text);
+        buf.encoding = encoding;
+        buf.disk_stamp = Some(stamp);
+        buf.disk_conflict = None;
+        buf.mark_clean();
+        let name = buf.name.clone();
+
+        self.sync_changes(None);
+        self.save_undo(buf_id);
+        self.set_status(format!("Reloaded {}", name));
+        Ok(())
+    }
+
+    /// Asks whether to reload a modified buffer whose file changed on disk,
+    /// keep it, or see how the two differ.
+    fn ask_about_disk_change(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let name = self
+            .buffers
+            .get(&buf_id)
+            .map(|buf| buf.name.clone())
+            .unwrap_or_default();
+        let choices = vec!["reload".to_string(), "keep".to_string(), "diff".to_string()];
+
+        let minibuffer: MiniBuffer<String> = MiniBuffer::new(
+            &format!("{} changed on disk and has unsaved changes: ", name),
+            choices,
+            move |editor: &mut Editor, choice: &String| {
+                match choice.as_str() {
+                    "reload" => editor.reload_buffer_from_disk(buf_id)?,
+                    "keep" => editor.keep_buffer_over_disk(buf_id)?,
+                    _ => editor.show_disk_diff(buf_id)?,
+                }
+                Ok(None)
+            },
+        );
+        self.activate_minibuffer(Box::new(minibuffer))
+    }
+
+    /// Keeps the buffer, letting the next save overwrite the file.
+    pub fn keep_buffer_over_disk(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let buf = self
+            .buffers
+            .get_mut(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        if let Some(path) = &buf.file_path
+            && let Ok((_, stamp)) = FileStamp::read(path)
+        {
+            buf.disk_stamp = Some(stamp);
+        }
+        buf.disk_conflict = None;
+        Ok(())
+    }
+
+    /// Shows how the buffer differs from its file on disk.
+    pub fn show_disk_diff(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let buf = self
+            .buffers
+            .get(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        if buf.large {
+            return Err(anyhow!("Diffing is off in large-file mode"));
+        }
+        let path = buf
+            .file_path
+            .clone()
+            .ok_or_else(|| anyhow!("Buffer has no file to compare with"))?;
+        let (bytes, _) =
+            FileStamp::read(&path).map_err(|e| anyhow!("Can't read {}: {}", path.display(), e))?;
+        let disk = if buf.is_hex() {
+            hex::dump(&bytes)
         } else {
-            let new_buf = self.new_empty_buffer("[No Name]");
-            self.focus_buf(new_buf);
+            Encoding::decode(&bytes)?.0
+        };
+
+        let text = diff::unified(
+            &disk,
+            &buf.to_string(),
+            &path.display().to_string(),
+            &format!("{} (buffer)", buf.name),
+        );
+        self.show_special_buffer("*diff*", &text);
+        Ok(())
+    }
+
+    /// Asks about the swap file of a buffer opened since the last call.
+    pub fn poll_swap_prompts(&mut self) {
+        if self.swap_prompts.is_empty() || self.minibuffer_manager.current.is_some() {
+            return;
+        }
+
+        let id = self.swap_prompts.remove(0);
+        if self.buffers.contains_key(&id)
+            && let Err(e) = self.ask_about_swap(id)
+        {
+            self.set_error(e.to_string());
+        }
+    }
+
+    /// Asks whether to recover the unsaved changes in the buffer's swap
+    /// file, see how they differ from the file, or delete them.
+    pub fn ask_about_swap(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let buf = self
+            .buffers
+            .get(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        let path = buf
+            .file_path
+            .clone()
+            .ok_or_else(|| anyhow!("Buffer has no file"))?;
+        if self.swaps.read(&path).is_err() {
+            return Err(anyhow!("{} has no swap file", buf.name));
+        }
+        let choices = vec![
+            "recover".to_string(),
+            "diff".to_string(),
+            "delete".to_string(),
+        ];
+
+        let minibuffer: MiniBuffer<String> = MiniBuffer::new(
+            &format!("{} has unsaved changes in a swap file: ", buf.name),
+            choices,
+            move |editor: &mut Editor, choice: &String| {
+                match choice.as_str() {
+                    "recover" => editor.recover_swap(buf_id)?,
+                    "diff" => editor.show_swap_diff(buf_id)?,
+                    _ => editor.delete_swap(buf_id)?,
+                }
+                Ok(None)
+            },
+        );
+        self.activate_minibuffer(Box::new(minibuffer))
+    }
+
+    /// The path of the buffer and the contents of its swap file.
+    fn read_swap(&self, buf_id: BufferId) -> anyhow::Result<(PathBuf, String)> {
+        let buf = self
+            .buffers
+            .get(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        let path = buf
+            .file_path
+            .clone()
+            .ok_or_else(|| anyhow!("Buffer has no file"))?;
+        let bytes = self
+            .swaps
+            .read(&path)
+            .map_err(|e| anyhow!("Can't read the swap file of {}: {}", buf.name, e))?;
+        let (text, _) = Encoding::decode(&bytes)?;
+        Ok((path, text))
+    }
+
+    /// Replaces the buffer's text with its swap file's, as one undoable edit.
+    pub fn recover_swap(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let (_, text) = self.read_swap(buf_id)?;
+        let buf = self
+            .buffers
+            .get_mut(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+
+        let transaction = diff::transaction(buf.text(), &text);
+        buf.undo_tree.commit_group();
+        buf.apply(&transaction);
+        buf.undo_tree.commit_group();
+        let name = buf.name.clone();
+
+        self.sync_changes(None);
+        self.set_status(format!(
+            "Recovered {} from its swap file; save to keep it",
+            name
+        ));
+        Ok(())
+    }
+
+    /// Shows how the buffer's swap file differs from its file on disk.
+    pub fn show_swap_diff(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let (path, swap) = self.read_swap(buf_id)?;
+        let disk = match fs::read(&path) {
+            Ok(bytes) => Encoding::decode(&bytes)?.0,
+            Err(_) => String::new(),
+        };
+
+        let text = diff::unified(
+            &disk,
+            &swap,
+            &path.display().to_string(),
+            &format!("{} (swap)", path.display()),
+        );
+        self.show_special_buffer("*diff*", &text);
+        Ok(())
+    }
+
+    pub fn delete_swap(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let path = self
+            .buffers
+            .get(&buf_id)
+            .and_then(|buf| buf.file_path.clone())
+            .ok_or_else(|| anyhow!("Buffer has no file"))?;
+        self.swaps
+            .delete(&path)
+            .map_err(|e| anyhow!("Can't delete the swap file of {}: {}", path.display(), e))?;
+        self.set_status(format!("Deleted the swap file of {}", path.display()));
+        Ok(())
+    }
+
+    /// Handles the writes that have finished, without waiting for others.
+    pub fn poll_writes(&mut self) {
+        while let Some(done) = self.writer.try_recv() {
+            if let Err(e) = self.finish_write(done) {
+                self.set_error(e.to_string());
+            }
+        }
+    }
+
+    fn finish_write(&mut self, done: WriteResult) -> anyhow::Result<()> {
+        self.write_count = self.write_count.saturating_sub(1);
+
+        // The buffer may have been killed or pointed elsewhere meanwhile.
+        let buf = self
+            .buffers
+            .get_mut(&done.buf_id)
+            .filter(|buf| buf.file_path.as_ref() == Some(&done.path));
+        let stamp = match done.result {
+            Ok(stamp) => stamp,
+            Err(e) => {
+                // The file wasn't replaced, so it's still the one read.
+                if let Some(buf) = buf
+                    && buf.disk_stamp.is_none()
+                {
+                    buf.disk_stamp = done.previous_stamp;
+                }
+                return Err(e);
+            }
+        };
+
+        if let Some(buf) = buf {
+            buf.disk_stamp = Some(stamp);
+            if buf.version() == done.version {
+                buf.mark_clean();
+                self.swaps.remove(&done.path);
+            }
+        }
+        self.set_status(format!("Wrote {}", done.path.display()));
+        Ok(())
+    }
+
+    /// Writes the buffer's undo history next to the contents just saved, so
+    /// it survives restarts. Failures are only logged.
+    fn save_undo(&mut self, buf_id: BufferId) {
+        if !self.config.persistent_undo {
+            return;
+        }
+
+        let limit = self.config.undo_limit;
+        let Some(buf) = self.buffers.get_mut(&buf_id) else {
+            return;
+        };
+        let Some(path) = buf.file_path.clone() else {
+            return;
+        };
+        if buf.large || buf.is_hex() {
+            return;
+        }
+
+        buf.undo_tree.commit_group();
+        buf.undo_tree.truncate(limit);
+
+        let undo_file = UndoFile::new(&self.undo_dir, &path);
+        if let Err(e) = undo_file.save(&buf.undo_tree, &buf.to_string()) {
+            log::warn!("undo history for {}: {}", path.display(), e);
+        }
+    }
+
+    /// Restores the undo history saved for `path`, if the file still has the
+    /// contents it was saved with.
+    fn load_undo(&mut self, buf_id: BufferId, path: &Path, contents: &str) {
+        if !self.config.persistent_undo {
+            return;
+        }
+
+        let tree = match UndoFile::new(&self.undo_dir, path).load(contents) {
+            Ok(Some(tree)) => tree,
+            Ok(None) => return,
+            Err(e) => {
+                log::warn!("undo history for {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        if let Some(buf) = self.buffers.get_mut(&buf_id) {
+            buf.undo_tree = tree;
+            buf.undo_tree.truncate(self.config.undo_limit);
+        }
+    }
+
+    pub fn modified_buffers(&self, ids: &[BufferId]) -> Vec<BufferId> {
+        ids.iter()
+            .copied()
+            .filter(|id| self.buffers.get(id).is_some_and(|buf| buf.is_modified()))
+            .collect()
+    }
+
+    pub fn activate_minibuffer(
+        &mut self,
+        minibuffer: Box<dyn MiniBufferTrait>,
+    ) -> anyhow::Result<()> {
+        self.minibuffer_manager.activate(minibuffer);
+        self.exec("set-mode", Some(vec![CommandArg::Mode(Mode::Minibuffer)]))?;
+        Ok(())
+    }
+
+    /// Stops the editor once pending writes finish; a failed one keeps it open.
+    pub fn exit(&mut self) {
+        if let Err(e) = self.block_flush_writes() {
+            self.set_error(format!("Not quitting: {}", e));
+            return;
+        }
+        // Buffers quit without saving keep their swaps, to recover later.
+        self.swaps.flush();
+        if self.config.save_session_on_exit
+            && let Err(e) = self.save_session(None)
+        {
+            log::error!("session: {}", e);
+        }
+        self.should_exit = true;
+    }
+
+    pub fn session_names(&self) -> Vec<String> {
+        session::list(&self.session_dir)
+    }
+
+    pub fn session_path(&self, name: &str) -> PathBuf {
+        session::session_path(&self.session_dir, name)
+    }
+
+    /// Saves projects, buffers and layouts as `name`, or the current session.
+    pub fn save_session(&mut self, name: Option<&str>) -> anyhow::Result<()> {
+        if let Some(name) = name {
+            self.session_name = name.to_string();
+        }
+        let path = self.session_path(&self.session_name);
+        session::save(self, &path)
+    }
+
+    /// Restores the session `name` on top of the open projects.
+    pub fn load_session(&mut self, name: &str) -> anyhow::Result<()> {
+        let path = self.session_path(name);
+        let warnings = session::restore(self, &path)?;
+        self.session_name = name.to_string();
+
+        for warning in &warnings {
+            log::warn!("{}", warning);
+        }
+        if !warnings.is_empty() {
+            self.set_error(format!(
+                "Session '{}' restored with {} warning(s); see the log",
+                name,
+                warnings.len()
+            ));
         }
 
         Ok(())
     }
 
+    pub fn quit(&mut self) -> anyhow::Result<()> {
+        let all: Vec<BufferId> = self.buffers.keys().copied().collect();
+        let modified = self.modified_buffers(&all);
+
+        if modified.is_empty() {
+            self.exit();
+            return Ok(());
+        }
+
+        let confirm = Confirm::new(
+            &format!(
+                "{} modified buffer(s); save before quitting? ",
+                modified.len()
+            ),
+            move |editor: &mut Editor, answer: Confirmation| match answer {
+                Confirmation::Yes => {
+                    for id in &modified {
+                        editor.save_buffer(*id, false)?;
+                    }
+                    editor.exit();
+                    Ok(())
+                }
+                Confirmation::No => {
+                    editor.exit();
+                    Ok(())
+                }
+                Confirmation::Cancel => Ok(()),
+            },
+        );
+        self.activate_minibuffer(Box::new(confirm))
+    }
+
     pub fn set_theme(&mut self, theme: Theme) {
         self.theme = theme;
     }
 
+    pub fn load_theme(&mut self, name: &str) -> anyhow::Result<()> {
+        let theme = self.theme_loader.load(name)?;
+        self.set_theme(theme);
+        Ok(())
+    }
+
+    pub fn buffer_id_by_path(&self, path: &Path) -> Option<BufferId> {
+        self.buffers
+            .values()
+            .find(|buf| buf.file_path.as_deref() == Some(path))
+            .map(|buf| buf.id)
+    }
+
+    pub fn buffer_id_by_name(&self, name: &str) -> Option<BufferId> {
+        self.buffers
+            .values()
+            .find(|buf| buf.name == name)
+            .map(|buf| buf.id)
+    }
+
+    pub fn completions(&self, kind: CompletionKind, prefix: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = match kind {
+            CompletionKind::None => return Vec::new(),
+            CompletionKind::Path => return complete_path(prefix),
+            CompletionKind::Command => self.registry.commands.keys().cloned().collect(),
+            CompletionKind::Buffer => self.buffers.values().map(|b| b.name.clone()).collect(),
+            CompletionKind::Theme => self.theme_loader.names(),
+            CompletionKind::Keymap => self.keymap.minor_mode_names(),
+            CompletionKind::TransientKeymap => self.keymap.transient_names(),
+            CompletionKind::Session => self.session_names(),
+            CompletionKind::Mode => ["normal", "insert", "visual", "command"]
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            CompletionKind::Encoding => Encoding::ALL.iter().map(|e| e.to_string()).collect(),
+            CompletionKind::LineEnding => LineEnding::ALL
+                .iter()
+                .map(|e| e.name().to_lowercase())
+                .collect(),
+        };
+
+        candidates.retain(|c| c.starts_with(prefix));
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
     pub fn exec(
         &mut self,
         name: &str,
@@ -184,22 +1090,226 @@ impl Editor {
     ) -> Result<(), HandleKeyError> {
         let count = self.prefix_arg.take().unwrap_or(1);
 
+        let outermost = self.exec_depth == 0;
+        let buf_id = self.focus_ref().1.id;
+        if outermost {
+            self.mark_undo_cursor();
+        }
+
+        self.exec_depth += 1;
         let registry = Arc::clone(&self.registry);
-        registry.execute(
+        let result = registry.execute(
             name,
             &mut CommandContext {
                 editor: self,
                 args: &args,
                 count,
             },
-        )?;
+        );
+        self.exec_depth -= 1;
 
+        if outermost {
+            self.sync_changes(Some(self.tree().focus));
+            self.end_undo_group(buf_id);
+        }
+
+        result?;
         self.needs_redraw = true;
         Ok(())
     }
 
+    /// Runs `line` as typed on the `:` line: a command name followed by
+    /// its arguments.
+    pub fn run_command_line(&mut self, line: &str) -> Result<(), HandleKeyError> {
+        let tokens = command_line::tokenize(line)?;
+        let Some((command_name, rest)) = tokens.split_first() else {
+            return Ok(());
+        };
+
+        let registry = Arc::clone(&self.registry);
+        let signature = registry
+            .signature(command_name)
+            .ok_or_else(|| HandleKeyError::CommandNotFound(command_name.to_string()))?;
+
+        let args = signature.parse(rest).map_err(|e| {
+            anyhow!(
+                "{}: {} (usage: {} {})",
+                command_name,
+                e,
+                command_name,
+                signature.usage()
+            )
+        })?;
+
+        self.exec(command_name, Some(args))
+    }
+
+    /// Cursor and selection anchor of the focused window, as undo history
+    /// records them.
+    pub fn undo_cursor(&self) -> UndoCursor {
+        let (window, buf) = self.focus_ref();
+        let anchor = match window.mode {
+            Mode::Visual => buf.selection.as_ref().map(|selection| selection.start),
+            _ => None,
+        };
+
+        UndoCursor {
+            cursor: window.cursor,
+            anchor,
+        }
+    }
+
+    /// Passes the focused window's cursor to its buffer's undo history,
+    /// before and after changing the text outside of a command.
+    pub fn mark_undo_cursor(&mut self) {
+        let cursor = self.undo_cursor();
+        self.focused_buf_mut().undo_tree.set_cursor(cursor);
+    }
+
+    /// Groups the edits of a command into one undo step. While a modal
+    /// preset is in Insert mode the group stays open, so the whole session
+    /// undoes at once; in Emacs, typing between two commands is one step.
+    fn end_undo_group(&mut self, started_in: BufferId) {
+        let base_mode = self.base_mode();
+        self.mark_undo_cursor();
+
+        let (window, buf) = self.focus();
+        if window.mode != Mode::Insert || base_mode == Mode::Insert {
+            buf.undo_tree.commit_group();
+        }
+
+        if buf.id != started_in
+            && let Some(buf) = self.buffers.get_mut(&started_in)
+        {
+            buf.undo_tree.commit_group();
+        }
+    }
+
+    pub fn undo(&mut self, count: usize) {
+        for _ in 0..count {
+            let Some(cursor) = self.focused_buf_mut().undo() else {
+                break;
+            };
+            self.sync_changes(None);
+            self.restore_undo_cursor(self.tree().focus, cursor);
+        }
+    }
+
+    pub fn redo(&mut self, count: usize) {
+        for _ in 0..count {
+            let Some(cursor) = self.focused_buf_mut().redo() else {
+                break;
+            };
+            self.sync_changes(None);
+            self.restore_undo_cursor(self.tree().focus, cursor);
+        }
+    }
+
+    /// Moves `window_id` back to a cursor recorded in its buffer's undo
+    /// history, reselecting what was selected.
+    pub fn restore_undo_cursor(&mut self, window_id: WindowId, cursor: UndoCursor) {
+        let base_mode = self.base_mode();
+        let buf_id = self.tree().get(window_id).buffer_id;
+        let Some(buf) = self.buffers.get_mut(&buf_id) else {
+            return;
+        };
+
+        let clamp = |pos: Position| {
+            let row = pos.row.min(buf.line_count().saturating_sub(1));
+            Position {
+                row,
+                col: pos.col.min(buf.line_len(row)),
+            }
+        };
+        let position = clamp(cursor.cursor);
+        let anchor = cursor.anchor.map(clamp);
+
+        buf.selection = anchor.map(|start| Selection { start });
+
+        let window = self.tree_mut().get_mut(window_id);
+        window.cursor = position;
+        match anchor {
+            Some(_) => window.mode = Mode::Visual,
+            None if window.mode == Mode::Visual => window.mode = base_mode,
+            None => {}
+        }
+    }
+
+    /// Moves the windows on every buffer edited since the last call through
+    /// its changes, so they stay on the same text, in every tab of every
+    /// project. `editing` is the focused window when it made the edits and
+    /// has already put its cursor where it wants it.
+    pub fn sync_changes(&mut self, editing: Option<WindowId>) {
+        let current_project = self.project_manager.current_id();
+        let current_tab = self.project_manager.current().current_tab;
+        let editing_visual = editing
+            .and_then(|id| self.tree().try_get(id))
+            .filter(|window| window.mode == Mode::Visual)
+            .map(|window| window.buffer_id);
+
+        for buf in self.buffers.values_mut() {
+            let Some((old, changes)) = buf.take_changes() else {
+                continue;
+            };
+            self.last_edit = Some(Instant::now());
+            if self.config.swap_files
+                && !buf.large
+                && let Some(path) = &buf.file_path
+            {
+                if buf.is_modified() {
+                    self.swaps.edited(path, buf.text().clone(), buf.encoding);
+                } else {
+                    self.swaps.remove(path);
+                }
+            }
+            // The selection is the buffer's; it moves with the first window
+            // selecting in Visual mode, unless that's the editing one.
+            let mut selection = buf
+                .selection
+                .clone()
+                .filter(|_| editing_visual != Some(buf.id));
+
+            for project in self.project_manager.projects_mut() {
+                let in_current = project.id == current_project;
+
+                for (index, tab) in project.tabs.iter_mut().enumerate() {
+                    let skip = editing.filter(|_| in_current && index == current_tab);
+                    let windows = tab
+                        .tree
+                        .windows_mut()
+                        .filter(|window| Some(window.id) != skip)
+                        .chain(tab.windows.values_mut())
+                        .filter(|window| window.buffer_id == buf.id);
+
+                    for window in windows {
+                        let head = pos_to_char(&old, window.cursor);
+
+                        if window.mode == Mode::Visual
+                            && let Some(anchor) = selection.take()
+                        {
+                            let anchor = pos_to_char(&old, anchor.start);
+                            let range = Range::new(anchor, head).map(&changes);
+                            buf.selection = Some(Selection {
+                                start: char_to_pos(buf.text(), range.anchor),
+                            });
+                            window.cursor = char_to_pos(buf.text(), range.head);
+                        } else {
+                            let head = changes.map_pos(head, Assoc::Before);
+                            window.cursor = char_to_pos(buf.text(), head);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     pub fn switch_project(&mut self, project_id: ProjectId) {
         if self.project_manager.switch_by_id(project_id).is_some() {
+            if self.tree().window_count() > 0 {
+                self.sync_focused_buffer();
+                return;
+            }
+
             let buffer_id = self.project_manager.current().buffers.first().copied();
             if let Some(buffer_id) = buffer_id {
                 self.focus_buf(buffer_id);
@@ -210,20 +1320,203 @@ impl Editor {
         }
     }
 
-    pub fn open_file(&mut self, path: &PathBuf) -> BufferId {
-        let contents = fs::read_to_string(path).unwrap_or_default();
+    /// Opens `path` in a new buffer, decoding it in the encoding it's
+    /// detected to have, or in the hex view when it looks binary. A file
+    /// that doesn't exist yet opens empty.
+    pub fn open_file(&mut self, path: &PathBuf) -> anyhow::Result<BufferId> {
+        let threshold = self.config.large_file_threshold_mb * 1024 * 1024;
+        if threshold > 0 && fs::metadata(path).is_ok_and(|m| m.len() > threshold) {
+            return Ok(self.open_large_file(path));
+        }
 
-        let id = self.new_buffer_from_text(
-            path.file_name().unwrap().to_str().unwrap(),
-            &contents,
-            Some(path),
-        );
+        let (bytes, stamp) = match FileStamp::read(path) {
+            Ok((bytes, stamp)) => (bytes, Some(stamp)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (Vec::new(), None),
+            Err(e) => return Err(anyhow!("Can't read {}: {}", path.display(), e)),
+        };
+        let name = path.file_name().unwrap().to_str().unwrap();
+
+        if Encoding::is_binary(&bytes) {
+            let id = self.new_buffer_from_text(name, "", Some(path));
+            if let Some(buf) = self.buffers.get_mut(&id) {
+                buf.set_hex(bytes);
+                buf.disk_stamp = stamp;
+            }
+            if let Err(e) = self.keymap.push_layer(id, "hex-mode") {
+                log::warn!("open_file: {}", e);
+            }
+            self.project_manager.add_buffer_to_current(id);
+            return Ok(id);
+        }
+
+        let (contents, encoding) = Encoding::decode(&bytes)
+            .map_err(|e| anyhow!("Can't decode {}: {}", path.display(), e))?;
+        let id = self.new_buffer_from_text(name, &contents, Some(path));
+        if self.config.swap_files && self.swaps.is_newer(path, &bytes) {
+            self.swap_prompts.push(id);
+        }
+        if let Some(buf) = self.buffers.get_mut(&id) {
+            buf.encoding = encoding;
+            buf.disk_stamp = stamp;
+        }
+        self.load_undo(id, path, &contents);
 
         self.project_manager.add_buffer_to_current(id);
+        Ok(id)
+    }
+
+    /// Switches the buffer between its text and a hex view of its bytes,
+    /// starting a new undo history.
+    pub fn toggle_hex_view(&mut self, buf_id: BufferId) -> anyhow::Result<()> {
+        let buf = self
+            .buffers
+            .get_mut(&buf_id)
+            .ok_or_else(|| anyhow!("Buffer not found"))?;
+        if buf.large {
+            return Err(anyhow!("The hex view is off in large-file mode"));
+        }
+        let old = buf.text().clone();
+
+        match buf.bytes() {
+            Some(bytes) => {
+                let (text, encoding) = Encoding::decode(bytes)
+                    .map_err(|e| anyhow!("Can't decode {}: {}", buf.name, e))?;
+                let len = bytes.len();
+                buf.set_text_kind(&text);
+                buf.encoding = encoding;
+                let new = buf.text().clone();
+
+                // Gone already if the user turned it off.
+                let _ = self.keymap.pop_layer(buf_id, Some("hex-mode"));
+                self.move_cursors(buf_id, |cursor| match hex::byte_at(cursor, len) {
+                    Some((byte, _)) => {
+                        char_to_pos(&new, new.byte_to_char(byte.min(new.len_bytes())))
+                    }
+                    None => Position::default(),
+                });
+            }
+            None => {
+                let bytes = buf.encoding.encode(&buf.to_string())?;
+                let len = bytes.len();
+                buf.set_hex(bytes);
+
+                if let Err(e) = self.keymap.push_layer(buf_id, "hex-mode") {
+                    log::warn!("toggle_hex_view: {}", e);
+                }
+                self.move_cursors(buf_id, |cursor| {
+                    let byte = old.char_to_byte(pos_to_char(&old, cursor));
+                    hex::step_bytes(hex::position(byte, 0), len, 0)
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the cursor of every window on the buffer, in every tab of
+    /// every project, to where `f` puts it.
+    fn move_cursors(&mut self, buf_id: BufferId, f: impl Fn(Position) -> Position) {
+        for project in self.project_manager.projects_mut() {
+            for tab in project.tabs.iter_mut() {
+                let windows = tab
+                    .tree
+                    .windows_mut()
+                    .chain(tab.windows.values_mut())
+                    .filter(|window| window.buffer_id == buf_id);
+                for window in windows {
+                    window.cursor = f(window.cursor);
+                }
+            }
+        }
+    }
+
+    /// Opens `path` in large-file mode: the buffer is read-only until the
+    /// file has loaded in the background, and live-grep, reloading, diffing
+    /// and persistent undo are off for it.
+    fn open_large_file(&mut self, path: &Path) -> BufferId {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let mut buf = Buffer::new(BufferId(0), name, Some(path.to_path_buf()), true);
+        buf.large = true;
+        buf.load_progress = Some(0);
+
+        let id = self.new_buffer(buf);
+        file_loader::load(id, path.to_path_buf());
+        self.project_manager.add_buffer_to_current(id);
+        self.set_status(format!(
+            "{} is over {} MB: opening it in large-file mode, without live-grep, \
+             reloading, diffing or persistent undo",
+            name, self.config.large_file_threshold_mb
+        ));
         id
     }
 
+    pub fn set_load_progress(&mut self, buf_id: BufferId, percent: u8) {
+        if let Some(buf) = self.buffers.get_mut(&buf_id) {
+            buf.load_progress = Some(percent);
+        }
+    }
+
+    pub fn finish_load(
+        &mut self,
+        buf_id: BufferId,
+        result: anyhow::Result<LoadedFile>,
+    ) -> anyhow::Result<()> {
+        // The buffer may have been killed while loading.
+        let Some(buf) = self.buffers.get_mut(&buf_id) else {
+            return Ok(());
+        };
+        buf.load_progress = None;
+        // The buffer stays empty, so it must not be saved over the file;
+        // opening the file again retries the load in a new buffer.
+        let file = result.inspect_err(|_| buf.file_path = None)?;
+
+        buf.set_loaded_text(file.text);
+        buf.encoding = file.encoding;
+        buf.line_ending = file.line_ending;
+        buf.mixed_line_endings = file.mixed_line_endings;
+        buf.disk_stamp = Some(file.stamp);
+        buf.set_read_only(false);
+        let message = format!(
+            "Loaded {} in large-file mode ({} lines)",
+            buf.name,
+            buf.line_count()
+        );
+
+        self.set_status(message);
+        Ok(())
+    }
+
     pub fn close_current_project(&mut self) -> anyhow::Result<()> {
+        let project = self.project_manager.current();
+        let modified = self.modified_buffers(&project.buffers);
+
+        if modified.is_empty() {
+            return self.close_project_unchecked();
+        }
+
+        let confirm = Confirm::new(
+            &format!(
+                "Project '{}' has {} modified buffer(s); save before closing? ",
+                project.name,
+                modified.len()
+            ),
+            move |editor: &mut Editor, answer: Confirmation| match answer {
+                Confirmation::Yes => {
+                    for id in &modified {
+                        editor.save_buffer(*id, false)?;
+                    }
+                    editor
+                        .block_flush_writes()
+                        .map_err(|e| anyhow!("Not closing the project: {}", e))?;
+                    editor.close_project_unchecked()
+                }
+                Confirmation::No => editor.close_project_unchecked(),
+                Confirmation::Cancel => Ok(()),
+            },
+        );
+        self.activate_minibuffer(Box::new(confirm))
+    }
+
+    fn close_project_unchecked(&mut self) -> anyhow::Result<()> {
         let id = self.project_manager.current_id();
 
         let killed_buffers = self.project_manager.close_project(id)?;
@@ -280,18 +1573,51 @@ impl Editor {
         self.prefix_arg = None;
     }
 
-    pub async fn flush_writes(&mut self) -> anyhow::Result<()> {
-        Ok(())
+    /// Waits for every queued write, returning the first error.
+    pub fn block_flush_writes(&mut self) -> anyhow::Result<()> {
+        futures_executor::block_on(self.flush_writes())
+    }
+
+    async fn flush_writes(&mut self) -> anyhow::Result<()> {
+        let mut result = Ok(());
+        while self.write_count > 0 {
+            let Some(done) = self.writer.recv().await else {
+                return Err(anyhow!("The file writer has stopped"));
+            };
+            if let Err(e) = self.finish_write(done)
+                && result.is_ok()
+            {
+                result = Err(e);
+            }
+        }
+        result
     }
 
     pub fn set_error(&mut self, error: String) {
+        self.log_message(format!("error: {}", error));
         self.error_message = Some(error);
     }
 
     pub fn set_status(&mut self, status: String) {
+        self.log_message(status.clone());
         self.message = Some(status);
     }
 
+    fn log_message(&mut self, message: String) {
+        const MAX_MESSAGES: usize = 1000;
+
+        if self.messages.len() == MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+        self.messages.push(message);
+    }
+
+    pub fn show_messages(&mut self) {
+        let mut text = self.messages.join("\n");
+        text.push('\n');
+        self.show_special_buffer("*messages*", &text);
+    }
+
     pub fn mode(&self) -> Mode {
         self.focus_ref().0.mode
     }
@@ -306,6 +1632,35 @@ impl Editor {
         self.new_buffer(buf)
     }
 
+    pub fn show_help(&mut self, text: &str) {
+        self.show_special_buffer("*help*", text);
+    }
+
+    /// Shows `text` in the special buffer `name`, replacing an earlier one.
+    pub fn show_special_buffer(&mut self, name: &str, text: &str) {
+        if let Some(old) = self.buffer_id_by_name(name) {
+            self.buffers.remove(&old);
+            self.project_manager
+                .current_mut()
+                .buffers
+                .retain(|&id| id != old);
+            self.forget_buffer(old);
+        }
+
+        let id = self.new_special_buffer(name, text);
+        self.focus_buf(id);
+    }
+
+    /// A read-only buffer for generated text (help, key bindings, undo tree) with
+    /// `special-mode` enabled, so `q` closes it.
+    pub fn new_special_buffer(&mut self, name: &str, text: &str) -> BufferId {
+        let id = self.new_read_only_buffer_from_text(name, text);
+        if let Err(e) = self.keymap.push_layer(id, "special-mode") {
+            log::warn!("new_special_buffer: {}", e);
+        }
+        id
+    }
+
     pub fn new_buffer_from_text(
         &mut self,
         name: &str,
@@ -316,14 +1671,19 @@ impl Editor {
         self.new_buffer(buf)
     }
 
-    fn new_buffer(&mut self, mut buf: Buffer) -> BufferId {
+    fn new_buffer(&mut self, buf: Buffer) -> BufferId {
+        let id = self.insert_buffer(buf);
+        self.focus_buf(id);
+        id
+    }
+
+    /// Adds `buf` without showing it anywhere.
+    fn insert_buffer(&mut self, mut buf: Buffer) -> BufferId {
         let id = BufferId(self.next_buffer_id.0);
         self.next_buffer_id = BufferId(self.next_buffer_id.0 + 1);
         buf.id = id;
 
         self.buffers.insert(id, buf);
-        self.focus_buf(id);
-
         id
     }
 
@@ -337,7 +1697,7 @@ impl Editor {
     pub fn focus(&mut self) -> (&mut Window, &mut Buffer) {
         let project_manager = &mut self.project_manager;
         let buffers = &mut self.buffers;
-        let tree = &mut project_manager.current_mut().tree;
+        let tree = &mut project_manager.current_mut().tab_mut().tree;
         let focus = tree.focus;
         let view = tree.get_mut(focus);
         let buffer_id = view.buffer_id;
@@ -349,7 +1709,7 @@ impl Editor {
     }
 
     pub fn focus_ref(&self) -> (&Window, &Buffer) {
-        let tree = &self.project_manager.current().tree;
+        let tree = &self.project_manager.current().tab().tree;
         let view = tree.get(tree.focus);
         let buf = &self.buffers[&view.buffer_id];
 
@@ -370,25 +1730,61 @@ impl Editor {
     }
 
     pub fn focus_buf(&mut self, buf_id: BufferId) {
+        let base_mode = self.base_mode();
         let current_window = {
             let tree = self.tree();
             tree.try_get(tree.focus).cloned()
         };
 
-        let project = self.project_manager.current_mut();
+        let tab = self.project_manager.current_mut().tab_mut();
 
         if let Some(window) = current_window {
-            project.windows.insert(window.buffer_id, window);
+            tab.windows.insert(window.buffer_id, window);
         }
 
-        let window = project
-            .windows
-            .get(&buf_id)
-            .cloned()
-            .unwrap_or_else(|| Window::new(buf_id));
+        let window = tab.windows.get(&buf_id).cloned().unwrap_or_else(|| {
+            let mut window = Window::new(buf_id);
+            window.mode = base_mode;
+            window
+        });
 
         self.focused_buf_id = buf_id;
-        project.tree.set_single_window(window);
+        tab.tree.set_single_window(window);
+    }
+
+    /// Opens a tab after the current one showing the focused buffer.
+    pub fn new_tab(&mut self, name: Option<&str>) {
+        let window = self.focus_ref().0.clone();
+        self.project_manager
+            .current_mut()
+            .new_tab(name)
+            .tree
+            .set_single_window(window);
+    }
+
+    /// Points `focused_buf_id` at the focused window of the current tab, after
+    /// switching tabs or projects.
+    pub fn sync_focused_buffer(&mut self) {
+        self.focused_buf_id = self.focus_ref().0.buffer_id;
+    }
+
+    pub fn tab_line(&self) -> Vec<(String, bool)> {
+        let project = self.project_manager.current();
+        project
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| (tab.name.clone(), i == project.current_tab))
+            .collect()
+    }
+
+    pub fn focus_window(&mut self, id: WindowId) {
+        let tree = self.tree_mut();
+        if let Some(window) = tree.try_get(id) {
+            let buffer_id = window.buffer_id;
+            tree.focus = id;
+            self.focused_buf_id = buffer_id;
+        }
     }
 
     pub fn focus_move(&mut self, direction: Direction) {
@@ -400,18 +1796,87 @@ impl Editor {
         }
     }
 
+    /// The mode windows rest in: Normal for modal presets, Insert for Emacs.
+    pub fn base_mode(&self) -> Mode {
+        self.config.keymap_preset.base_mode()
+    }
+
     pub fn split_current_buffer(&mut self, layout: Layout) {
         let window = self.focus_ref().0.clone();
-        self.project_manager
-            .current_mut()
+        self.tree_mut().split(window, layout);
+    }
+
+    /// Splits the focused window and shows `buf_id` in the new half, restoring
+    /// the cursor it had when it was last shown.
+    pub fn split_with_buffer(&mut self, buf_id: BufferId, layout: Layout) {
+        let base_mode = self.base_mode();
+        let tab = self.project_manager.current_mut().tab_mut();
+
+        let window = tab.windows.get(&buf_id).cloned().unwrap_or_else(|| {
+            let mut window = Window::new(buf_id);
+            window.mode = base_mode;
+            window
+        });
+
+        tab.tree.split(window, layout);
+        self.focused_buf_id = buf_id;
+    }
+
+    pub fn close_window(&mut self) -> anyhow::Result<()> {
+        let tab = self.project_manager.current_mut().tab_mut();
+        let window = tab
+            .tree
+            .remove(tab.tree.focus)
+            .ok_or_else(|| anyhow!("Can't close the last window"))?;
+
+        tab.windows.insert(window.buffer_id, window);
+        self.focused_buf_id = tab.tree.get(tab.tree.focus).buffer_id;
+        Ok(())
+    }
+
+    /// Closes every window but the focused one.
+    pub fn only_window(&mut self) {
+        let tab = self.project_manager.current_mut().tab_mut();
+        let focus = tab.tree.focus;
+
+        let others: Vec<Window> = tab
             .tree
-            .split(window, layout);
+            .windows()
+            .filter(|(window, _, _)| window.id != focus)
+            .map(|(window, _, _)| window.clone())
+            .collect();
+        for window in others {
+            tab.windows.insert(window.buffer_id, window);
+        }
+
+        let window = tab.tree.get(focus).clone();
+        tab.tree.set_single_window(window);
     }
 
     #[inline]
     pub fn buf(&self, id: BufferId) -> Option<&Buffer> {
         self.buffers.get(&id)
     }
+
+    #[inline]
+    pub fn buf_mut(&mut self, id: BufferId) -> Option<&mut Buffer> {
+        self.buffers.get_mut(&id)
+    }
+}
+
+fn pos_to_char(text: &Rope, pos: Position) -> usize {
+    let row = pos.row.min(text.len_lines().saturating_sub(1));
+    let line = text.line(row);
+    text.line_to_char(row) + pos.col.min(line.len_chars())
+}
+
+fn char_to_pos(text: &Rope, idx: usize) -> Position {
+    let idx = idx.min(text.len_chars());
+    let row = text.char_to_line(idx);
+    Position {
+        row,
+        col: idx - text.line_to_char(row),
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -422,6 +1887,26 @@ pub struct EditorConfig {
     pub scroll_min_step_cols: f32,
     pub scroll_lines: isize,
     pub scroll_offset: usize,
+    pub which_key_delay_ms: u64,
+    pub key_timeout_ms: u64,
+    pub key_matching: KeyMatching,
+    pub keymap_preset: KeymapPreset,
+    pub restore_session: bool,
+    pub save_session_on_exit: bool,
+    pub persistent_undo: bool,
+    /// Most edits kept in a saved undo history; older ones are dropped.
+    pub undo_limit: usize,
+    /// Whether to copy a file's previous contents aside before saving it.
+    pub backup: bool,
+    /// Files bigger than this many megabytes open in large-file mode; 0
+    /// turns it off.
+    pub large_file_threshold_mb: u64,
+    /// Whether to keep swap files of modified buffers.
+    pub swap_files: bool,
+    pub auto_save: AutoSave,
+    pub auto_save_delay_ms: u64,
+    /// `auto-save` for projects under these directories.
+    pub project_auto_save: Vec<(PathBuf, AutoSave)>,
 }
 
 impl Default for EditorConfig {
@@ -433,6 +1918,20 @@ impl Default for EditorConfig {
             scroll_min_step_cols: 1.0,
             scroll_lines: 3,
             scroll_offset: 8,
+            which_key_delay_ms: 500,
+            key_timeout_ms: 300,
+            key_matching: KeyMatching::Physical,
+            keymap_preset: KeymapPreset::Vim,
+            restore_session: false,
+            save_session_on_exit: false,
+            persistent_undo: true,
+            undo_limit: 1000,
+            backup: false,
+            large_file_threshold_mb: 50,
+            swap_files: true,
+            auto_save: AutoSave::Off,
+            auto_save_delay_ms: 1000,
+            project_auto_save: Vec::new(),
         }
     }
 }
@@ -0,0 +1,370 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Result, anyhow};
+
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+const UTF16_LE_BOM: &[u8] = &[0xff, 0xfe];
+const UTF16_BE_BOM: &[u8] = &[0xfe, 0xff];
+
+/// What Windows-1252 bytes 0x80 to 0x9f decode to. The five bytes it leaves
+/// undefined map to the control character with the same value, as browsers
+/// do, so they survive a round trip.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Also used for Latin-1, of which it is a superset.
+    Windows1252,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Windows1252,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf8Bom => "utf-8-bom",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Windows1252 => "windows-1252",
+        }
+    }
+
+    /// Detects the encoding of `bytes` and decodes them. A byte order mark
+    /// decides between UTF-8 and UTF-16; without one, text that is mostly
+    /// ASCII with a zero byte every other byte is taken for UTF-16, valid
+    /// UTF-8 for UTF-8, and anything else for Windows-1252.
+    pub fn decode(bytes: &[u8]) -> Result<(String, Encoding)> {
+        if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+            let text = String::from_utf8(rest.to_vec())
+                .map_err(|e| anyhow!("invalid UTF-8 after a byte order mark: {}", e))?;
+            return Ok((text, Encoding::Utf8Bom));
+        }
+        if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+            return Ok((decode_utf16(rest, u16::from_le_bytes)?, Encoding::Utf16Le));
+        }
+        if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+            return Ok((decode_utf16(rest, u16::from_be_bytes)?, Encoding::Utf16Be));
+        }
+
+        if let Some(encoding) = guess_utf16(bytes) {
+            let decoded = match encoding {
+                Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+                _ => decode_utf16(bytes, u16::from_be_bytes),
+            };
+            if let Ok(text) = decoded {
+                return Ok((text, encoding));
+            }
+        }
+
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Ok((text.to_string(), Encoding::Utf8)),
+            Err(_) => Ok((decode_windows_1252(bytes), Encoding::Windows1252)),
+        }
+    }
+
+    /// The encoding of a file starting with `head`, and the length of its
+    /// byte order mark. Without a mark, UTF-16 is guessed from `head` alone.
+    pub fn detect(head: &[u8]) -> (Encoding, usize) {
+        if head.starts_with(UTF8_BOM) {
+            return (Encoding::Utf8Bom, UTF8_BOM.len());
+        }
+        if head.starts_with(UTF16_LE_BOM) {
+            return (Encoding::Utf16Le, UTF16_LE_BOM.len());
+        }
+        if head.starts_with(UTF16_BE_BOM) {
+            return (Encoding::Utf16Be, UTF16_BE_BOM.len());
+        }
+        let even = &head[..head.len() & !1];
+        (guess_utf16(even).unwrap_or(Encoding::Utf8), 0)
+    }
+
+    /// Whether `bytes` look like a binary file rather than text: a zero
+    /// byte in the first 8 KB that isn't part of UTF-16 text.
+    pub fn is_binary(bytes: &[u8]) -> bool {
+        let head = &bytes[..bytes.len().min(8 * 1024)];
+        if !head.contains(&0) {
+            return false;
+        }
+        let utf16_bom = bytes.starts_with(UTF16_LE_BOM) || bytes.starts_with(UTF16_BE_BOM);
+        !utf16_bom && guess_utf16(bytes).is_none()
+    }
+
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Utf8Bom => Ok([UTF8_BOM, text.as_bytes()].concat()),
+            Encoding::Utf16Le => Ok(UTF16_LE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect()),
+            Encoding::Utf16Be => Ok(UTF16_BE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect()),
+            Encoding::Windows1252 => text
+                .chars()
+                .map(|c| {
+                    encode_windows_1252(c)
+                        .ok_or_else(|| anyhow!("'{}' can't be written as {}", c, self.name()))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "utf-8-bom" | "utf8-bom" => Ok(Encoding::Utf8Bom),
+            "utf-16le" | "utf-16-le" | "utf16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf-16-be" | "utf16be" => Ok(Encoding::Utf16Be),
+            "windows-1252" | "cp1252" | "latin-1" | "latin1" | "iso-8859-1" => {
+                Ok(Encoding::Windows1252)
+            }
+            _ => Err(anyhow!("Unknown encoding '{}'", s)),
+        }
+    }
+}
+
+/// Decodes text read in chunks, which may split characters.
+pub struct Decoder {
+    encoding: Encoding,
+    pending: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Decodes `bytes`, keeping a character they end in the middle of for the
+    /// next call.
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<String> {
+        match self.encoding {
+            Encoding::Windows1252 => Ok(decode_windows_1252(bytes)),
+            Encoding::Utf8 | Encoding::Utf8Bom => {
+                self.pending.extend_from_slice(bytes);
+                let valid = match std::str::from_utf8(&self.pending) {
+                    Ok(text) => text.len(),
+                    Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                    Err(e) => return Err(anyhow!("invalid UTF-8: {}", e)),
+                };
+                let rest = self.pending.split_off(valid);
+                let text = String::from_utf8(std::mem::replace(&mut self.pending, rest))?;
+                Ok(text)
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let unit = match self.encoding {
+                    Encoding::Utf16Le => u16::from_le_bytes,
+                    _ => u16::from_be_bytes,
+                };
+                self.pending.extend_from_slice(bytes);
+                let mut whole = self.pending.len() & !1;
+                // A high surrogate waits for the low one that follows it.
+                if whole >= 2
+                    && (0xd800..0xdc00)
+                        .contains(&unit([self.pending[whole - 2], self.pending[whole - 1]]))
+                {
+                    whole -= 2;
+                }
+                let rest = self.pending.split_off(whole);
+                let units = std::mem::replace(&mut self.pending, rest);
+                decode_utf16(&units, unit)
+            }
+        }
+    }
+
+    /// Fails if the text ended in the middle of a character.
+    pub fn finish(&self) -> Result<()> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("{} text cut off in a character", self.encoding))
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Result<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(anyhow!("UTF-16 text with an odd number of bytes"));
+    }
+
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|e| anyhow!("invalid UTF-16: {}", e))
+}
+
+/// UTF-16 without a byte order mark, recognised by zero bytes on one side
+/// of most pairs, as ASCII text encoded in it has.
+fn guess_utf16(bytes: &[u8]) -> Option<Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let pairs = bytes.len() / 2;
+    let zeros_at = |offset: usize| {
+        bytes
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+
+    if odd * 2 > pairs && even == 0 {
+        Some(Encoding::Utf16Le)
+    } else if even * 2 > pairs && odd == 0 {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9f => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+fn encode_windows_1252(c: char) -> Option<u8> {
+    match c as u32 {
+        0..=0x7f | 0xa0..=0xff => Some(c as u8),
+        _ => WINDOWS_1252_HIGH
+            .iter()
+            .position(|high| *high == c)
+            .map(|i| 0x80 + i as u8),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 2] = [LineEnding::Lf, LineEnding::Crlf];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
+
+    /// The ending most lines of `text` use, LF when it has no line breaks,
+    /// and whether some lines use the other one.
+    pub fn detect(text: &str) -> (LineEnding, bool) {
+        LineEnding::from_counts(text.matches('\n').count(), text.matches("\r\n").count())
+    }
+
+    /// Like `detect`, from the number of line breaks and how many of them
+    /// are CRLF.
+    pub fn from_counts(lines: usize, crlf: usize) -> (LineEnding, bool) {
+        let lf = lines - crlf;
+
+        let ending = if crlf > lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        };
+        (ending, crlf > 0 && lf > 0)
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LineEnding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "lf" | "unix" => Ok(LineEnding::Lf),
+            "crlf" | "dos" | "windows" => Ok(LineEnding::Crlf),
+            _ => Err(anyhow!("Unknown line ending '{}': expected lf or crlf", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_in_chunks(encoding: Encoding, bytes: &[u8], size: usize) -> Result<String> {
+        let mut decoder = Decoder::new(encoding);
+        let mut text = String::new();
+        for chunk in bytes.chunks(size) {
+            text.push_str(&decoder.decode(chunk)?);
+        }
+        decoder.finish()?;
+        Ok(text)
+    }
+
+    #[test]
+    fn decoder_joins_characters_split_between_chunks() {
+        let text = "a€b😀c\r\n";
+        for encoding in Encoding::ALL {
+            let Ok(bytes) = encoding.encode(text) else {
+                continue;
+            };
+            let (encoding, bom) = Encoding::detect(&bytes);
+            for size in 1..=4 {
+                let decoded = decode_in_chunks(encoding, &bytes[bom..], size).unwrap();
+                assert_eq!(decoded, text, "{} in chunks of {}", encoding, size);
+            }
+        }
+    }
+
+    #[test]
+    fn decoder_rejects_text_cut_off_in_a_character() {
+        let bytes = "a€".as_bytes();
+        assert!(decode_in_chunks(Encoding::Utf8, &bytes[..bytes.len() - 1], 2).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use ropey::{Rope, RopeBuilder};
+
+use crate::{
+    buffer::BufferId,
+    encoding::{Decoder, Encoding, LineEnding},
+    ui::job::{self, Callback, Job},
+    undo_file,
+    writer::FileStamp,
+};
+
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+pub struct LoadedFile {
+    pub text: Rope,
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+    pub mixed_line_endings: bool,
+    pub stamp: FileStamp,
+}
+
+/// Reads `path` into buffer `buf_id` in a background job.
+pub fn load(buf_id: BufferId, path: PathBuf) {
+    Job::with_callback(async move {
+        let result = tokio::task::spawn_blocking(move || {
+            read(buf_id, &path).map_err(|e| anyhow!("Can't load {}: {}", path.display(), e))
+        })
+        .await?;
+        Ok(Callback::Editor(Box::new(move |editor| {
+            if let Err(e) = editor.finish_load(buf_id, result) {
+                editor.set_error(e.to_string());
+            }
+        })))
+    })
+    .spawn();
+}
+
+fn read(buf_id: BufferId, path: &Path) -> Result<LoadedFile> {
+    let mut head = Vec::new();
+    File::open(path)?.take(8 * 1024).read_to_end(&mut head)?;
+    let (encoding, bom) = Encoding::detect(&head);
+
+    // The same fallbacks as `Encoding::decode`, when the text turns out not
+    // to be what its start looked like.
+    let fallbacks = match (encoding, bom) {
+        (_, 1..) => vec![],
+        (Encoding::Utf8, _) => vec![Encoding::Windows1252],
+        _ => vec![Encoding::Utf8, Encoding::Windows1252],
+    };
+    let mut result = read_as(buf_id, path, encoding, bom);
+    for encoding in fallbacks {
+        if result.is_ok() {
+            break;
+        }
+        result = read_as(buf_id, path, encoding, 0);
+    }
+    result
+}
+
+/// Reads the file in chunks, decoding each into the rope as it comes.
+fn read_as(buf_id: BufferId, path: &Path, encoding: Encoding, bom: usize) -> Result<LoadedFile> {
+    let metadata = fs::metadata(path)?;
+    let total = metadata.len().max(1);
+    let mut file = File::open(path)?;
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut decoder = Decoder::new(encoding);
+    let mut builder = RopeBuilder::new();
+    let mut hash = undo_file::hash(&[]);
+    let (mut lines, mut crlf, mut after_cr) = (0, 0, false);
+    let (mut read, mut reported) = (0, 0);
+
+    loop {
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        hash = undo_file::hash_more(hash, &chunk[..n]);
+        let skip = bom.saturating_sub(read).min(n);
+        read += n;
+
+        let text = decoder.decode(&chunk[skip..n])?;
+        for (i, _) in text.match_indices('\n') {
+            lines += 1;
+            if (i == 0 && after_cr) || (i > 0 && text.as_bytes()[i - 1] == b'\r') {
+                crlf += 1;
+            }
+        }
+        if !text.is_empty() {
+            after_cr = text.ends_with('\r');
+        }
+        builder.append(&text);
+
+        let percent = (read as u64 * 100 / total).min(99) as u8;
+        if percent != reported {
+            reported = percent;
+            job::dispatch_blocking(move |editor, _| editor.set_load_progress(buf_id, percent));
+        }
+    }
+    decoder.finish()?;
+
+    let (line_ending, mixed_line_endings) = LineEnding::from_counts(lines, crlf);
+    Ok(LoadedFile {
+        text: builder.finish(),
+        encoding,
+        line_ending,
+        mixed_line_endings,
+        stamp: FileStamp::with_hash(&metadata, hash),
+    })
+}
@@ -0,0 +1,125 @@
+use anyhow::{Result, anyhow};
+
+use crate::{editor::Editor, editor::Mode, keymap::KeySequence, mini_buffer::MiniBufferDisplay};
+
+#[derive(Debug, Clone)]
+pub struct CommandItem {
+    pub name: String,
+    pub doc: String,
+    pub keys: String,
+}
+
+impl CommandItem {
+    pub fn all(editor: &Editor) -> Vec<CommandItem> {
+        let mut items: Vec<CommandItem> = editor
+            .registry
+            .commands
+            .iter()
+            .map(|(name, entry)| CommandItem {
+                name: name.clone(),
+                doc: entry.doc.clone(),
+                keys: key_hint(editor, name),
+            })
+            .collect();
+
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        items
+    }
+}
+
+impl MiniBufferDisplay for CommandItem {
+    fn as_display_string(&self) -> String {
+        format!("{:<30} {:<16} {}", self.name, self.keys, self.doc)
+    }
+}
+
+fn key_hint(editor: &Editor, command: &str) -> String {
+    let mut keys: Vec<String> = Vec::new();
+
+    let buf_id = editor.focus_ref().1.id;
+    for (seq, _mode) in editor.keymap.bindings_for(buf_id, command) {
+        let key = seq.to_string();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    keys.truncate(2);
+    keys.join(", ")
+}
+
+pub fn describe_command(editor: &Editor, name: &str) -> Result<String> {
+    let entry = editor
+        .registry
+        .commands
+        .get(name)
+        .ok_or_else(|| anyhow!("Unknown command: {}", name))?;
+
+    let mut out = String::new();
+    out.push_str(&format!("{}\n", name));
+    out.push_str(&"=".repeat(name.len()));
+    out.push_str("\n\n");
+
+    if entry.doc.is_empty() {
+        out.push_str("Not documented.\n\n");
+    } else {
+        out.push_str(&format!("{}\n\n", entry.doc));
+    }
+
+    out.push_str(&format!("Category: {}\n", entry.category.name()));
+    out.push_str(&format!("Kind:     {}\n", entry.kind()));
+
+    let usage = entry.signature.usage();
+    if usage.is_empty() {
+        out.push_str(&format!("Usage:    :{}\n", name));
+    } else {
+        out.push_str(&format!("Usage:    :{} {}\n", name, usage));
+    }
+
+    out.push_str("\nKey bindings:\n");
+    let buf_id = editor.focus_ref().1.id;
+    let bindings = editor.keymap.bindings_for(buf_id, name);
+    if bindings.is_empty() {
+        out.push_str("  (none)\n");
+    }
+    for (seq, mode) in bindings {
+        let binding = editor.keymap.lookup(buf_id, mode, &seq);
+        let source = binding.map_or("", |b| b.source.as_str());
+        out.push_str(&format!(
+            "  {:<20} {:<12} {}\n",
+            seq.to_string(),
+            format!("{:?}", mode),
+            source
+        ));
+    }
+
+    Ok(out)
+}
+
+pub fn describe_key(editor: &Editor, mode: Mode, seq: &KeySequence) -> String {
+    let buf_id = editor.focus_ref().1.id;
+    let Some(binding) = editor.keymap.lookup(buf_id, mode, seq) else {
+        return format!("{} is not bound in {:?} mode\n", seq.to_string(), mode);
+    };
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "{} runs the command `{}` in {:?} mode\n",
+        seq.to_string(),
+        binding.command,
+        mode
+    ));
+    out.push_str(&format!("Bound in: {}\n", binding.source));
+
+    if let Some(args) = &binding.args {
+        out.push_str(&format!("Arguments: {:?}\n", args));
+    }
+
+    out.push('\n');
+    match describe_command(editor, &binding.command) {
+        Ok(text) => out.push_str(&text),
+        Err(e) => out.push_str(&format!("{}\n", e)),
+    }
+
+    out
+}
@@ -0,0 +1,122 @@
+use ropey::Rope;
+
+use crate::buffer::Position;
+
+pub const BYTES_PER_ROW: usize = 16;
+
+/// Where the hex bytes and the ASCII column start in a row.
+pub const HEX_START: usize = 10;
+pub const ASCII_START: usize = HEX_START + BYTES_PER_ROW * 3 + 2;
+
+/// The whole dump of `bytes`, one row of sixteen per line: the offset, the
+/// bytes in hex and the bytes as ASCII.
+pub fn dump(bytes: &[u8]) -> String {
+    let rows = bytes.len().div_ceil(BYTES_PER_ROW);
+    (0..rows)
+        .map(|row| self::row(bytes, row))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Row `row` of the dump, without a line break.
+pub fn row(bytes: &[u8], row: usize) -> String {
+    let start = row * BYTES_PER_ROW;
+    let chunk = &bytes[start..(start + BYTES_PER_ROW).min(bytes.len())];
+    let mut line = format!("{:08x}  ", start);
+
+    for i in 0..BYTES_PER_ROW {
+        match chunk.get(i) {
+            Some(byte) => line.push_str(&format!("{:02x} ", byte)),
+            None => line.push_str("   "),
+        }
+        if i == BYTES_PER_ROW / 2 - 1 {
+            line.push(' ');
+        }
+    }
+
+    line.push('|');
+    line.extend(chunk.iter().map(|&b| match b {
+        0x20..=0x7e => b as char,
+        _ => '.',
+    }));
+    line.push('|');
+    line
+}
+
+/// The bytes a dump shows, as `dump` wrote them.
+pub fn parse(dump: &Rope) -> Vec<u8> {
+    dump.lines()
+        .flat_map(|line| {
+            let line = line.to_string();
+            (0..BYTES_PER_ROW).map_while(move |i| {
+                let col = hex_col(i);
+                u8::from_str_radix(line.get(col..col + 2)?, 16).ok()
+            })
+        })
+        .collect()
+}
+
+/// Column of the high nibble of the `i`th byte of a row.
+fn hex_col(i: usize) -> usize {
+    HEX_START + i * 3 + usize::from(i >= BYTES_PER_ROW / 2)
+}
+
+/// Where nibble `nibble` (0 for the high one) of byte `byte` is shown.
+pub fn position(byte: usize, nibble: u8) -> Position {
+    Position {
+        row: byte / BYTES_PER_ROW,
+        col: hex_col(byte % BYTES_PER_ROW) + nibble as usize,
+    }
+}
+
+/// The byte and nibble shown at or nearest to `pos` among `len` bytes, or
+/// `None` when there are none.
+pub fn byte_at(pos: Position, len: usize) -> Option<(usize, u8)> {
+    if len == 0 {
+        return None;
+    }
+
+    let (i, nibble) = if pos.col >= ASCII_START - 1 {
+        (
+            pos.col.saturating_sub(ASCII_START).min(BYTES_PER_ROW - 1),
+            0,
+        )
+    } else if pos.col >= HEX_START {
+        let mut offset = pos.col - HEX_START;
+        if offset >= hex_col(BYTES_PER_ROW / 2) - HEX_START {
+            offset -= 1;
+        }
+        (offset / 3, (offset % 3).min(1) as u8)
+    } else {
+        (0, 0)
+    };
+
+    let byte = pos.row * BYTES_PER_ROW + i;
+    if byte >= len {
+        return Some((len - 1, 0));
+    }
+    Some((byte, nibble))
+}
+
+/// `pos` moved by `delta` bytes among `len`, onto the high nibble.
+pub fn step_bytes(pos: Position, len: usize, delta: isize) -> Position {
+    let Some((byte, _)) = byte_at(pos, len) else {
+        return Position::default();
+    };
+    let byte = byte.saturating_add_signed(delta).min(len - 1);
+    position(byte, 0)
+}
+
+/// `pos` moved one nibble forward or back among `len` bytes.
+pub fn step_nibble(pos: Position, len: usize, forward: bool) -> Position {
+    let Some((byte, nibble)) = byte_at(pos, len) else {
+        return Position::default();
+    };
+    let nibble = byte * 2 + nibble as usize;
+    let nibble = if forward {
+        (nibble + 1).min(len * 2 - 1)
+    } else {
+        nibble.saturating_sub(1)
+    };
+    position(nibble / 2, (nibble % 2) as u8)
+}
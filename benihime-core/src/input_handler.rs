@@ -1,9 +1,9 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
 use benihime_renderer::event::{InputEvent, Key, KeyPress, MouseEvent, ScrollDelta};
 
-use crate::{
-    editor::Mode,
-    keymap::key_chord::{KeyChord, KeyModifiers},
-};
+use crate::{editor::Mode, keymap::key_chord::KeyChord};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UnifiedKey {
@@ -11,6 +11,8 @@ pub enum UnifiedKey {
     Special(SpecialKey),
     Modified {
         key: char,
+        /// The character the layout produced, when `key` is the physical one.
+        text: Option<char>,
         shift: bool,
         ctrl: bool,
         alt: bool,
@@ -51,9 +53,34 @@ pub enum ProcessedInput {
     Scroll(ScrollDelta),
 }
 
+/// Which key a modified chord is matched on. `Physical` makes `Alt+o` and
+/// `Super+p` work the same on every layout (and with macOS Option characters),
+/// while `Logical` uses whatever character the layout produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMatching {
+    Logical,
+    Physical,
+}
+
+impl FromStr for KeyMatching {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "logical" => Ok(KeyMatching::Logical),
+            "physical" => Ok(KeyMatching::Physical),
+            _ => Err(anyhow!(
+                "Invalid key matching: {} (expected logical or physical)",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InputProcessor {
     mode: Mode,
+    key_matching: KeyMatching,
     pending_char: bool,
     shift_held: bool,
     ctrl_held: bool,
@@ -65,6 +92,7 @@ impl InputProcessor {
     pub fn new(mode: Mode) -> Self {
         Self {
             mode,
+            key_matching: KeyMatching::Physical,
             pending_char: false,
             shift_held: false,
             ctrl_held: false,
@@ -77,6 +105,10 @@ impl InputProcessor {
         self.mode = mode;
     }
 
+    pub fn set_key_matching(&mut self, key_matching: KeyMatching) {
+        self.key_matching = key_matching;
+    }
+
     pub fn set_pending_char(&mut self, pending: bool) {
         self.pending_char = pending;
     }
@@ -87,6 +119,7 @@ impl InputProcessor {
             InputEvent::Text(text) => self.process_text(text),
             InputEvent::Mouse(mouse) => vec![ProcessedInput::Mouse(mouse)],
             InputEvent::Scroll(delta) => vec![ProcessedInput::Scroll(delta)],
+            InputEvent::Focus(_) => vec![],
         }
     }
 
@@ -147,51 +180,23 @@ impl InputProcessor {
             Key::Right => Some(UnifiedKey::Special(SpecialKey::Right)),
             Key::F(f) => Some(UnifiedKey::Special(SpecialKey::F(f))),
             Key::Char(ch) if key_press.has_modifier() => {
-                if ch.is_ascii_alphanumeric() || ch.is_ascii_punctuation() {
-                    Some(UnifiedKey::Modified {
-                        key: ch,
-                        shift: key_press.modifier.shift,
-                        ctrl: key_press.modifier.control,
-                        alt: key_press.modifier.alt,
-                        super_key: key_press.modifier.super_key,
-                    })
-                } else {
-                    let base_key = match ch.to_ascii_lowercase() {
-                        'ø' => 'o',
-                        'π' => 'p',
-                        'ß' => 's',
-                        '∆' => 'j',
-                        '¬' => 'l',
-                        '◊' => 'k',
-                        '≈' => '=',
-                        '÷' => '/',
-                        '¥' => 'y',
-                        'å' => 'a',
-                        '∑' => '=',
-                        'œ' => 'o',
-                        'ƒ' => 'f',
-                        '∂' => 'd',
-                        '®' => 'r',
-                        '†' => 't',
-                        '©' => 'c',
-                        '™' => 't',
-                        '§' => 's',
-                        '€' => 'e',
-                        '£' => 'l',
-                        '√' => 'v',
-                        '∞' => '8',
-                        '•' => '.',
-                        '°' => ';',
-                        _ => ch,
-                    };
-                    Some(UnifiedKey::Modified {
-                        key: base_key,
-                        shift: key_press.modifier.shift,
-                        ctrl: key_press.modifier.control,
-                        alt: key_press.modifier.alt,
-                        super_key: key_press.modifier.super_key,
-                    })
-                }
+                let key = match (self.key_matching, &key_press.physical) {
+                    (KeyMatching::Physical, Key::Char(physical))
+                        if key_press.modifier.alt || key_press.modifier.super_key =>
+                    {
+                        *physical
+                    }
+                    _ => ch,
+                };
+
+                Some(UnifiedKey::Modified {
+                    key,
+                    text: (key != ch).then_some(ch),
+                    shift: key_press.modifier.shift,
+                    ctrl: key_press.modifier.control,
+                    alt: key_press.modifier.alt,
+                    super_key: key_press.modifier.super_key,
+                })
             }
             Key::Char(ch) => {
                 if self.pending_char {
@@ -214,48 +219,13 @@ impl InputProcessor {
         for ch in text.chars() {
             if self.mode == Mode::Insert || self.pending_char {
                 events.push(ProcessedInput::Key(UnifiedKey::Character(ch)));
-            } else if self.alt_held {
-                let base_key = match ch.to_ascii_lowercase() {
-                    'ø' => 'o',
-                    'π' => 'p',
-                    'ß' => 's',
-                    '∆' => 'j',
-                    '¬' => 'l',
-                    '◊' => 'k',
-                    '≈' => '=',
-                    '÷' => '/',
-                    '¥' => 'y',
-                    'å' => 'a',
-                    '∑' => '=',
-                    'œ' => 'o',
-                    'ƒ' => 'f',
-                    '∂' => 'd',
-                    '®' => 'r',
-                    '†' => 't',
-                    '©' => 'c',
-                    '™' => 't',
-                    '§' => 's',
-                    '€' => 'e',
-                    '£' => 'l',
-                    '√' => 'v',
-                    '∞' => '8',
-                    '•' => '.',
-                    '°' => ';',
-                    _ => ch,
-                };
-                events.push(ProcessedInput::Key(UnifiedKey::Modified {
-                    key: base_key,
-                    shift: self.shift_held,
-                    ctrl: self.ctrl_held,
-                    alt: true,
-                    super_key: self.super_held,
-                }));
-            } else if self.ctrl_held {
+            } else if self.ctrl_held || self.alt_held {
                 events.push(ProcessedInput::Key(UnifiedKey::Modified {
                     key: ch,
+                    text: None,
                     shift: self.shift_held,
-                    ctrl: true,
-                    alt: false,
+                    ctrl: self.ctrl_held,
+                    alt: self.alt_held,
                     super_key: self.super_held,
                 }));
             } else {
@@ -273,12 +243,16 @@ impl UnifiedKey {
             UnifiedKey::Character(ch) => Some(KeyChord::new(Key::Char(*ch))),
             UnifiedKey::Modified {
                 key,
+                text,
                 shift,
                 ctrl,
                 alt,
                 super_key,
             } => {
-                Some(KeyChord::new(Key::Char(*key)).with_modifiers(*ctrl, *shift, *alt, *super_key))
+                let mut chord =
+                    KeyChord::new(Key::Char(*key)).with_modifiers(*ctrl, *shift, *alt, *super_key);
+                chord.text = *text;
+                Some(chord)
             }
             UnifiedKey::Escape => Some(KeyChord::new(Key::Esc)),
             UnifiedKey::Special(special) => {
@@ -323,15 +297,8 @@ impl UnifiedKey {
                     SpecialKey::Right => Key::Right,
                     SpecialKey::F(u) => Key::F(*u),
                 };
-                let binding = KeyChord {
-                    code: key_code,
-                    modifiers: KeyModifiers {
-                        shift: *shift,
-                        control: *ctrl,
-                        alt: *alt,
-                        super_key: *super_key,
-                    },
-                };
+                let binding =
+                    KeyChord::new(key_code).with_modifiers(*ctrl, *shift, *alt, *super_key);
                 Some(binding)
             }
         }
@@ -379,6 +346,10 @@ impl InputHandler {
         }
     }
 
+    pub fn set_key_matching(&mut self, key_matching: KeyMatching) {
+        self.processor.set_key_matching(key_matching);
+    }
+
     pub fn set_pending_char(&mut self) {
         self.state = InputState::PendingChar;
         self.processor.set_pending_char(true);
@@ -459,3 +430,49 @@ pub struct InputResult {
     pub consumed: bool,
     pub cancelled: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use benihime_renderer::event;
+
+    use super::*;
+
+    /// Option+L on a German Mac layout, which types `@`.
+    fn option_l() -> InputEvent {
+        InputEvent::Keyboard(KeyPress {
+            code: Key::Char('@'),
+            physical: Key::Char('l'),
+            pressed: true,
+            modifier: event::KeyModifiers::ALT,
+        })
+    }
+
+    fn chord(handler: &mut InputHandler, event: InputEvent) -> KeyChord {
+        handler.handle_input(event).keys.unwrap().pop().unwrap()
+    }
+
+    #[test]
+    fn physical_matching_keeps_the_typed_character() {
+        let mut handler = InputHandler::new(Mode::Insert);
+        let chord = chord(&mut handler, option_l());
+
+        assert_eq!(
+            chord,
+            KeyChord::new(Key::Char('l')).with_modifiers(false, false, true, false)
+        );
+        assert_eq!(chord.as_char(), Some('@'));
+    }
+
+    #[test]
+    fn logical_matching_uses_the_typed_character() {
+        let mut handler = InputHandler::new(Mode::Insert);
+        handler.set_key_matching(KeyMatching::Logical);
+        let chord = chord(&mut handler, option_l());
+
+        assert_eq!(
+            chord,
+            KeyChord::new(Key::Char('@')).with_modifiers(false, false, true, false)
+        );
+        assert_eq!(chord.as_char(), Some('@'));
+    }
+}
@@ -1,660 +1,194 @@
-use benihime_renderer::event::Key;
-
-use crate::{command::CommandArg, editor::Mode};
+use crate::editor::Mode;
 
 use super::{
-    key_chord::{KeyChord, KeyModifiers},
-    {KeySequence, Keymap},
+    Keymap,
+    preset::{BindingTable, COMMAND, GroupTable, KeymapPreset, MINIBUFFER},
 };
 
-pub fn register_default_keymap(km: &mut Keymap) {
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('i'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "set-mode",
-        Some(vec![CommandArg::Mode(Mode::Insert)]),
-    );
-
-    km.bind(
-        &[Mode::Insert, Mode::Command, Mode::Minibuffer],
-        KeySequence::single(KeyChord {
-            code: Key::Esc,
-            modifiers: KeyModifiers::NONE,
-        }),
-        "set-mode",
-        Some(vec![CommandArg::Mode(Mode::Normal)]),
-    );
-
-    km.bind(
-        &[Mode::Minibuffer],
-        KeySequence::single(KeyChord {
-            code: Key::Esc,
-            modifiers: KeyModifiers::NONE,
-        }),
-        "close-minibuffer",
-        Some(vec![CommandArg::Mode(Mode::Normal)]),
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('x'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "set-mode",
-        Some(vec![CommandArg::Mode(Mode::Minibuffer)]),
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('h'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "move-left",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('j'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "move-down",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('k'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "move-up",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('l'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "move-right",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('-'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "first-non-blank",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('0'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "beginning-of-line",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('='),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "end-of-line",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('o'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "open-below",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('o'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "open-above",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('e'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "word-end",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('b'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "word-backward",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('w'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "word-forward",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('w'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "long-word-forward",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('e'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "long-word-forward-end",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal, Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('b'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "long-word-backward",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char(':'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "set-mode",
-        Some(vec![CommandArg::Mode(Mode::Command)]),
-    );
-
-    km.bind(
-        &[Mode::Command],
-        KeySequence::single(KeyChord {
-            code: Key::Enter,
-            modifiers: KeyModifiers::NONE,
-        }),
-        "execute-command-buffer",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('j'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "next-buffer",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('k'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "previous-buffer",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![
-            KeyChord {
-                code: Key::Char('z'),
-                modifiers: KeyModifiers::NONE,
-            },
-            KeyChord {
-                code: Key::Char('z'),
-                modifiers: KeyModifiers::NONE,
-            },
-        ]),
-        "center-cursor",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Minibuffer],
-        KeySequence::single(KeyChord {
-            code: Key::Char('j'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "minibuffer-next-completion",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Minibuffer],
-        KeySequence::single(KeyChord {
-            code: Key::Char('k'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "minibuffer-previous-completion",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Minibuffer],
-        KeySequence::single(KeyChord {
-            code: Key::Down,
-            modifiers: KeyModifiers::NONE,
-        }),
-        "minibuffer-next-completion",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Minibuffer],
-        KeySequence::single(KeyChord {
-            code: Key::Up,
-            modifiers: KeyModifiers::NONE,
-        }),
-        "minibuffer-previous-completion",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('p'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "find-file",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![
-            KeyChord {
-                code: Key::Char('x'),
-                modifiers: KeyModifiers::CTRL,
-            },
-            KeyChord {
-                code: Key::Char('f'),
-                modifiers: KeyModifiers::CTRL,
-            },
-        ]),
-        "open-file",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Minibuffer],
-        KeySequence::single(KeyChord {
-            code: Key::Enter,
-            modifiers: KeyModifiers::NONE,
-        }),
-        "minibuffer-accept",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('x'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "find-command",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('q'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "kill-this-buffer",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('v'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "enter-visual-mode",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('v'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "exit-visual-mode",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Esc,
-            modifiers: KeyModifiers::NONE,
-        }),
-        "exit-visual-mode",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('o'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "visual_select_other_end",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('d'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "delete-selection",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Visual],
-        KeySequence::single(KeyChord {
-            code: Key::Char('c'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "change-selection",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('b'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "find-buffer",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('d'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "delete-range",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('c'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "change-range",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('u'),
-            modifiers: KeyModifiers::NONE,
-        }),
-        "undo",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('r'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "redo",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('d'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "scroll-half-down",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('f'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "scroll-full-down",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('u'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "scroll-half-up",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('b'),
-            modifiers: KeyModifiers::CTRL,
-        }),
-        "scroll-full-up",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![
-            KeyChord {
-                code: Key::Char('w'),
-                modifiers: KeyModifiers::CTRL,
-            },
-            KeyChord {
-                code: Key::Char('s'),
-                modifiers: KeyModifiers::NONE,
-            },
-        ]),
-        "split-window-horizontal",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![
-            KeyChord {
-                code: Key::Char('w'),
-                modifiers: KeyModifiers::CTRL,
-            },
-            KeyChord {
-                code: Key::Char('v'),
-                modifiers: KeyModifiers::NONE,
-            },
-        ]),
-        "split-window-vertical",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![KeyChord {
-            code: Key::Char('h'),
-            modifiers: KeyModifiers::CTRL,
-        }]),
-        "focus-left",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![KeyChord {
-            code: Key::Char('j'),
-            modifiers: KeyModifiers::CTRL,
-        }]),
-        "focus-down",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![KeyChord {
-            code: Key::Char('k'),
-            modifiers: KeyModifiers::CTRL,
-        }]),
-        "focus-up",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![KeyChord {
-            code: Key::Char('l'),
-            modifiers: KeyModifiers::CTRL,
-        }]),
-        "focus-right",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::new(vec![
-            KeyChord {
-                code: Key::Char('g'),
-                modifiers: KeyModifiers::NONE,
-            },
-            KeyChord {
-                code: Key::Char('g'),
-                modifiers: KeyModifiers::NONE,
-            },
-        ]),
-        "goto-first-line",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('g'),
-            modifiers: KeyModifiers::SHIFT,
-        }),
-        "goto-last-line",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('s'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "save-current-buffer",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('n'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "open-project",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('x'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "kill-current-project",
-        None,
-    );
-
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('j'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "next-project",
-        None,
-    );
+/// Bindings shared by every preset.
+const BINDINGS: BindingTable = &[
+    (COMMAND, "<Enter>", "execute-command-buffer"),
+    (COMMAND, "<Tab>", "complete-command-line"),
+    (MINIBUFFER, "<Esc>", "close-minibuffer"),
+    (MINIBUFFER, "C-g", "close-minibuffer"),
+    (MINIBUFFER, "C-j", "minibuffer-next-completion"),
+    (MINIBUFFER, "C-k", "minibuffer-previous-completion"),
+    (MINIBUFFER, "C-n", "minibuffer-next-completion"),
+    (MINIBUFFER, "C-p", "minibuffer-previous-completion"),
+    (MINIBUFFER, "<Down>", "minibuffer-next-completion"),
+    (MINIBUFFER, "<Up>", "minibuffer-previous-completion"),
+    (MINIBUFFER, "<Enter>", "minibuffer-accept"),
+];
+
+/// Bound in the preset's base mode.
+const BASE_MODE_BINDINGS: &[(&str, &str)] =
+    &[("<F1> k", "describe-key"), ("<F1> f", "describe-command")];
+
+const BASE_MODE_GROUPS: &[(&str, &str)] = &[("<F1>", "help")];
+
+const SPECIAL_MODE: &[(&str, &str)] = &[("q", "kill-this-buffer")];
+
+/// Keys of the interactive `*undo-tree*` view.
+const UNDO_TREE_MODE: &[(&str, &str)] = &[
+    ("j", "undo-tree-newer"),
+    ("<Down>", "undo-tree-newer"),
+    ("C-n", "undo-tree-newer"),
+    ("k", "undo-tree-older"),
+    ("<Up>", "undo-tree-older"),
+    ("C-p", "undo-tree-older"),
+    ("h", "undo-tree-previous-branch"),
+    ("<Left>", "undo-tree-previous-branch"),
+    ("C-b", "undo-tree-previous-branch"),
+    ("l", "undo-tree-next-branch"),
+    ("<Right>", "undo-tree-next-branch"),
+    ("C-f", "undo-tree-next-branch"),
+    ("<Enter>", "undo-tree-accept"),
+    ("q", "undo-tree-cancel"),
+    ("<Esc>", "undo-tree-cancel"),
+    ("C-g", "undo-tree-cancel"),
+];
+
+/// Keys of hex buffers, moving by byte. They're also bound in Insert mode,
+/// where typing hex digits overwrites the byte under the cursor.
+const HEX_MODE: &[(&str, &str)] = &[
+    ("<Right>", "hex-next-byte"),
+    ("C-f", "hex-next-byte"),
+    ("<Left>", "hex-previous-byte"),
+    ("C-b", "hex-previous-byte"),
+    ("<Down>", "hex-next-row"),
+    ("C-n", "hex-next-row"),
+    ("<Up>", "hex-previous-row"),
+    ("C-p", "hex-previous-row"),
+];
+
+/// Hex buffer keys only bound in Normal mode.
+const HEX_MODE_NORMAL: &[(&str, &str)] = &[
+    ("l", "hex-next-byte"),
+    ("h", "hex-previous-byte"),
+    ("j", "hex-next-row"),
+    ("k", "hex-previous-row"),
+];
+
+/// Transient keymaps entered with `enter-transient`. Their keys repeat until a
+/// key they don't bind is pressed, so they only hold single keys.
+const TRANSIENTS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "buffers",
+        &[
+            ("j", "next-buffer"),
+            ("J", "next-buffer"),
+            ("k", "previous-buffer"),
+            ("K", "previous-buffer"),
+            ("q", "kill-this-buffer"),
+        ],
+    ),
+    (
+        "projects",
+        &[
+            ("j", "next-project"),
+            ("k", "previous-project"),
+            ("x", "kill-current-project"),
+        ],
+    ),
+    ("undo", &[("u", "undo"), ("r", "redo"), ("U", "redo")]),
+    (
+        "windows",
+        &[
+            ("h", "focus-left"),
+            ("j", "focus-down"),
+            ("k", "focus-up"),
+            ("l", "focus-right"),
+            ("s", "split-window-horizontal"),
+            ("v", "split-window-vertical"),
+            ("H", "swap-window-left"),
+            ("J", "swap-window-down"),
+            ("K", "swap-window-up"),
+            ("L", "swap-window-right"),
+            ("S->", "resize-window width 5"),
+            ("S-<", "resize-window width -5"),
+            ("S-+", "resize-window height 5"),
+            ("-", "resize-window height -5"),
+            ("=", "equalize-windows"),
+            ("r", "rotate-layout"),
+            ("z", "zoom-window"),
+            ("c", "close-window"),
+            ("o", "only-window"),
+        ],
+    ),
+];
+
+/// Modes a transient keymap stays active in.
+const TRANSIENT_MODES: &[Mode] = &[Mode::Normal, Mode::Insert, Mode::Visual];
+
+fn bind_table(km: &mut Keymap, bindings: BindingTable) {
+    for (modes, keys, command) in bindings {
+        if let Err(e) = km.bind_str(modes, keys, command) {
+            log::error!("default keymap: {}: {}", keys, e);
+        }
+    }
+}
 
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('k'),
-            modifiers: KeyModifiers::SUPER,
-        }),
-        "previous-project",
-        None,
-    );
+fn group_table(km: &mut Keymap, groups: GroupTable) {
+    for (modes, keys, label) in groups {
+        match keys.parse() {
+            Ok(seq) => km.group(modes, seq, label),
+            Err(e) => log::error!("default keymap: {}: {}", keys, e),
+        }
+    }
+}
 
-    km.bind(
-        &[Mode::Normal],
-        KeySequence::single(KeyChord {
-            code: Key::Char('f'),
-            modifiers: KeyModifiers::ALT,
-        }),
-        "live-grep",
-        None,
-    );
+pub fn register_default_keymap(km: &mut Keymap, preset: KeymapPreset) {
+    let base: &[Mode] = &[preset.base_mode()];
+
+    bind_table(km, BINDINGS);
+    bind_table(km, preset.bindings());
+    group_table(km, preset.groups());
+
+    for (keys, command) in BASE_MODE_BINDINGS {
+        if let Err(e) = km.bind_str(base, keys, command) {
+            log::error!("default keymap: {}: {}", keys, e);
+        }
+    }
+
+    for (keys, label) in BASE_MODE_GROUPS {
+        match keys.parse() {
+            Ok(seq) => km.group(base, seq, label),
+            Err(e) => log::error!("default keymap: {}: {}", keys, e),
+        }
+    }
+
+    for (name, bindings) in TRANSIENTS {
+        let transient = km.transient_mut(name);
+        for (keys, command) in bindings.iter() {
+            if let Err(e) = transient.bind_str(TRANSIENT_MODES, keys, command) {
+                log::error!("{} transient keymap: {}: {}", name, keys, e);
+            }
+        }
+    }
+
+    let special = km.minor_mode_mut("special-mode");
+    for (keys, command) in SPECIAL_MODE {
+        if let Err(e) = special.bind_str(base, keys, command) {
+            log::error!("special-mode keymap: {}: {}", keys, e);
+        }
+    }
+
+    let undo_tree = km.minor_mode_mut("undo-tree-mode");
+    for (keys, command) in UNDO_TREE_MODE {
+        if let Err(e) = undo_tree.bind_str(base, keys, command) {
+            log::error!("undo-tree-mode keymap: {}: {}", keys, e);
+        }
+    }
+
+    let hex_mode = km.minor_mode_mut("hex-mode");
+    for (keys, command) in HEX_MODE {
+        if let Err(e) = hex_mode.bind_str(&[Mode::Normal, Mode::Insert], keys, command) {
+            log::error!("hex-mode keymap: {}: {}", keys, e);
+        }
+    }
+    for (keys, command) in HEX_MODE_NORMAL {
+        if let Err(e) = hex_mode.bind_str(&[Mode::Normal], keys, command) {
+            log::error!("hex-mode keymap: {}: {}", keys, e);
+        }
+    }
 }
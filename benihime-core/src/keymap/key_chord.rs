@@ -1,5 +1,9 @@
+use std::hash::{Hash, Hasher};
+
 use benihime_renderer::event::Key;
 
+use crate::keymap::notation::key_name;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct KeyModifiers {
     pub shift: bool,
@@ -15,38 +19,30 @@ impl KeyModifiers {
         shift: false,
         super_key: false,
     };
-
-    pub const ALT: Self = Self {
-        alt: true,
-        control: false,
-        shift: false,
-        super_key: false,
-    };
-    pub const CTRL: Self = Self {
-        alt: false,
-        control: true,
-        shift: false,
-        super_key: false,
-    };
-    pub const SHIFT: Self = Self {
-        alt: false,
-        control: false,
-        shift: true,
-        super_key: false,
-    };
-
-    pub const SUPER: Self = Self {
-        alt: false,
-        control: false,
-        shift: false,
-        super_key: true,
-    };
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub struct KeyChord {
     pub code: Key,
     pub modifiers: KeyModifiers,
+    /// The character the layout produced, when the chord is matched on the
+    /// physical key instead. Typed text uses it; bindings ignore it.
+    pub text: Option<char>,
+}
+
+impl PartialEq for KeyChord {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code && self.modifiers == other.modifiers
+    }
+}
+
+impl Eq for KeyChord {}
+
+impl Hash for KeyChord {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code.hash(state);
+        self.modifiers.hash(state);
+    }
 }
 
 impl KeyChord {
@@ -54,6 +50,7 @@ impl KeyChord {
         KeyChord {
             code,
             modifiers: KeyModifiers::NONE,
+            text: None,
         }
     }
 
@@ -75,6 +72,9 @@ impl KeyChord {
     }
 
     pub fn as_char(&self) -> Option<char> {
+        if self.text.is_some() {
+            return self.text;
+        }
         match self.code {
             Key::Char(c) => {
                 if self.modifiers.shift {
@@ -110,15 +110,7 @@ impl KeyChord {
             parts.push("Super".to_string());
         }
 
-        let key_str = match &self.code {
-            Key::Char(c) => c.to_string(),
-            Key::Enter => "Enter".to_string(),
-            Key::Backspace => "Backspace".to_string(),
-            Key::Esc => "Esc".to_string(),
-            Key::Tab => "Tab".to_string(),
-            // fallback for all other keys
-            other => format!("{:?}", other),
-        };
+        let key_str = key_name(&self.code);
 
         parts.push(key_str);
 
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    command::{CommandArg, command_line},
+    editor::Mode,
+    keymap::{Binding, KeySequence, key_chord::KeyChord, trie::KeyTrie},
+};
+
+/// A named set of per-mode bindings. The global keymap, each minor mode and each
+/// buffer-local keymap is one layer.
+#[derive(Debug, Clone)]
+pub struct KeymapLayer {
+    pub name: String,
+    tries: HashMap<Mode, KeyTrie>,
+}
+
+impl KeymapLayer {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            tries: HashMap::new(),
+        }
+    }
+
+    pub fn bind(
+        &mut self,
+        modes: &[Mode],
+        seq: KeySequence,
+        command: &str,
+        args: Option<Vec<CommandArg>>,
+    ) {
+        for mode in modes.iter() {
+            let node = self
+                .tries
+                .entry(*mode)
+                .or_default()
+                .get_or_insert(&seq.chords);
+            node.binding = Some(Binding {
+                command: command.to_string(),
+                args: args.clone(),
+                source: self.name.clone(),
+            });
+        }
+    }
+
+    /// Binds `keys` (see `keymap::notation`) to a command line such as
+    /// `set-mode insert`. Arguments are kept as strings and converted to the
+    /// command's argument types when it runs.
+    pub fn bind_str(&mut self, modes: &[Mode], keys: &str, command_line: &str) -> Result<()> {
+        let seq: KeySequence = keys.parse()?;
+        let tokens = command_line::tokenize(command_line)?;
+        let (command, args) = tokens
+            .split_first()
+            .ok_or_else(|| anyhow!("empty command bound to `{}`", keys))?;
+
+        let args = (!args.is_empty()).then(|| args.iter().cloned().map(CommandArg::Str).collect());
+
+        self.bind(modes, seq, command, args);
+        Ok(())
+    }
+
+    /// Removes the binding for `keys`, so lower layers (or nothing) answer it.
+    pub fn unbind_str(&mut self, modes: &[Mode], keys: &str) -> Result<()> {
+        let seq: KeySequence = keys.parse()?;
+        for mode in modes.iter() {
+            if let Some(trie) = self.tries.get_mut(mode) {
+                trie.remove(&seq.chords);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn group(&mut self, modes: &[Mode], seq: KeySequence, label: &str) {
+        for mode in modes.iter() {
+            let node = self
+                .tries
+                .entry(*mode)
+                .or_default()
+                .get_or_insert(&seq.chords);
+            node.label = Some(label.to_string());
+        }
+    }
+
+    pub fn node(&self, mode: Mode, chords: &[KeyChord]) -> Option<&KeyTrie> {
+        self.tries.get(&mode)?.get(chords)
+    }
+
+    pub fn modes(&self) -> impl Iterator<Item = Mode> + '_ {
+        self.tries.keys().copied()
+    }
+
+    pub fn bindings(&self, mode: Mode) -> Vec<(KeySequence, &Binding)> {
+        self.tries
+            .get(&mode)
+            .map(|trie| {
+                trie.bindings()
+                    .into_iter()
+                    .map(|(chords, binding)| (KeySequence::new(chords), binding))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
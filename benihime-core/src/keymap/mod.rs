@@ -1,9 +1,28 @@
 pub mod default_keymap;
 pub mod key_chord;
+pub mod layer;
+pub mod notation;
+pub mod preset;
+pub mod trie;
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
-use crate::{command::CommandArg, editor::Mode, keymap::key_chord::KeyChord};
+use anyhow::{Result, anyhow};
+use benihime_renderer::event::Key;
+
+use crate::{
+    buffer::BufferId,
+    command::{CommandArg, command_registry::CommandRegistry},
+    editor::Mode,
+    keymap::{
+        key_chord::{KeyChord, KeyModifiers},
+        layer::KeymapLayer,
+        trie::KeyTrie,
+    },
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct KeySequence {
@@ -15,12 +34,6 @@ impl KeySequence {
         KeySequence { chords: seq }
     }
 
-    pub fn single(chord: KeyChord) -> Self {
-        KeySequence {
-            chords: vec![chord],
-        }
-    }
-
     pub fn default() -> Self {
         KeySequence { chords: vec![] }
     }
@@ -38,80 +51,416 @@ impl KeySequence {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub command: String,
+    pub args: Option<Vec<CommandArg>>,
+    pub source: String,
+}
+
+/// Outcome of feeding a chord to the keymap. A single chord can resolve several
+/// pending keys at once, so `push_key` returns them in the order they happened.
+#[derive(Debug, Clone)]
+pub enum KeymapResult {
+    Matched(Binding),
+    Pending,
+    NotFound(KeyChord),
+}
+
+#[derive(Debug, Clone)]
+struct BufferKeymaps {
+    local: KeymapLayer,
+    minor_modes: Vec<String>,
+}
+
+impl Default for BufferKeymaps {
+    fn default() -> Self {
+        Self {
+            local: KeymapLayer::new("buffer-local keymap"),
+            minor_modes: Vec::new(),
+        }
+    }
+}
+
+/// Keys are looked up through layers: the buffer-local keymap, then the minor
+/// modes enabled in that buffer (most recently pushed first), then the global
+/// keymap. The first layer with a binding for a sequence wins; a sequence is a
+/// prefix if any layer has longer bindings below it.
+///
+/// A transient keymap sits above all of them while active: its single-key
+/// bindings repeat until a key it doesn't bind (or `Esc`) ends it.
 #[derive(Debug, Clone)]
 pub struct Keymap {
-    pub bindings: HashMap<(KeySequence, Mode), (String, Option<Vec<CommandArg>>)>,
+    global: KeymapLayer,
+    minor_modes: HashMap<String, KeymapLayer>,
+    transients: HashMap<String, KeymapLayer>,
+    transient: Option<String>,
+    buffers: HashMap<BufferId, BufferKeymaps>,
     pub buffer: KeySequence,
+    pending: Option<(BufferId, Mode, Instant)>,
 }
 
 impl Keymap {
     pub fn new() -> Self {
         Self {
-            bindings: HashMap::new(),
+            global: KeymapLayer::new("global keymap"),
+            minor_modes: HashMap::new(),
+            transients: HashMap::new(),
+            transient: None,
+            buffers: HashMap::new(),
             buffer: KeySequence::default(),
+            pending: None,
         }
     }
-    pub fn bind(
-        &mut self,
-        modes: &[Mode],
-        seq: KeySequence,
-        command: &str,
-        args: Option<Vec<CommandArg>>,
-    ) {
-        for mode in modes.iter() {
-            self.bindings
-                .insert((seq.clone(), *mode), (command.to_string(), args.clone()));
+
+    pub fn bind_str(&mut self, modes: &[Mode], keys: &str, command_line: &str) -> Result<()> {
+        self.global.bind_str(modes, keys, command_line)
+    }
+
+    pub fn unbind_str(&mut self, modes: &[Mode], keys: &str) -> Result<()> {
+        self.global.unbind_str(modes, keys)
+    }
+
+    pub fn group(&mut self, modes: &[Mode], seq: KeySequence, label: &str) {
+        self.global.group(modes, seq, label);
+    }
+
+    /// The keymap of minor mode `name`, created on first use.
+    pub fn minor_mode_mut(&mut self, name: &str) -> &mut KeymapLayer {
+        self.minor_modes
+            .entry(name.to_string())
+            .or_insert_with(|| KeymapLayer::new(name))
+    }
+
+    pub fn minor_mode_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.minor_modes.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// The transient keymap `name`, created on first use.
+    pub fn transient_mut(&mut self, name: &str) -> &mut KeymapLayer {
+        self.transients
+            .entry(name.to_string())
+            .or_insert_with(|| KeymapLayer::new(name))
+    }
+
+    pub fn transient_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.transients.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn enter_transient(&mut self, name: &str) -> Result<()> {
+        if !self.transients.contains_key(name) {
+            return Err(anyhow!("Unknown transient keymap: {}", name));
+        }
+
+        self.transient = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn exit_transient(&mut self) -> Option<String> {
+        self.transient.take()
+    }
+
+    pub fn active_transient(&self) -> Option<&KeymapLayer> {
+        self.transients.get(self.transient.as_ref()?)
+    }
+
+    pub fn buffer_local_mut(&mut self, buffer: BufferId) -> &mut KeymapLayer {
+        &mut self.buffers.entry(buffer).or_default().local
+    }
+
+    pub fn push_layer(&mut self, buffer: BufferId, name: &str) -> Result<()> {
+        if !self.minor_modes.contains_key(name) {
+            return Err(anyhow!("Unknown keymap: {}", name));
+        }
+
+        let active = &mut self.buffers.entry(buffer).or_default().minor_modes;
+        active.retain(|active| active != name);
+        active.push(name.to_string());
+        Ok(())
+    }
+
+    /// Disables minor mode `name`, or the most recently pushed one when `None`.
+    pub fn pop_layer(&mut self, buffer: BufferId, name: Option<&str>) -> Result<String> {
+        let active = self
+            .buffers
+            .get_mut(&buffer)
+            .map(|keymaps| &mut keymaps.minor_modes)
+            .filter(|active| !active.is_empty())
+            .ok_or_else(|| anyhow!("No keymap layers active in this buffer"))?;
+
+        match name {
+            Some(name) => {
+                let index = active
+                    .iter()
+                    .position(|active| active == name)
+                    .ok_or_else(|| anyhow!("Keymap {} is not active in this buffer", name))?;
+                Ok(active.remove(index))
+            }
+            None => Ok(active.pop().unwrap_or_default()),
+        }
+    }
+
+    pub fn active_layers(&self, buffer: BufferId) -> Vec<&KeymapLayer> {
+        let mut layers = Vec::new();
+
+        if let Some(keymaps) = self.buffers.get(&buffer) {
+            layers.push(&keymaps.local);
+            layers.extend(
+                keymaps
+                    .minor_modes
+                    .iter()
+                    .rev()
+                    .filter_map(|name| self.minor_modes.get(name)),
+            );
+        }
+
+        layers.push(&self.global);
+        layers
+    }
+
+    pub fn remove_buffer(&mut self, buffer: BufferId) {
+        self.buffers.remove(&buffer);
+    }
+
+    fn nodes(&self, buffer: BufferId, mode: Mode, chords: &[KeyChord]) -> Vec<&KeyTrie> {
+        self.active_layers(buffer)
+            .into_iter()
+            .filter_map(|layer| layer.node(mode, chords))
+            .collect()
+    }
+
+    /// Chords that can follow `prefix` in `mode`, labelled with the command they
+    /// run or, for longer sequences, the group label of the next prefix.
+    pub fn continuations(
+        &self,
+        buffer: BufferId,
+        mode: Mode,
+        prefix: &KeySequence,
+    ) -> Vec<(KeyChord, String)> {
+        let mut next: Vec<(KeyChord, String)> = Vec::new();
+
+        for node in self.nodes(buffer, mode, &prefix.chords) {
+            for (chord, child) in &node.children {
+                if next.iter().any(|(seen, _)| seen == chord) {
+                    continue;
+                }
+
+                let label = match (&child.binding, &child.label) {
+                    (Some(binding), _) if child.is_leaf() => binding.command.clone(),
+                    (Some(binding), Some(label)) => format!("{} / +{}", binding.command, label),
+                    (Some(binding), None) => format!("{} / +prefix", binding.command),
+                    (None, Some(label)) => format!("+{}", label),
+                    (None, None) => "+prefix".to_string(),
+                };
+                next.push((chord.clone(), label));
+            }
+        }
+
+        next.sort_by_key(|(chord, _)| chord.to_string());
+        next
+    }
+
+    pub fn lookup(&self, buffer: BufferId, mode: Mode, seq: &KeySequence) -> Option<&Binding> {
+        self.nodes(buffer, mode, &seq.chords)
+            .into_iter()
+            .find_map(|node| node.binding.as_ref())
+    }
+
+    pub fn is_prefix(&self, buffer: BufferId, mode: Mode, seq: &KeySequence) -> bool {
+        self.nodes(buffer, mode, &seq.chords)
+            .iter()
+            .any(|node| !node.is_leaf())
+    }
+
+    /// Effective bindings in `buffer`, each paired with the layer it comes from.
+    /// Bindings shadowed by a higher layer are left out.
+    pub fn effective_bindings(&self, buffer: BufferId, mode: Mode) -> Vec<(KeySequence, &Binding)> {
+        let mut found: Vec<(KeySequence, &Binding)> = Vec::new();
+
+        for layer in self.active_layers(buffer) {
+            for (seq, binding) in layer.bindings(mode) {
+                if !found.iter().any(|(seen, _)| *seen == seq) {
+                    found.push((seq, binding));
+                }
+            }
         }
+
+        found.sort_by_key(|(seq, _)| seq.to_string());
+        found
+    }
+
+    pub fn bindings_for(&self, buffer: BufferId, command: &str) -> Vec<(KeySequence, Mode)> {
+        let mut modes: Vec<Mode> = self
+            .active_layers(buffer)
+            .iter()
+            .flat_map(|layer| layer.modes())
+            .collect();
+        modes.sort_by_key(|mode| *mode as u8);
+        modes.dedup();
+
+        let mut found: Vec<(KeySequence, Mode)> = Vec::new();
+        for mode in modes {
+            found.extend(
+                self.effective_bindings(buffer, mode)
+                    .into_iter()
+                    .filter(|(_, binding)| binding.command == command)
+                    .map(|(seq, _)| (seq, mode)),
+            );
+        }
+
+        found
+    }
+
+    /// When the pending sequence should be resolved without waiting for another
+    /// key. Only ambiguous sequences (bound *and* a prefix) and sequences typed in
+    /// text-entry modes time out; a plain prefix like `Ctrl+x` waits indefinitely.
+    pub fn deadline(&self, timeout: Duration) -> Option<Instant> {
+        let (buffer, mode, since) = self.pending?;
+
+        let ambiguous = self.lookup(buffer, mode, &self.buffer).is_some();
+        let text_entry = matches!(mode, Mode::Insert | Mode::Command | Mode::Minibuffer);
+
+        (ambiguous || text_entry).then(|| since + timeout)
     }
 
     pub fn push_key(
         &mut self,
+        buffer: BufferId,
         mode: Mode,
         chord: &KeyChord,
-    ) -> Option<(String, Option<Vec<CommandArg>>)> {
-        self.buffer.chords.push(chord.clone());
+    ) -> Vec<KeymapResult> {
+        let mut out = Vec::new();
 
-        let current_seq = KeySequence::new(self.buffer.chords.clone());
+        if self.buffer.chords.is_empty()
+            && let Some(transient) = self.active_transient()
+        {
+            let binding = transient
+                .node(mode, std::slice::from_ref(chord))
+                .and_then(|node| node.binding.clone());
+
+            if let Some(binding) = binding {
+                out.push(KeymapResult::Matched(binding));
+                return out;
+            }
 
-        if let Some(binding) = self.bindings.get(&(current_seq, mode)) {
-            self.buffer.chords.clear();
-            return Some(binding.clone());
+            self.transient = None;
+            if chord.code == Key::Esc && chord.modifiers == KeyModifiers::NONE {
+                return out;
+            }
         }
 
-        let is_prefix = self
-            .bindings
-            .keys()
-            .any(|seq| seq.0.chords.starts_with(&self.buffer.chords));
+        self.step(buffer, mode, chord.clone(), &mut out);
+        out
+    }
+
+    /// Resolves whatever is pending as if no further key will arrive.
+    pub fn timeout(&mut self) -> Vec<KeymapResult> {
+        let mut out = Vec::new();
+
+        if let Some((buffer, mode, _)) = self.pending.take() {
+            let pending = std::mem::take(&mut self.buffer.chords);
+            self.flush(buffer, mode, pending, &mut out);
+        }
+
+        out
+    }
+
+    fn step(&mut self, buffer: BufferId, mode: Mode, chord: KeyChord, out: &mut Vec<KeymapResult>) {
+        self.buffer.chords.push(chord);
+
+        let is_prefix = self.is_prefix(buffer, mode, &self.buffer);
+        let binding = self.lookup(buffer, mode, &self.buffer).cloned();
 
         if is_prefix {
-        } else {
-            self.buffer.chords.clear();
+            self.pending = Some((buffer, mode, Instant::now()));
+            out.push(KeymapResult::Pending);
+            return;
+        }
+
+        let pending = std::mem::take(&mut self.buffer.chords);
+        self.pending = None;
+
+        match binding {
+            Some(binding) => out.push(KeymapResult::Matched(binding)),
+            None => self.flush(buffer, mode, pending, out),
         }
-        None
     }
 
-    pub fn render(&self) -> String {
-        let mut lines: Vec<String> = Vec::new();
+    /// Resolves a sequence that cannot be extended any further: the longest bound
+    /// prefix fires (or the first chord falls through unbound) and the remaining
+    /// chords are fed again from the root.
+    fn flush(
+        &mut self,
+        buffer: BufferId,
+        mode: Mode,
+        pending: Vec<KeyChord>,
+        out: &mut Vec<KeymapResult>,
+    ) {
+        if pending.is_empty() {
+            return;
+        }
 
-        let mut mode_map: HashMap<Mode, Vec<(&KeySequence, &String)>> = HashMap::new();
+        let matched = (1..=pending.len()).rev().find_map(|len| {
+            let seq = KeySequence::new(pending[..len].to_vec());
+            self.lookup(buffer, mode, &seq)
+                .cloned()
+                .map(|binding| (len, binding))
+        });
 
-        for ((seq, mode), (command, _args)) in &self.bindings {
-            mode_map.entry(*mode).or_default().push((seq, command));
+        let consumed = match matched {
+            Some((len, binding)) => {
+                out.push(KeymapResult::Matched(binding));
+                len
+            }
+            None => {
+                out.push(KeymapResult::NotFound(pending[0].clone()));
+                1
+            }
+        };
+
+        for chord in pending.into_iter().skip(consumed) {
+            self.step(buffer, mode, chord, out);
         }
+    }
+
+    pub fn render(&self, buffer: BufferId, registry: &CommandRegistry) -> String {
+        let mut lines: Vec<String> = Vec::new();
 
-        let mut modes: Vec<Mode> = mode_map.keys().copied().collect();
+        let layers = self.active_layers(buffer);
+        lines.push(format!(
+            "Layers: {}",
+            self.active_transient()
+                .into_iter()
+                .chain(layers.iter().copied())
+                .map(|layer| layer.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" > ")
+        ));
+        lines.push(String::new());
+
+        let mut modes: Vec<Mode> = layers.iter().flat_map(|layer| layer.modes()).collect();
         modes.sort_by_key(|m| *m as u8);
+        modes.dedup();
 
         for mode in modes {
             lines.push(format!("=== {:?} mode ===", mode));
 
-            if let Some(bindings) = mode_map.get(&mode) {
-                let mut sorted_bindings = bindings.clone();
-                sorted_bindings.sort_by_key(|(seq, _)| seq.to_string());
-
-                for (seq, command_name) in sorted_bindings {
-                    lines.push(format!("{:<20} => {}", seq.to_string(), command_name));
-                }
+            for (seq, binding) in self.effective_bindings(buffer, mode) {
+                let doc = registry
+                    .commands
+                    .get(binding.command.as_str())
+                    .map_or("", |entry| entry.doc.as_str());
+                lines.push(format!(
+                    "{:<20} => {:<28} {:<20} {}",
+                    seq.to_string(),
+                    binding.command,
+                    binding.source,
+                    doc
+                ));
             }
 
             lines.push(String::new());
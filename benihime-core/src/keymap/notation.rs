@@ -0,0 +1,359 @@
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use benihime_renderer::event::Key;
+
+use crate::keymap::{
+    KeySequence,
+    key_chord::{KeyChord, KeyModifiers},
+};
+
+/// Canonical name of every non-character key, used by `to_string`.
+pub fn key_name(key: &Key) -> String {
+    match key {
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::F(n) => format!("F{}", n),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Enter => "Enter".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PageUp".to_string(),
+        Key::PageDown => "PageDown".to_string(),
+        Key::Tab => "Tab".to_string(),
+        Key::BackTab => "BackTab".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Insert => "Insert".to_string(),
+        Key::Null => "Null".to_string(),
+        Key::Esc => "Esc".to_string(),
+        Key::CapsLock => "CapsLock".to_string(),
+        Key::ScrollLock => "ScrollLock".to_string(),
+        Key::NumLock => "NumLock".to_string(),
+        Key::PrintScreen => "PrintScreen".to_string(),
+        Key::Pause => "Pause".to_string(),
+        Key::Other => "Other".to_string(),
+    }
+}
+
+fn parse_key_name(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+
+    let key = match name.to_ascii_lowercase().as_str() {
+        "space" | "spc" => Key::Char(' '),
+        "lt" => Key::Char('<'),
+        "gt" => Key::Char('>'),
+        "bar" => Key::Char('|'),
+        "bslash" => Key::Char('\\'),
+        "minus" => Key::Char('-'),
+        "plus" => Key::Char('+'),
+        "backspace" | "bs" => Key::Backspace,
+        "enter" | "return" | "ret" | "cr" => Key::Enter,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" | "pgup" | "prior" => Key::PageUp,
+        "pagedown" | "pgdn" | "next" => Key::PageDown,
+        "tab" => Key::Tab,
+        "backtab" => Key::BackTab,
+        "delete" | "del" => Key::Delete,
+        "insert" | "ins" => Key::Insert,
+        "null" | "nul" => Key::Null,
+        "esc" | "escape" => Key::Esc,
+        "capslock" => Key::CapsLock,
+        "scrolllock" => Key::ScrollLock,
+        "numlock" => Key::NumLock,
+        "printscreen" | "print" => Key::PrintScreen,
+        "pause" => Key::Pause,
+        "other" => Key::Other,
+        lower => {
+            let n: u8 = lower.strip_prefix('f')?.parse().ok()?;
+            if n == 0 {
+                return None;
+            }
+            Key::F(n)
+        }
+    };
+
+    Some(key)
+}
+
+fn canonical_modifier(name: &str, modifiers: &mut KeyModifiers) -> bool {
+    match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => modifiers.control = true,
+        "alt" | "meta" | "option" => modifiers.alt = true,
+        "shift" => modifiers.shift = true,
+        "super" | "cmd" | "command" | "win" => modifiers.super_key = true,
+        _ => return false,
+    }
+    true
+}
+
+fn emacs_modifier(prefix: char, modifiers: &mut KeyModifiers) -> bool {
+    match prefix {
+        'C' => modifiers.control = true,
+        'M' | 'A' => modifiers.alt = true,
+        'S' => modifiers.shift = true,
+        's' | 'D' => modifiers.super_key = true,
+        _ => return false,
+    }
+    true
+}
+
+/// Keys arrive from the renderer as lowercase letters plus Shift, so `G` and
+/// `S-g` both mean the same chord.
+fn chord(key: Key, mut modifiers: KeyModifiers) -> KeyChord {
+    let code = match key {
+        Key::Char(c) if c.is_ascii_uppercase() => {
+            modifiers.shift = true;
+            Key::Char(c.to_ascii_lowercase())
+        }
+        key => key,
+    };
+
+    KeyChord {
+        code,
+        modifiers,
+        text: None,
+    }
+}
+
+fn parse_emacs(text: &str) -> Option<KeyChord> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = text;
+
+    while let Some((prefix, tail)) = rest.split_once('-') {
+        let mut chars = prefix.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            break;
+        };
+        if tail.is_empty() || !emacs_modifier(c, &mut modifiers) {
+            break;
+        }
+        rest = tail;
+    }
+
+    parse_key_name(rest).map(|key| chord(key, modifiers))
+}
+
+fn parse_canonical(text: &str) -> Option<KeyChord> {
+    let (mods, key) = match text.strip_suffix("++") {
+        Some(mods) => (mods, "+"),
+        None => text.rsplit_once('+')?,
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for name in mods.split('+') {
+        if !canonical_modifier(name, &mut modifiers) {
+            return None;
+        }
+    }
+
+    parse_key_name(key).map(|key| chord(key, modifiers))
+}
+
+fn parse_vim(text: &str) -> Option<KeyChord> {
+    let inner = text.strip_prefix('<')?.strip_suffix('>')?;
+    if inner.is_empty() {
+        return None;
+    }
+    parse_emacs(inner)
+}
+
+fn parse_chord(text: &str) -> Option<KeyChord> {
+    if text.is_empty() {
+        return None;
+    }
+
+    parse_vim(text)
+        .or_else(|| parse_canonical(text))
+        .or_else(|| parse_emacs(text))
+}
+
+/// Whether `text` starts with a modifier, as in `C-x`, `S-Tab` or `Ctrl+x`.
+fn has_modifier(text: &str) -> bool {
+    let mut modifiers = KeyModifiers::NONE;
+
+    if let Some((prefix, tail)) = text.split_once('-')
+        && !tail.is_empty()
+    {
+        let mut chars = prefix.chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && emacs_modifier(c, &mut modifiers)
+        {
+            return true;
+        }
+    }
+
+    text.split_once('+')
+        .is_some_and(|(name, tail)| !tail.is_empty() && canonical_modifier(name, &mut modifiers))
+}
+
+/// Splits a word like `<C-w>v` or `gg` into chords.
+fn parse_run(word: &str) -> Option<Vec<KeyChord>> {
+    let mut chords = Vec::new();
+    let mut rest = word;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && let Some(parsed) = parse_vim(&rest[..=end])
+        {
+            chords.push(parsed);
+            rest = &rest[end + 1..];
+            continue;
+        }
+
+        chords.push(chord(Key::Char(c), KeyModifiers::NONE));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    (!chords.is_empty()).then_some(chords)
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_chord(s.trim()).ok_or_else(|| anyhow!("invalid key: `{}`", s))
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chords = Vec::new();
+
+        for word in s.split_whitespace() {
+            let single = word.chars().nth(1).is_none();
+            match parse_chord(word).filter(|_| single || has_modifier(word)) {
+                Some(parsed) => chords.push(parsed),
+                None => chords.extend(
+                    parse_run(word).ok_or_else(|| anyhow!("invalid key sequence: `{}`", s))?,
+                ),
+            }
+        }
+
+        if chords.is_empty() {
+            return Err(anyhow!("empty key sequence"));
+        }
+
+        Ok(KeySequence::new(chords))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_keys() -> Vec<Key> {
+        let mut keys = vec![
+            Key::Backspace,
+            Key::Enter,
+            Key::Left,
+            Key::Right,
+            Key::Up,
+            Key::Down,
+            Key::Home,
+            Key::End,
+            Key::PageUp,
+            Key::PageDown,
+            Key::Tab,
+            Key::BackTab,
+            Key::Delete,
+            Key::Insert,
+            Key::Null,
+            Key::Esc,
+            Key::CapsLock,
+            Key::ScrollLock,
+            Key::NumLock,
+            Key::PrintScreen,
+            Key::Pause,
+            Key::Other,
+        ];
+        keys.extend((1..=35).map(Key::F));
+        keys.extend(
+            ('a'..='z')
+                .chain('0'..='9')
+                .chain(" <>|\\-+=,.;:'\"/?!@#$%^&*()[]{}`~_".chars())
+                .chain(['é', 'ß', '日', '🎉'])
+                .map(Key::Char),
+        );
+        keys
+    }
+
+    fn all_modifiers() -> Vec<KeyModifiers> {
+        (0..16)
+            .map(|bits| KeyModifiers {
+                control: bits & 1 != 0,
+                alt: bits & 2 != 0,
+                shift: bits & 4 != 0,
+                super_key: bits & 8 != 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chord_round_trips_through_to_string() {
+        for key in all_keys() {
+            for modifiers in all_modifiers() {
+                let chord = KeyChord::new(key).with_modifiers(
+                    modifiers.control,
+                    modifiers.shift,
+                    modifiers.alt,
+                    modifiers.super_key,
+                );
+                let text = chord.to_string();
+                let parsed: KeyChord = text
+                    .parse()
+                    .unwrap_or_else(|e| panic!("`{}` doesn't parse: {}", text, e));
+                assert_eq!(parsed, chord, "`{}`", text);
+            }
+        }
+    }
+
+    fn keys(s: &str) -> Vec<KeyChord> {
+        s.parse::<KeySequence>().unwrap().chords
+    }
+
+    fn plain(c: char) -> KeyChord {
+        KeyChord::new(Key::Char(c))
+    }
+
+    fn ctrl(c: char) -> KeyChord {
+        KeyChord::new(Key::Char(c)).with_modifiers(true, false, false, false)
+    }
+
+    fn shift(key: Key) -> KeyChord {
+        KeyChord::new(key).with_modifiers(false, true, false, false)
+    }
+
+    #[test]
+    fn bare_words_are_runs_of_keys() {
+        for word in ["gt", "cr", "up", "end", "tab", "bs", "gg", "dw"] {
+            assert_eq!(keys(word), word.chars().map(plain).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn key_names_need_brackets_or_modifiers() {
+        let enter = KeyChord::new(Key::Enter);
+        assert_eq!(keys("<CR>"), vec![enter.clone()]);
+        assert_eq!(keys("<gt>"), vec![plain('>')]);
+        assert_eq!(keys("g<Enter>"), vec![plain('g'), enter]);
+        assert_eq!(keys("S-Tab"), vec![shift(Key::Tab)]);
+        assert_eq!(keys("C-x Ctrl+f"), vec![ctrl('x'), ctrl('f')]);
+        assert_eq!(keys("S->"), vec![shift(Key::Char('>'))]);
+        assert_eq!(keys("a-b"), vec![plain('a'), plain('-'), plain('b')]);
+    }
+}
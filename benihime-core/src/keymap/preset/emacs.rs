@@ -0,0 +1,88 @@
+use super::{BindingTable, COMMAND, GroupTable, INSERT, INSERT_VISUAL, VISUAL};
+
+pub const BINDINGS: BindingTable = &[
+    (INSERT, "M-x", "find-command"),
+    (INSERT, "M-S-;", "set-mode command"),
+    (COMMAND, "<Esc>", "set-mode insert"),
+    (COMMAND, "C-g", "set-mode insert"),
+    // Movement
+    (INSERT_VISUAL, "C-f", "move-right"),
+    (INSERT_VISUAL, "C-b", "move-left"),
+    (INSERT_VISUAL, "C-n", "move-down"),
+    (INSERT_VISUAL, "C-p", "move-up"),
+    (INSERT_VISUAL, "<Right>", "move-right"),
+    (INSERT_VISUAL, "<Left>", "move-left"),
+    (INSERT_VISUAL, "<Down>", "move-down"),
+    (INSERT_VISUAL, "<Up>", "move-up"),
+    (INSERT_VISUAL, "C-a", "beginning-of-line"),
+    (INSERT_VISUAL, "C-e", "end-of-line"),
+    (INSERT_VISUAL, "M-m", "first-non-blank"),
+    (INSERT_VISUAL, "M-f", "word-forward"),
+    (INSERT_VISUAL, "M-b", "word-backward"),
+    (INSERT_VISUAL, "M-S-,", "goto-first-line"),
+    (INSERT_VISUAL, "M-S-.", "goto-last-line"),
+    (INSERT_VISUAL, "C-v", "scroll-full-down"),
+    (INSERT_VISUAL, "M-v", "scroll-full-up"),
+    (INSERT, "C-l", "center-cursor"),
+    // Editing
+    (INSERT, "C-d", "delete-range"),
+    (INSERT, "M-d", "apply-operator delete-range word-end"),
+    (INSERT, "C-o", "open-above"),
+    (INSERT, "C-/", "undo"),
+    (INSERT, "C-S-?", "redo"),
+    (INSERT, "C-x u", "undo-tree-show"),
+    // The region
+    (INSERT, "C-<Space>", "enter-visual-mode"),
+    (VISUAL, "C-<Space>", "exit-visual-mode"),
+    (VISUAL, "C-g", "exit-visual-mode"),
+    (VISUAL, "<Esc>", "exit-visual-mode"),
+    (VISUAL, "C-x C-x", "visual_select_other_end"),
+    (VISUAL, "C-w", "delete-selection"),
+    // Files, buffers and windows
+    (INSERT, "C-x C-f", "open-file"),
+    (INSERT, "C-x C-s", "save-current-buffer"),
+    (INSERT, "C-x b", "find-buffer"),
+    (INSERT, "C-x k", "kill-this-buffer"),
+    (INSERT, "C-x <Right>", "next-buffer"),
+    (INSERT, "C-x <Left>", "previous-buffer"),
+    (INSERT, "C-x 2", "split-window-horizontal"),
+    (INSERT, "C-x 3", "split-window-vertical"),
+    (INSERT, "C-x o", "focus-right"),
+    (INSERT, "C-x 0", "close-window"),
+    (INSERT, "C-x 1", "only-window"),
+    (INSERT, "C-x S-^", "resize-window height 10"),
+    (INSERT, "C-x S-}", "resize-window width 10"),
+    (INSERT, "C-x S-{", "resize-window width -10"),
+    (INSERT, "C-x S-+", "equalize-windows"),
+    (INSERT, "C-x 4 b", "split-window-buffer"),
+    (INSERT, "C-x t 2", "new-tab"),
+    (INSERT, "C-x t 0", "close-tab"),
+    (INSERT, "C-x t o", "next-tab"),
+    (INSERT, "C-x t O", "previous-tab"),
+    (INSERT, "C-x t r", "rename-tab"),
+    (INSERT, "C-x t m", "move-tab 1"),
+    (INSERT, "C-x t M", "move-tab -1"),
+    (INSERT, "C-x C-c", "quit"),
+    (INSERT, "C-x p p", "open-project"),
+    (INSERT, "C-x p f", "find-file"),
+    (INSERT, "C-x p k", "kill-current-project"),
+    (INSERT, "M-s o", "live-grep"),
+    (INSERT, "C-h k", "describe-key"),
+    (INSERT, "C-h f", "describe-command"),
+    // Transient keymaps
+    (INSERT, "C-c r b", "enter-transient buffers"),
+    (INSERT, "C-c r p", "enter-transient projects"),
+    (INSERT, "C-c r u", "enter-transient undo"),
+    (INSERT, "C-x .", "enter-transient windows"),
+];
+
+pub const GROUPS: GroupTable = &[
+    (INSERT, "C-x", "ctl-x"),
+    (INSERT, "C-x p", "project"),
+    (INSERT, "C-x 4", "other-window"),
+    (INSERT, "C-x t", "tab"),
+    (INSERT, "M-s", "search"),
+    (INSERT, "C-h", "help"),
+    (INSERT, "C-c r", "repeat"),
+    (VISUAL, "C-x", "ctl-x"),
+];
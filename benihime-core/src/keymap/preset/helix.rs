@@ -0,0 +1,103 @@
+use super::{BindingTable, COMMAND, GroupTable, INSERT, NORMAL, NORMAL_VISUAL, VISUAL};
+
+pub const BINDINGS: BindingTable = &[
+    (NORMAL, "i", "set-mode insert"),
+    (NORMAL, "S-:", "set-mode command"),
+    (NORMAL, "v", "enter-visual-mode"),
+    (INSERT, "<Esc>", "set-mode normal"),
+    (COMMAND, "<Esc>", "set-mode normal"),
+    // Movement
+    (NORMAL_VISUAL, "h", "move-left"),
+    (NORMAL_VISUAL, "j", "move-down"),
+    (NORMAL_VISUAL, "k", "move-up"),
+    (NORMAL_VISUAL, "l", "move-right"),
+    (NORMAL, "C-d", "scroll-half-down"),
+    (NORMAL, "C-u", "scroll-half-up"),
+    (NORMAL, "C-f", "scroll-full-down"),
+    (NORMAL, "C-b", "scroll-full-up"),
+    (NORMAL, "zz", "center-cursor"),
+    // Word motions select the word they move over
+    (NORMAL_VISUAL, "w", "word-forward"),
+    (NORMAL_VISUAL, "b", "word-backward"),
+    (NORMAL_VISUAL, "e", "word-end"),
+    (NORMAL_VISUAL, "W", "long-word-forward"),
+    (NORMAL_VISUAL, "B", "long-word-backward"),
+    (NORMAL_VISUAL, "E", "long-word-forward-end"),
+    // Goto mode
+    (NORMAL_VISUAL, "gg", "goto-first-line"),
+    (NORMAL_VISUAL, "ge", "goto-last-line"),
+    (NORMAL_VISUAL, "gh", "beginning-of-line"),
+    (NORMAL_VISUAL, "gl", "end-of-line"),
+    (NORMAL_VISUAL, "gs", "first-non-blank"),
+    (NORMAL, "gn", "next-buffer"),
+    (NORMAL, "gp", "previous-buffer"),
+    // Actions apply to the selection
+    (NORMAL, "d", "delete-range"),
+    (NORMAL, "c", "change-range"),
+    (VISUAL, "d", "delete-selection"),
+    (VISUAL, "c", "change-selection"),
+    (VISUAL, ";", "exit-visual-mode"),
+    (VISUAL, "v", "exit-visual-mode"),
+    (VISUAL, "<Esc>", "exit-visual-mode"),
+    (VISUAL, "M-;", "visual_select_other_end"),
+    (NORMAL, "o", "open-below"),
+    (NORMAL, "O", "open-above"),
+    (NORMAL, "u", "undo"),
+    (NORMAL, "M-u", "undo-earlier"),
+    (NORMAL, "M-U", "undo-later"),
+    (NORMAL, "U", "redo"),
+    // Space mode
+    (NORMAL, "<Space> f", "find-file"),
+    (NORMAL, "<Space> b", "find-buffer"),
+    (NORMAL, "<Space> /", "live-grep"),
+    (NORMAL, "<Space> ?", "find-command"),
+    (NORMAL, "<Space> p", "open-project"),
+    (NORMAL, "<Space> u", "undo-tree-show"),
+    (NORMAL, "<Space> t n", "new-tab"),
+    (NORMAL, "<Space> t c", "close-tab"),
+    (NORMAL, "<Space> t r", "rename-tab"),
+    (NORMAL, "<Space> t l", "next-tab"),
+    (NORMAL, "<Space> t h", "previous-tab"),
+    (NORMAL, "<Space> t S-<", "move-tab -1"),
+    (NORMAL, "<Space> t S->", "move-tab 1"),
+    // Windows
+    (NORMAL, "C-w s", "split-window-horizontal"),
+    (NORMAL, "C-w v", "split-window-vertical"),
+    (NORMAL, "C-w h", "focus-left"),
+    (NORMAL, "C-w j", "focus-down"),
+    (NORMAL, "C-w k", "focus-up"),
+    (NORMAL, "C-w l", "focus-right"),
+    (NORMAL, "C-w c", "close-window"),
+    (NORMAL, "C-w q", "close-window"),
+    (NORMAL, "C-w o", "only-window"),
+    (NORMAL, "C-w H", "swap-window-left"),
+    (NORMAL, "C-w J", "swap-window-down"),
+    (NORMAL, "C-w K", "swap-window-up"),
+    (NORMAL, "C-w L", "swap-window-right"),
+    (NORMAL, "C-w S->", "resize-window width 10"),
+    (NORMAL, "C-w S-<", "resize-window width -10"),
+    (NORMAL, "C-w S-+", "resize-window height 10"),
+    (NORMAL, "C-w -", "resize-window height -10"),
+    (NORMAL, "C-w =", "equalize-windows"),
+    (NORMAL, "C-w r", "rotate-layout"),
+    (NORMAL, "C-w z", "zoom-window"),
+    (NORMAL, "C-w b", "split-window-buffer"),
+    (NORMAL, "s-s", "save-current-buffer"),
+    (NORMAL, "s-j", "next-project"),
+    (NORMAL, "s-k", "previous-project"),
+    (NORMAL, "s-x", "kill-current-project"),
+    // Transient keymaps
+    (NORMAL, "<Space> r b", "enter-transient buffers"),
+    (NORMAL, "<Space> r p", "enter-transient projects"),
+    (NORMAL, "<Space> r u", "enter-transient undo"),
+    (NORMAL, "C-w .", "enter-transient windows"),
+];
+
+pub const GROUPS: GroupTable = &[
+    (NORMAL_VISUAL, "g", "goto"),
+    (NORMAL, "z", "view"),
+    (NORMAL, "<Space>", "space"),
+    (NORMAL, "<Space> r", "repeat"),
+    (NORMAL, "<Space> t", "tab"),
+    (NORMAL, "C-w", "window"),
+];
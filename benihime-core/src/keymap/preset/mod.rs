@@ -0,0 +1,76 @@
+pub mod emacs;
+pub mod helix;
+pub mod vim;
+
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::editor::Mode;
+
+pub const NORMAL: &[Mode] = &[Mode::Normal];
+pub const VISUAL: &[Mode] = &[Mode::Visual];
+pub const NORMAL_VISUAL: &[Mode] = &[Mode::Normal, Mode::Visual];
+pub const INSERT: &[Mode] = &[Mode::Insert];
+pub const INSERT_VISUAL: &[Mode] = &[Mode::Insert, Mode::Visual];
+pub const COMMAND: &[Mode] = &[Mode::Command];
+pub const MINIBUFFER: &[Mode] = &[Mode::Minibuffer];
+
+/// `(modes, keys, command line)`. Keys use the notation in `keymap::notation`.
+pub type BindingTable = &'static [(&'static [Mode], &'static str, &'static str)];
+
+/// `(modes, prefix keys, label)` shown by the which-key popup.
+pub type GroupTable = &'static [(&'static [Mode], &'static str, &'static str)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapPreset {
+    /// Verb-object editing: operators like `d` wait for a motion (`dw`).
+    Vim,
+    /// Selection-first editing: motions select, then `d`/`c` act on the selection.
+    Helix,
+    /// Modeless editing: Insert is the resting mode and commands live on
+    /// `C-`/`M-` chords.
+    Emacs,
+}
+
+impl KeymapPreset {
+    /// The mode windows start in and return to after a command or prompt.
+    pub fn base_mode(&self) -> Mode {
+        match self {
+            KeymapPreset::Vim | KeymapPreset::Helix => Mode::Normal,
+            KeymapPreset::Emacs => Mode::Insert,
+        }
+    }
+
+    pub fn bindings(&self) -> BindingTable {
+        match self {
+            KeymapPreset::Vim => vim::BINDINGS,
+            KeymapPreset::Helix => helix::BINDINGS,
+            KeymapPreset::Emacs => emacs::BINDINGS,
+        }
+    }
+
+    pub fn groups(&self) -> GroupTable {
+        match self {
+            KeymapPreset::Vim => vim::GROUPS,
+            KeymapPreset::Helix => helix::GROUPS,
+            KeymapPreset::Emacs => emacs::GROUPS,
+        }
+    }
+}
+
+impl FromStr for KeymapPreset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "vim" => Ok(KeymapPreset::Vim),
+            "helix" => Ok(KeymapPreset::Helix),
+            "emacs" => Ok(KeymapPreset::Emacs),
+            _ => Err(anyhow!(
+                "Invalid keymap preset: {} (expected vim, helix or emacs)",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use super::{BindingTable, COMMAND, GroupTable, INSERT, NORMAL, NORMAL_VISUAL, VISUAL};
+
+pub const BINDINGS: BindingTable = &[
+    (NORMAL, "i", "set-mode insert"),
+    (NORMAL, "S-:", "set-mode command"),
+    (NORMAL, "v", "enter-visual-mode"),
+    (INSERT, "<Esc>", "set-mode normal"),
+    (INSERT, "jk", "set-mode normal"),
+    (COMMAND, "<Esc>", "set-mode normal"),
+    // Motions
+    (NORMAL_VISUAL, "h", "move-left"),
+    (NORMAL_VISUAL, "j", "move-down"),
+    (NORMAL_VISUAL, "k", "move-up"),
+    (NORMAL_VISUAL, "l", "move-right"),
+    (NORMAL_VISUAL, "0", "beginning-of-line"),
+    (NORMAL_VISUAL, "S-^", "first-non-blank"),
+    (NORMAL_VISUAL, "-", "first-non-blank"),
+    (NORMAL_VISUAL, "S-$", "end-of-line"),
+    (NORMAL_VISUAL, "=", "end-of-line"),
+    (NORMAL_VISUAL, "w", "word-forward"),
+    (NORMAL_VISUAL, "b", "word-backward"),
+    (NORMAL_VISUAL, "e", "word-end"),
+    (NORMAL_VISUAL, "W", "long-word-forward"),
+    (NORMAL_VISUAL, "B", "long-word-backward"),
+    (NORMAL_VISUAL, "E", "long-word-forward-end"),
+    (NORMAL, "gg", "goto-first-line"),
+    (NORMAL, "G", "goto-last-line"),
+    (NORMAL, "zz", "center-cursor"),
+    (NORMAL, "C-d", "scroll-half-down"),
+    (NORMAL, "C-u", "scroll-half-up"),
+    (NORMAL, "C-f", "scroll-full-down"),
+    (NORMAL, "C-b", "scroll-full-up"),
+    // Operators wait for a motion
+    (NORMAL, "x", "delete-range"),
+    (NORMAL, "dw", "apply-operator delete-range word-forward"),
+    (NORMAL, "de", "apply-operator delete-range word-end"),
+    (NORMAL, "db", "apply-operator delete-range word-backward"),
+    (
+        NORMAL,
+        "dW",
+        "apply-operator delete-range long-word-forward",
+    ),
+    (
+        NORMAL,
+        "dB",
+        "apply-operator delete-range long-word-backward",
+    ),
+    (NORMAL, "cw", "apply-operator change-range word-end"),
+    (NORMAL, "ce", "apply-operator change-range word-end"),
+    (NORMAL, "cb", "apply-operator change-range word-backward"),
+    (
+        NORMAL,
+        "cW",
+        "apply-operator change-range long-word-forward-end",
+    ),
+    (
+        NORMAL,
+        "cB",
+        "apply-operator change-range long-word-backward",
+    ),
+    (NORMAL, "o", "open-below"),
+    (NORMAL, "O", "open-above"),
+    (NORMAL, "u", "undo"),
+    (NORMAL, "C-r", "redo"),
+    (NORMAL, "g -", "undo-earlier"),
+    (NORMAL, "g S-+", "undo-later"),
+    (NORMAL, "g u", "undo-tree-show"),
+    // Visual mode
+    (VISUAL, "v", "exit-visual-mode"),
+    (VISUAL, "<Esc>", "exit-visual-mode"),
+    (VISUAL, "o", "visual_select_other_end"),
+    (VISUAL, "d", "delete-selection"),
+    (VISUAL, "x", "delete-selection"),
+    (VISUAL, "c", "change-selection"),
+    // Buffers, files and windows
+    (NORMAL, "J", "next-buffer"),
+    (NORMAL, "K", "previous-buffer"),
+    (NORMAL, "C-q", "kill-this-buffer"),
+    (NORMAL, "C-x C-f", "open-file"),
+    (NORMAL, "C-w s", "split-window-horizontal"),
+    (NORMAL, "C-w v", "split-window-vertical"),
+    (NORMAL, "C-w h", "focus-left"),
+    (NORMAL, "C-w j", "focus-down"),
+    (NORMAL, "C-w k", "focus-up"),
+    (NORMAL, "C-w l", "focus-right"),
+    (NORMAL, "C-w c", "close-window"),
+    (NORMAL, "C-w q", "close-window"),
+    (NORMAL, "C-w o", "only-window"),
+    (NORMAL, "C-w H", "swap-window-left"),
+    (NORMAL, "C-w J", "swap-window-down"),
+    (NORMAL, "C-w K", "swap-window-up"),
+    (NORMAL, "C-w L", "swap-window-right"),
+    (NORMAL, "C-w S->", "resize-window width 10"),
+    (NORMAL, "C-w S-<", "resize-window width -10"),
+    (NORMAL, "C-w S-+", "resize-window height 10"),
+    (NORMAL, "C-w -", "resize-window height -10"),
+    (NORMAL, "C-w =", "equalize-windows"),
+    (NORMAL, "C-w r", "rotate-layout"),
+    (NORMAL, "C-w z", "zoom-window"),
+    (NORMAL, "C-w b", "split-window-buffer"),
+    (NORMAL, "gt", "next-tab"),
+    (NORMAL, "gT", "previous-tab"),
+    (NORMAL, "C-w t n", "new-tab"),
+    (NORMAL, "C-w t c", "close-tab"),
+    (NORMAL, "C-w t r", "rename-tab"),
+    (NORMAL, "C-w t S-<", "move-tab -1"),
+    (NORMAL, "C-w t S->", "move-tab 1"),
+    (NORMAL, "C-h", "focus-left"),
+    (NORMAL, "C-j", "focus-down"),
+    (NORMAL, "C-k", "focus-up"),
+    (NORMAL, "C-l", "focus-right"),
+    (NORMAL, "s-p", "find-file"),
+    (NORMAL, "s-b", "find-buffer"),
+    (NORMAL, "s-s", "save-current-buffer"),
+    (NORMAL, "s-n", "open-project"),
+    (NORMAL, "s-x", "kill-current-project"),
+    (NORMAL, "s-j", "next-project"),
+    (NORMAL, "s-k", "previous-project"),
+    (NORMAL, "M-f", "live-grep"),
+    // Transient keymaps
+    (NORMAL, "<Space> b", "enter-transient buffers"),
+    (NORMAL, "<Space> p", "enter-transient projects"),
+    (NORMAL, "<Space> u", "enter-transient undo"),
+    (NORMAL, "C-w .", "enter-transient windows"),
+];
+
+pub const GROUPS: GroupTable = &[
+    (NORMAL, "g", "goto"),
+    (NORMAL, "d", "delete"),
+    (NORMAL, "c", "change"),
+    (NORMAL, "z", "view"),
+    (NORMAL, "C-x", "file"),
+    (NORMAL, "C-w", "window"),
+    (NORMAL, "C-w t", "tab"),
+    (NORMAL, "<Space>", "repeat"),
+];
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::keymap::{Binding, key_chord::KeyChord};
+
+/// One node per chord of a key sequence. A node can carry a binding and still
+/// have children, in which case the binding only fires once the sequence can
+/// no longer be extended (next chord doesn't match, or the key timeout expires).
+#[derive(Debug, Clone, Default)]
+pub struct KeyTrie {
+    pub binding: Option<Binding>,
+    pub label: Option<String>,
+    pub children: HashMap<KeyChord, KeyTrie>,
+}
+
+impl KeyTrie {
+    pub fn get(&self, chords: &[KeyChord]) -> Option<&KeyTrie> {
+        let mut node = self;
+        for chord in chords {
+            node = node.children.get(chord)?;
+        }
+        Some(node)
+    }
+
+    pub fn get_or_insert(&mut self, chords: &[KeyChord]) -> &mut KeyTrie {
+        let mut node = self;
+        for chord in chords {
+            node = node.children.entry(chord.clone()).or_default();
+        }
+        node
+    }
+
+    /// Drops the binding at `chords` and prunes nodes left with nothing in them.
+    pub fn remove(&mut self, chords: &[KeyChord]) -> Option<Binding> {
+        let Some((first, rest)) = chords.split_first() else {
+            return self.binding.take();
+        };
+
+        let child = self.children.get_mut(first)?;
+        let removed = child.remove(rest);
+        if child.binding.is_none() && child.label.is_none() && child.is_leaf() {
+            self.children.remove(first);
+        }
+        removed
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Every binding below this node, paired with its full chord path.
+    pub fn bindings(&self) -> Vec<(Vec<KeyChord>, &Binding)> {
+        let mut out = Vec::new();
+        self.collect(&mut Vec::new(), &mut out);
+        out
+    }
+
+    fn collect<'a>(
+        &'a self,
+        path: &mut Vec<KeyChord>,
+        out: &mut Vec<(Vec<KeyChord>, &'a Binding)>,
+    ) {
+        if let Some(binding) = &self.binding {
+            out.push((path.clone(), binding));
+        }
+
+        for (chord, child) in &self.children {
+            path.push(chord.clone());
+            child.collect(path, out);
+            path.pop();
+        }
+    }
+}
@@ -1,11 +1,19 @@
 use application::Application;
+use args::{Args, USAGE};
 
 mod application;
+mod args;
 mod buffer;
 mod chars;
 mod command;
+mod config;
+mod diff;
 mod editor;
+mod encoding;
+mod file_loader;
 mod graphics;
+mod help;
+mod hex;
 mod input_handler;
 mod keymap;
 mod macros;
@@ -13,16 +21,39 @@ mod mini_buffer;
 mod movement;
 mod position;
 mod project;
+mod session;
+mod swap;
 mod theme;
+mod transaction;
 mod tree;
 mod ui;
+mod undo_file;
+mod undo_view;
 mod undotree;
 mod window;
+mod writer;
 
 fn main() -> anyhow::Result<()> {
-    let window_config = benihime_renderer::WindowConfig::new("The Editor", false);
+    let args = Args::parse(std::env::args().skip(1))?;
+    if args.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    if args.version {
+        println!("benihime {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    // Async hooks and jobs run here.
+    let runtime = tokio::runtime::Runtime::new()?;
+    let _guard = runtime.enter();
 
-    let app = Application::new();
+    let mut app = Application::new(&args)?;
+    if args.headless {
+        return app.finish_headless();
+    }
+
+    let window_config = benihime_renderer::WindowConfig::new("The Editor", false);
 
     benihime_renderer::run(window_config, app)
         .map_err(|e| anyhow::anyhow!("Failed to run renderer: {}", e))
@@ -14,6 +14,14 @@ pub trait MiniBufferTrait {
     fn offset(&self) -> usize;
     fn len(&self) -> usize;
     fn filter_items(&mut self);
+
+    fn should_accept(&self) -> bool {
+        false
+    }
+
+    fn cancel(&mut self, _editor: &mut Editor) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub struct MiniBuffer<T> {
@@ -81,6 +89,7 @@ impl MiniBufferDisplay for &str {
 pub enum MinibufferCallbackResult {
     NewItems,
     Executed,
+    Rejected(String),
 }
 
 impl<T> MiniBufferTrait for MiniBuffer<T>
@@ -179,6 +188,257 @@ where
     }
 }
 
+pub type PromptCompletionFn = Box<dyn Fn(&str) -> Vec<String> + Send>;
+pub type PromptValidatorFn = Box<dyn Fn(&str) -> Result<()> + Send>;
+pub type PromptCallbackFn = Box<dyn FnOnce(&mut Editor, String) -> Result<()> + Send>;
+
+pub struct Prompt {
+    input: String,
+    prompt: String,
+    completions: Vec<String>,
+    index: usize,
+    offset: usize,
+    completion: Option<PromptCompletionFn>,
+    validator: Option<PromptValidatorFn>,
+    callback: Option<PromptCallbackFn>,
+}
+
+impl Prompt {
+    pub fn new(
+        prompt: &str,
+        callback: impl FnOnce(&mut Editor, String) -> Result<()> + Send + 'static,
+    ) -> Self {
+        Self {
+            input: String::new(),
+            prompt: prompt.to_string(),
+            completions: Vec::new(),
+            index: 0,
+            offset: 0,
+            completion: None,
+            validator: None,
+            callback: Some(Box::new(callback)),
+        }
+    }
+
+    pub fn with_input(mut self, input: &str) -> Self {
+        self.input = input.to_string();
+        self.filter_items();
+        self
+    }
+
+    pub fn with_completion(
+        mut self,
+        completion: impl Fn(&str) -> Vec<String> + Send + 'static,
+    ) -> Self {
+        self.completion = Some(Box::new(completion));
+        self.filter_items();
+        self
+    }
+
+    pub fn with_validator(
+        mut self,
+        validator: impl Fn(&str) -> Result<()> + Send + 'static,
+    ) -> Self {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+}
+
+impl MiniBufferTrait for Prompt {
+    fn render_candidates(&self) -> Vec<String> {
+        self.completions.clone()
+    }
+
+    fn move_focus(&mut self, delta: isize) {
+        let max_count = 10;
+        let len = self.completions.len();
+
+        if len == 0 {
+            return;
+        }
+
+        self.index = ((self.index as isize + delta).rem_euclid(len as isize)) as usize;
+        self.input = self.completions[self.index].clone();
+
+        if self.index < self.offset {
+            self.offset = self.index;
+        } else if self.index >= self.offset + max_count {
+            self.offset = self.index + 1 - max_count;
+        }
+    }
+
+    fn run_callback(&mut self, editor: &mut Editor) -> Result<MinibufferCallbackResult> {
+        if let Some(validator) = &self.validator
+            && let Err(e) = validator(&self.input)
+        {
+            return Ok(MinibufferCallbackResult::Rejected(e.to_string()));
+        }
+
+        if let Some(callback) = self.callback.take() {
+            callback(editor, self.input.clone())?;
+        }
+
+        Ok(MinibufferCallbackResult::Executed)
+    }
+
+    fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    fn input(&self) -> &String {
+        &self.input
+    }
+
+    fn input_mut(&mut self) -> &mut String {
+        &mut self.input
+    }
+
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn len(&self) -> usize {
+        self.completions.len()
+    }
+
+    fn filter_items(&mut self) {
+        if let Some(completion) = &self.completion {
+            self.completions = completion(&self.input);
+        }
+
+        self.index = 0;
+        self.offset = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    Yes,
+    No,
+    Cancel,
+}
+
+pub type ConfirmCallbackFn = Box<dyn FnOnce(&mut Editor, Confirmation) -> Result<()> + Send>;
+
+pub struct Confirm {
+    input: String,
+    prompt: String,
+    callback: Option<ConfirmCallbackFn>,
+}
+
+impl Confirm {
+    pub fn new(
+        prompt: &str,
+        callback: impl FnOnce(&mut Editor, Confirmation) -> Result<()> + Send + 'static,
+    ) -> Self {
+        Self {
+            input: String::new(),
+            prompt: format!("{prompt}(y)es, (n)o, (c)ancel: "),
+            callback: Some(Box::new(callback)),
+        }
+    }
+
+    fn answer(&self) -> Option<Confirmation> {
+        match self.input.trim().to_lowercase().as_str() {
+            "y" | "yes" => Some(Confirmation::Yes),
+            "n" | "no" => Some(Confirmation::No),
+            "c" | "cancel" => Some(Confirmation::Cancel),
+            _ => None,
+        }
+    }
+}
+
+impl MiniBufferTrait for Confirm {
+    fn render_candidates(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn move_focus(&mut self, _delta: isize) {}
+
+    fn run_callback(&mut self, editor: &mut Editor) -> Result<MinibufferCallbackResult> {
+        let Some(answer) = self.answer() else {
+            self.input.clear();
+            return Ok(MinibufferCallbackResult::Rejected(
+                "Please answer y, n or c".to_string(),
+            ));
+        };
+
+        if let Some(callback) = self.callback.take() {
+            callback(editor, answer)?;
+        }
+
+        Ok(MinibufferCallbackResult::Executed)
+    }
+
+    fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    fn input(&self) -> &String {
+        &self.input
+    }
+
+    fn input_mut(&mut self) -> &mut String {
+        &mut self.input
+    }
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn offset(&self) -> usize {
+        0
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    fn filter_items(&mut self) {}
+
+    fn should_accept(&self) -> bool {
+        self.answer().is_some()
+    }
+
+    fn cancel(&mut self, editor: &mut Editor) -> Result<()> {
+        if let Some(callback) = self.callback.take() {
+            callback(editor, Confirmation::Cancel)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn complete_path(input: &str) -> Vec<String> {
+    let (dir, prefix) = match input.rfind('/') {
+        Some(idx) => (&input[..=idx], &input[idx + 1..]),
+        None => ("", input),
+    };
+
+    let read_from = if dir.is_empty() { "." } else { dir };
+    let Some(entries) = std::fs::read_dir(read_from).ok() else {
+        return Vec::new();
+    };
+
+    let mut completions: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            if !name.starts_with(prefix) {
+                return None;
+            }
+            let suffix = if e.path().is_dir() { "/" } else { "" };
+            Some(format!("{dir}{name}{suffix}"))
+        })
+        .collect();
+
+    completions.sort();
+    completions
+}
+
 pub struct MiniBufferManager {
     pub current: Option<Box<dyn MiniBufferTrait>>,
 }
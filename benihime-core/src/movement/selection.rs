@@ -1,3 +1,7 @@
+use std::cmp::Ordering;
+
+use crate::transaction::{Assoc, ChangeSet};
+
 use super::movement::Direction;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -69,6 +73,21 @@ impl Range {
         self.from() <= pos && pos < self.to()
     }
 
+    /// The range covering the same text after `changes`. Text inserted at
+    /// either end stays outside of it; an empty range moves past it.
+    pub fn map(&self, changes: &ChangeSet) -> Self {
+        let (anchor, head) = match self.anchor.cmp(&self.head) {
+            Ordering::Equal => (Assoc::After, Assoc::After),
+            Ordering::Less => (Assoc::After, Assoc::Before),
+            Ordering::Greater => (Assoc::Before, Assoc::After),
+        };
+
+        Self {
+            anchor: changes.map_pos(self.anchor, anchor),
+            head: changes.map_pos(self.head, head),
+        }
+    }
+
     pub fn merge(&self, other: Self) -> Self {
         let start = self.from().min(other.from());
         let end = self.to().max(other.to());
@@ -1,9 +1,12 @@
 // project.rs
-use std::{collections::BTreeMap, path::PathBuf};
+use std::path::PathBuf;
 
-use crate::{buffer::BufferId, tree::Tree, window::Window};
+use anyhow::anyhow;
+
+use crate::{buffer::BufferId, graphics::Rect, project::tab::Tab};
 
 pub mod project_manager;
+pub mod tab;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ProjectId(u64);
@@ -14,6 +17,75 @@ pub struct Project {
     pub name: String,
     pub root: Option<PathBuf>,
     pub buffers: Vec<BufferId>,
-    pub tree: Tree,
-    pub windows: BTreeMap<BufferId, Window>,
+    pub tabs: Vec<Tab>,
+    pub current_tab: usize,
+}
+
+impl Project {
+    pub fn new(id: ProjectId, name: String, root: Option<PathBuf>, area: Rect) -> Self {
+        Self {
+            id,
+            name,
+            root,
+            buffers: Vec::new(),
+            tabs: vec![Tab::new("1", area)],
+            current_tab: 0,
+        }
+    }
+
+    pub fn tab(&self) -> &Tab {
+        &self.tabs[self.current_tab]
+    }
+
+    pub fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.current_tab]
+    }
+
+    /// Inserts an empty tab after the current one and switches to it.
+    pub fn new_tab(&mut self, name: Option<&str>) -> &mut Tab {
+        let area = self.tab().tree.area();
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => (self.tabs.len() + 1).to_string(),
+        };
+
+        self.current_tab += 1;
+        self.tabs.insert(self.current_tab, Tab::new(&name, area));
+        self.tab_mut()
+    }
+
+    pub fn close_tab(&mut self) -> anyhow::Result<Tab> {
+        if self.tabs.len() <= 1 {
+            return Err(anyhow!("Can't close the last tab"));
+        }
+
+        let tab = self.tabs.remove(self.current_tab);
+        self.current_tab = self.current_tab.min(self.tabs.len() - 1);
+        Ok(tab)
+    }
+
+    pub fn select_tab(&mut self, index: usize) -> anyhow::Result<()> {
+        if index >= self.tabs.len() {
+            return Err(anyhow!("No tab {}", index + 1));
+        }
+
+        self.current_tab = index;
+        Ok(())
+    }
+
+    /// Switches `offset` tabs forward (or back, when negative), wrapping around.
+    pub fn cycle_tab(&mut self, offset: isize) {
+        let len = self.tabs.len() as isize;
+        self.current_tab = (self.current_tab as isize + offset).rem_euclid(len) as usize;
+    }
+
+    /// Moves the current tab `offset` places, clamped to the ends of the tab bar.
+    pub fn move_tab(&mut self, offset: isize) {
+        let last = self.tabs.len() as isize - 1;
+        let target = (self.current_tab as isize + offset).clamp(0, last) as usize;
+
+        let tab = self.tabs.remove(self.current_tab);
+        self.tabs.insert(target, tab);
+        self.current_tab = target;
+    }
 }
@@ -1,7 +1,4 @@
-use std::{
-    collections::{BTreeMap, HashMap},
-    path::PathBuf,
-};
+use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::anyhow;
 
@@ -9,7 +6,6 @@ use crate::{
     buffer::BufferId,
     graphics::Rect,
     project::{Project, ProjectId},
-    tree::Tree,
 };
 
 pub const DEFAULT_PROJECT_ID: ProjectId = ProjectId(0);
@@ -27,14 +23,7 @@ impl ProjectManager {
         let mut projects = HashMap::new();
         let mut name_index = HashMap::new();
 
-        let default_project = Project {
-            id: DEFAULT_PROJECT_ID,
-            name: "empty".to_string(),
-            root: None,
-            buffers: Vec::new(),
-            tree: Tree::new(area),
-            windows: BTreeMap::new(),
-        };
+        let default_project = Project::new(DEFAULT_PROJECT_ID, "empty".to_string(), None, area);
 
         projects.insert(DEFAULT_PROJECT_ID, default_project);
         name_index.insert("empty".to_string(), DEFAULT_PROJECT_ID);
@@ -52,14 +41,7 @@ impl ProjectManager {
         let id = ProjectId(self.next_id);
         self.next_id += 1;
 
-        let project = Project {
-            id,
-            name: name.clone(),
-            root: Some(root),
-            buffers: Vec::new(),
-            tree: Tree::new(self.area),
-            windows: BTreeMap::new(),
-        };
+        let project = Project::new(id, name.clone(), Some(root), self.area);
 
         self.projects.insert(id, project);
         self.name_index.insert(name, id);
@@ -67,6 +49,28 @@ impl ProjectManager {
         id
     }
 
+    /// The project rooted at `root`, added under the directory's name when
+    /// there isn't one.
+    pub fn open_dir(&mut self, root: PathBuf) -> ProjectId {
+        let existing = self.projects.values().find(|project| {
+            project
+                .root
+                .as_ref()
+                .and_then(|r| r.canonicalize().ok())
+                .is_some_and(|r| r == root)
+        });
+        if let Some(project) = existing {
+            return project.id;
+        }
+
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .filter(|name| !self.name_index.contains_key(name))
+            .unwrap_or_else(|| root.display().to_string());
+        self.add(name, root)
+    }
+
     pub fn len(&self) -> usize {
         self.projects.len()
     }
@@ -85,8 +89,8 @@ impl ProjectManager {
 
     pub fn resize(&mut self, area: Rect) {
         self.area = area;
-        for project in self.projects.values_mut() {
-            project.tree.resize(area);
+        for tab in self.projects.values_mut().flat_map(|p| p.tabs.iter_mut()) {
+            tab.tree.resize(area);
         }
     }
 
@@ -142,6 +146,10 @@ impl ProjectManager {
         self.projects.values().collect()
     }
 
+    pub fn projects_mut(&mut self) -> impl Iterator<Item = &mut Project> {
+        self.projects.values_mut()
+    }
+
     pub fn get_projects_cloned(&self) -> Vec<Project> {
         self.projects.values().cloned().collect()
     }
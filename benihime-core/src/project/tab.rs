@@ -0,0 +1,28 @@
+use std::collections::BTreeMap;
+
+use crate::{buffer::BufferId, graphics::Rect, tree::Tree, window::Window};
+
+/// A window layout inside a project. Each tab remembers the last window state
+/// (cursor, scroll, mode) of every buffer shown in it.
+#[derive(Debug, Clone)]
+pub struct Tab {
+    pub name: String,
+    pub tree: Tree,
+    pub windows: BTreeMap<BufferId, Window>,
+}
+
+impl Tab {
+    pub fn new(name: &str, area: Rect) -> Self {
+        Self {
+            name: name.to_string(),
+            tree: Tree::new(area),
+            windows: BTreeMap::new(),
+        }
+    }
+
+    pub fn shows(&self, buf_id: BufferId) -> bool {
+        self.tree
+            .windows()
+            .any(|(window, _, _)| window.buffer_id == buf_id)
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use toml::{Table, Value};
+
+use crate::{
+    buffer::{BufferId, Position},
+    editor::Editor,
+    project::{Project, tab::Tab},
+    tree::{Layout, LayoutSnapshot},
+    window::Window,
+};
+
+pub const DEFAULT_SESSION: &str = "default";
+
+pub fn session_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.toml", name))
+}
+
+/// Names of the sessions saved in `dir`, sorted.
+pub fn list(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn save(editor: &Editor, path: &Path) -> Result<()> {
+    let mut projects = editor.project_manager.get_projects();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let projects: Vec<Value> = projects
+        .into_iter()
+        .filter_map(|project| save_project(editor, project))
+        .collect();
+
+    let mut session = Table::new();
+    session.insert(
+        "current-project".to_string(),
+        Value::String(editor.project_manager.current_name()),
+    );
+    session.insert("projects".to_string(), Value::Array(projects));
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string(&session)?)?;
+
+    Ok(())
+}
+
+fn save_project(editor: &Editor, project: &Project) -> Option<Value> {
+    let paths: Vec<Value> = project
+        .buffers
+        .iter()
+        .filter_map(|id| buffer_path(editor, *id))
+        .map(|path| path_value(path))
+        .collect();
+
+    if paths.is_empty() && project.root.is_none() {
+        return None;
+    }
+
+    let mut table = Table::new();
+    table.insert("name".to_string(), Value::String(project.name.clone()));
+    if let Some(root) = &project.root {
+        table.insert("root".to_string(), path_value(root));
+    }
+    table.insert(
+        "current-tab".to_string(),
+        Value::Integer(project.current_tab as i64),
+    );
+    table.insert("buffers".to_string(), Value::Array(paths));
+
+    let tabs = project
+        .tabs
+        .iter()
+        .map(|tab| save_tab(editor, tab))
+        .collect();
+    table.insert("tabs".to_string(), Value::Array(tabs));
+
+    Some(Value::Table(table))
+}
+
+fn save_tab(editor: &Editor, tab: &Tab) -> Value {
+    let mut table = Table::new();
+    table.insert("name".to_string(), Value::String(tab.name.clone()));
+
+    if let Some(layout) = save_layout(editor, tab.tree.snapshot()) {
+        table.insert("layout".to_string(), layout);
+    }
+
+    let windows = tab
+        .windows
+        .values()
+        .filter_map(|window| save_window(editor, window))
+        .map(Value::Table)
+        .collect();
+    table.insert("windows".to_string(), Value::Array(windows));
+
+    Value::Table(table)
+}
+
+fn save_layout(editor: &Editor, snapshot: LayoutSnapshot) -> Option<Value> {
+    match snapshot {
+        LayoutSnapshot::Window {
+            window,
+            weight,
+            focused,
+        } => {
+            let mut table = save_window(editor, &window)?;
+            table.insert("weight".to_string(), Value::Float(weight as f64));
+            table.insert("focused".to_string(), Value::Boolean(focused));
+            Some(Value::Table(table))
+        }
+        LayoutSnapshot::Container {
+            layout,
+            weight,
+            children,
+        } => {
+            let children: Vec<Value> = children
+                .into_iter()
+                .filter_map(|child| save_layout(editor, child))
+                .collect();
+            if children.is_empty() {
+                return None;
+            }
+
+            let split = match layout {
+                Layout::Horizontal => "horizontal",
+                Layout::Vertical => "vertical",
+            };
+
+            let mut table = Table::new();
+            table.insert("split".to_string(), Value::String(split.to_string()));
+            table.insert("weight".to_string(), Value::Float(weight as f64));
+            table.insert("children".to_string(), Value::Array(children));
+            Some(Value::Table(table))
+        }
+    }
+}
+
+fn save_window(editor: &Editor, window: &Window) -> Option<Table> {
+    let path = buffer_path(editor, window.buffer_id)?;
+
+    let mut table = Table::new();
+    table.insert("path".to_string(), path_value(path));
+    table.insert("row".to_string(), Value::Integer(window.cursor.row as i64));
+    table.insert("col".to_string(), Value::Integer(window.cursor.col as i64));
+    table.insert(
+        "scroll".to_string(),
+        Value::Integer(window.scroll_offset as i64),
+    );
+    table.insert(
+        "scroll-left".to_string(),
+        Value::Integer(window.scroll_left as i64),
+    );
+    Some(table)
+}
+
+fn buffer_path(editor: &Editor, id: BufferId) -> Option<&PathBuf> {
+    editor.buf(id)?.file_path.as_ref()
+}
+
+fn path_value(path: &Path) -> Value {
+    Value::String(path.display().to_string())
+}
+
+/// Reopens the session at `path` on top of the current state, skipping
+/// projects and files that no longer exist.
+pub fn restore(editor: &mut Editor, path: &Path) -> Result<Vec<String>> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| anyhow!("can't read session {}: {}", path.display(), e))?;
+    let session: Table = toml::from_str(&data)?;
+
+    let mut warnings = Vec::new();
+    let previous_project = editor.project_manager.current_id();
+
+    let projects = session
+        .get("projects")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    for project in &projects {
+        if let Err(e) = restore_project(editor, project, &mut warnings) {
+            warnings.push(format!("session: {}", e));
+        }
+    }
+
+    let current = session.get("current-project").and_then(Value::as_str);
+    let switched =
+        current.is_some_and(|name| editor.project_manager.switch_by_name(name).is_some());
+    if !switched {
+        editor.project_manager.switch_by_id(previous_project);
+    }
+
+    let project_id = editor.project_manager.current_id();
+    editor.switch_project(project_id);
+
+    Ok(warnings)
+}
+
+fn restore_project(editor: &mut Editor, value: &Value, warnings: &mut Vec<String>) -> Result<()> {
+    let name = value
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("project without a name"))?;
+    let root = value.get("root").and_then(Value::as_str).map(PathBuf::from);
+
+    if editor.project_manager.switch_by_name(name).is_none() {
+        let Some(root) = root else {
+            return Err(anyhow!("project '{}' has no root", name));
+        };
+        if !root.is_dir() {
+            return Err(anyhow!(
+                "skipping project '{}': {} no longer exists",
+                name,
+                root.display()
+            ));
+        }
+
+        let id = editor.project_manager.add(name.to_string(), root);
+        editor.project_manager.switch_by_id(id);
+    }
+
+    let mut buffers = HashMap::new();
+    let paths = value.get("buffers").and_then(Value::as_array);
+
+    for path in paths.into_iter().flatten().filter_map(Value::as_str) {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            warnings.push(format!("session: skipping missing file {}", path.display()));
+            continue;
+        }
+
+        let id = match editor.buffer_id_by_path(&path) {
+            Some(id) => {
+                let project = editor.project_manager.current_mut();
+                if !project.buffers.contains(&id) {
+                    project.buffers.push(id);
+                }
+                id
+            }
+            None => match editor.open_file(&path) {
+                Ok(id) => id,
+                Err(e) => {
+                    warnings.push(format!("session: {}", e));
+                    continue;
+                }
+            },
+        };
+        buffers.insert(path, id);
+    }
+
+    let area = editor.tree().area();
+    let mut tabs = Vec::new();
+    let saved_tabs = value.get("tabs").and_then(Value::as_array);
+
+    for saved in saved_tabs.into_iter().flatten() {
+        let name = saved.get("name").and_then(Value::as_str).unwrap_or("1");
+        let mut tab = Tab::new(name, area);
+
+        let windows = saved.get("windows").and_then(Value::as_array);
+        for window in windows.into_iter().flatten() {
+            if let Some(window) = restore_window(editor, window, &buffers) {
+                tab.windows.insert(window.buffer_id, window);
+            }
+        }
+
+        let layout = saved
+            .get("layout")
+            .and_then(|layout| restore_layout(editor, layout, &buffers));
+        if let Some(layout) = layout
+            && tab.tree.restore(layout)
+        {
+            tabs.push(tab);
+        }
+    }
+
+    if !tabs.is_empty() {
+        let current_tab = value
+            .get("current-tab")
+            .and_then(Value::as_integer)
+            .unwrap_or(0)
+            .clamp(0, tabs.len() as i64 - 1) as usize;
+
+        let project = editor.project_manager.current_mut();
+        project.tabs = tabs;
+        project.current_tab = current_tab;
+    }
+
+    Ok(())
+}
+
+fn restore_layout(
+    editor: &Editor,
+    value: &Value,
+    buffers: &HashMap<PathBuf, BufferId>,
+) -> Option<LayoutSnapshot> {
+    let weight = value
+        .get("weight")
+        .and_then(Value::as_float)
+        .filter(|weight| *weight > 0.0)
+        .unwrap_or(1.0) as f32;
+
+    let Some(split) = value.get("split").and_then(Value::as_str) else {
+        let window = restore_window(editor, value, buffers)?;
+        let focused = value
+            .get("focused")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        return Some(LayoutSnapshot::Window {
+            window,
+            weight,
+            focused,
+        });
+    };
+
+    let layout = match split {
+        "horizontal" => Layout::Horizontal,
+        _ => Layout::Vertical,
+    };
+    let children = value
+        .get("children")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|child| restore_layout(editor, child, buffers))
+        .collect();
+
+    Some(LayoutSnapshot::Container {
+        layout,
+        weight,
+        children,
+    })
+}
+
+/// Builds the window for a saved `path`, clamped to the file's contents.
+fn restore_window(
+    editor: &Editor,
+    value: &Value,
+    buffers: &HashMap<PathBuf, BufferId>,
+) -> Option<Window> {
+    let path = PathBuf::from(value.get("path")?.as_str()?);
+    let buf_id = *buffers.get(&path)?;
+    let buf = editor.buf(buf_id)?;
+
+    let get = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_integer)
+            .unwrap_or(0)
+            .max(0) as usize
+    };
+
+    let row = get("row").min(buf.line_count().saturating_sub(1));
+    let col = get("col").min(buf.line_len(row));
+
+    let mut window = Window::new(buf_id);
+    window.mode = editor.base_mode();
+    window.cursor = Position { row, col };
+    window.scroll_offset = get("scroll").min(row);
+    window.scroll_left = get("scroll-left").min(col);
+
+    Some(window)
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use benihime_event::{AsyncHook, send_blocking};
+use parking_lot::Mutex;
+use ropey::Rope;
+use tokio::{sync::mpsc::Sender, time::Instant};
+
+use crate::{encoding::Encoding, writer::write_atomic};
+
+/// How long edits must pause before their swaps are written.
+const SWAP_DELAY: Duration = Duration::from_secs(2);
+/// Longest a swap waits while edits keep coming.
+const SWAP_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// Buffer contents waiting to be written, by file path.
+type Pending = Arc<Mutex<BTreeMap<PathBuf, (Rope, Encoding)>>>;
+
+pub enum SwapEvent {
+    Edited {
+        path: PathBuf,
+        text: Rope,
+        encoding: Encoding,
+    },
+    /// The buffer was saved or reloaded.
+    Remove(PathBuf),
+}
+
+pub struct SwapFiles {
+    dir: PathBuf,
+    tx: Sender<SwapEvent>,
+    pending: Pending,
+}
+
+impl SwapFiles {
+    /// Starts the hooks writing swaps to `dir`, after edits and on panic.
+    pub fn new(dir: PathBuf) -> Self {
+        let pending = Pending::default();
+
+        let previous = std::panic::take_hook();
+        let (panic_dir, panic_pending) = (dir.clone(), pending.clone());
+        std::panic::set_hook(Box::new(move |info| {
+            write_pending(&panic_dir, &panic_pending);
+            previous(info);
+        }));
+
+        let tx = SwapHook {
+            dir: dir.clone(),
+            pending: pending.clone(),
+            first_edit: None,
+        }
+        .spawn();
+
+        Self { dir, tx, pending }
+    }
+
+    pub fn edited(&self, path: &Path, text: Rope, encoding: Encoding) {
+        send_blocking(
+            &self.tx,
+            SwapEvent::Edited {
+                path: path.to_path_buf(),
+                text,
+                encoding,
+            },
+        );
+    }
+
+    pub fn remove(&self, path: &Path) {
+        send_blocking(&self.tx, SwapEvent::Remove(path.to_path_buf()));
+    }
+
+    /// Writes the swaps still waiting for edits to pause.
+    pub fn flush(&self) {
+        write_pending(&self.dir, &self.pending);
+    }
+
+    /// Whether `path` has a swap written after the file was last modified
+    /// that differs from `disk`, its contents.
+    pub fn is_newer(&self, path: &Path, disk: &[u8]) -> bool {
+        let swap = swap_path(&self.dir, path);
+        let Ok(swapped) = fs::metadata(&swap).and_then(|m| m.modified()) else {
+            return false;
+        };
+        if fs::metadata(path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified >= swapped)
+        {
+            return false;
+        }
+        fs::read(&swap).is_ok_and(|bytes| bytes != disk)
+    }
+
+    pub fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(swap_path(&self.dir, path))
+    }
+
+    /// Deletes the swap of `path` now, along with any waiting to be written.
+    pub fn delete(&self, path: &Path) -> io::Result<()> {
+        self.pending.lock().remove(path);
+        self.remove(path);
+        match fs::remove_file(swap_path(&self.dir, path)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+struct SwapHook {
+    dir: PathBuf,
+    pending: Pending,
+    /// When the first edit not written yet was made.
+    first_edit: Option<Instant>,
+}
+
+impl AsyncHook for SwapHook {
+    type Event = SwapEvent;
+
+    fn handle_event(&mut self, event: SwapEvent, timeout: Option<Instant>) -> Option<Instant> {
+        match event {
+            SwapEvent::Edited {
+                path,
+                text,
+                encoding,
+            } => {
+                self.pending.lock().insert(path, (text, encoding));
+                let now = Instant::now();
+                let first = *self.first_edit.get_or_insert(now);
+                Some((now + SWAP_DELAY).min(first + SWAP_MAX_DELAY))
+            }
+            SwapEvent::Remove(path) => {
+                self.pending.lock().remove(&path);
+                if let Err(e) = fs::remove_file(swap_path(&self.dir, &path))
+                    && e.kind() != io::ErrorKind::NotFound
+                {
+                    log::warn!("swap file for {}: {}", path.display(), e);
+                }
+                timeout
+            }
+        }
+    }
+
+    fn finish_debounce(&mut self) {
+        self.first_edit = None;
+        write_pending(&self.dir, &self.pending);
+    }
+}
+
+fn write_pending(dir: &Path, pending: &Pending) {
+    let swaps = std::mem::take(&mut *pending.lock());
+    if swaps.is_empty() {
+        return;
+    }
+    if let Err(e) = fs::create_dir_all(dir) {
+        log::warn!("swap directory {}: {}", dir.display(), e);
+        return;
+    }
+
+    for (path, (text, encoding)) in swaps {
+        let text = text.to_string();
+        // Text the encoding can't hold is kept as UTF-8 rather than lost.
+        let bytes = encoding.encode(&text).unwrap_or_else(|_| text.into_bytes());
+        if let Err(e) = write_atomic(&swap_path(dir, &path), &bytes, None) {
+            log::warn!("swap file for {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// The swap of `path` in `dir`, named after its full path like backups.
+fn swap_path(dir: &Path, path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = path
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "%");
+    dir.join(format!("{}.swp", name))
+}
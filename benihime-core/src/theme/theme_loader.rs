@@ -56,6 +56,22 @@ impl ThemeLoader {
         Err(anyhow!("File not found for: {:?}", path.display()))
     }
 
+    pub fn names(&self) -> Vec<String> {
+        let Some(entries) = std::fs::read_dir(&self.dir).ok() else {
+            return Vec::new();
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|p| p.file_stem()?.to_str().map(str::to_string))
+            .collect();
+
+        names.sort();
+        names
+    }
+
     pub fn default(&self) -> Theme {
         self.load("default").expect("Failed to get default theme")
     }
@@ -0,0 +1,483 @@
+use std::cmp::Ordering;
+
+use ropey::Rope;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Retain(usize),
+    Delete(usize),
+    Insert(String),
+}
+
+/// Which side of text inserted exactly at a position the position ends up
+/// on when mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Before,
+    After,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSet {
+    operations: Vec<Operation>,
+    /// Length in chars of the text the changes apply to.
+    len: usize,
+    /// Length in chars of the text once they are applied.
+    len_after: usize,
+}
+
+impl ChangeSet {
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// `true` when applying the changes leaves the text as it was.
+    pub fn is_empty(&self) -> bool {
+        self.operations
+            .iter()
+            .all(|op| matches!(op, Operation::Retain(_)))
+    }
+
+    pub fn retain(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.len += n;
+        self.len_after += n;
+
+        if let Some(Operation::Retain(count)) = self.operations.last_mut() {
+            *count += n;
+        } else {
+            self.operations.push(Operation::Retain(n));
+        }
+    }
+
+    pub fn delete(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.len += n;
+
+        if let Some(Operation::Delete(count)) = self.operations.last_mut() {
+            *count += n;
+        } else {
+            self.operations.push(Operation::Delete(n));
+        }
+    }
+
+    /// Inserts `text`, keeping an insertion next to a deletion in front of
+    /// it so equal changes always have the same operations.
+    pub fn insert(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        self.len_after += text.chars().count();
+
+        match self.operations.as_mut_slice() {
+            [.., Operation::Insert(prev)] | [.., Operation::Insert(prev), Operation::Delete(_)] => {
+                prev.push_str(&text);
+            }
+            [.., last @ Operation::Delete(_)] => {
+                let delete = std::mem::replace(last, Operation::Insert(text));
+                self.operations.push(delete);
+            }
+            _ => self.operations.push(Operation::Insert(text)),
+        }
+    }
+
+    /// Applies the changes to `text`, returning `false` without touching it
+    /// when they were made for a text of another length.
+    pub fn apply(&self, text: &mut Rope) -> bool {
+        if text.len_chars() != self.len {
+            return false;
+        }
+
+        let mut pos = 0;
+        for op in &self.operations {
+            match op {
+                Operation::Retain(n) => pos += n,
+                Operation::Delete(n) => text.remove(pos..pos + n),
+                Operation::Insert(s) => {
+                    text.insert(pos, s);
+                    pos += s.chars().count();
+                }
+            }
+        }
+        true
+    }
+
+    /// The changes that turn the changed text back into `original`, which
+    /// must be the text these changes were made to.
+    pub fn invert(&self, original: &Rope) -> ChangeSet {
+        let mut inverse = ChangeSet::default();
+        let mut pos = 0;
+
+        for op in &self.operations {
+            match op {
+                Operation::Retain(n) => {
+                    inverse.retain(*n);
+                    pos += n;
+                }
+                Operation::Delete(n) => {
+                    inverse.insert(original.slice(pos..pos + n).to_string());
+                    pos += n;
+                }
+                Operation::Insert(s) => inverse.delete(s.chars().count()),
+            }
+        }
+
+        inverse
+    }
+
+    /// A single change set doing these changes and then `other`, which must
+    /// have been made to the text these changes produce.
+    pub fn compose(self, other: ChangeSet) -> ChangeSet {
+        debug_assert_eq!(self.len_after, other.len);
+
+        let mut composed = ChangeSet::default();
+        let mut a = self.operations.into_iter();
+        let mut b = other.operations.into_iter();
+        let mut head_a = a.next();
+        let mut head_b = b.next();
+
+        loop {
+            match (head_a.take(), head_b.take()) {
+                (None, None) => break,
+                (Some(Operation::Delete(n)), next_b) => {
+                    composed.delete(n);
+                    head_a = a.next();
+                    head_b = next_b;
+                }
+                (next_a, Some(Operation::Insert(text))) => {
+                    composed.insert(text);
+                    head_a = next_a;
+                    head_b = b.next();
+                }
+                (None, Some(op)) | (Some(op), None) => {
+                    // Only reachable when the lengths don't line up; keep the
+                    // rest rather than losing it.
+                    match op {
+                        Operation::Retain(n) => composed.retain(n),
+                        Operation::Delete(n) => composed.delete(n),
+                        Operation::Insert(text) => composed.insert(text),
+                    }
+                    head_a = a.next();
+                    head_b = b.next();
+                }
+                (Some(Operation::Retain(i)), Some(Operation::Retain(j))) => match i.cmp(&j) {
+                    Ordering::Less => {
+                        composed.retain(i);
+                        head_a = a.next();
+                        head_b = Some(Operation::Retain(j - i));
+                    }
+                    Ordering::Equal => {
+                        composed.retain(i);
+                        head_a = a.next();
+                        head_b = b.next();
+                    }
+                    Ordering::Greater => {
+                        composed.retain(j);
+                        head_a = Some(Operation::Retain(i - j));
+                        head_b = b.next();
+                    }
+                },
+                (Some(Operation::Retain(i)), Some(Operation::Delete(j))) => match i.cmp(&j) {
+                    Ordering::Less => {
+                        composed.delete(i);
+                        head_a = a.next();
+                        head_b = Some(Operation::Delete(j - i));
+                    }
+                    Ordering::Equal => {
+                        composed.delete(i);
+                        head_a = a.next();
+                        head_b = b.next();
+                    }
+                    Ordering::Greater => {
+                        composed.delete(j);
+                        head_a = Some(Operation::Retain(i - j));
+                        head_b = b.next();
+                    }
+                },
+                (Some(Operation::Insert(text)), Some(Operation::Retain(j))) => {
+                    let len = text.chars().count();
+                    match len.cmp(&j) {
+                        Ordering::Less => {
+                            composed.insert(text);
+                            head_a = a.next();
+                            head_b = Some(Operation::Retain(j - len));
+                        }
+                        Ordering::Equal => {
+                            composed.insert(text);
+                            head_a = a.next();
+                            head_b = b.next();
+                        }
+                        Ordering::Greater => {
+                            let (kept, rest) = split_chars(&text, j);
+                            composed.insert(kept);
+                            head_a = Some(Operation::Insert(rest));
+                            head_b = b.next();
+                        }
+                    }
+                }
+                (Some(Operation::Insert(text)), Some(Operation::Delete(j))) => {
+                    let len = text.chars().count();
+                    match len.cmp(&j) {
+                        Ordering::Less => {
+                            head_a = a.next();
+                            head_b = Some(Operation::Delete(j - len));
+                        }
+                        Ordering::Equal => {
+                            head_a = a.next();
+                            head_b = b.next();
+                        }
+                        Ordering::Greater => {
+                            let (_, rest) = split_chars(&text, j);
+                            head_a = Some(Operation::Insert(rest));
+                            head_b = b.next();
+                        }
+                    }
+                }
+            }
+        }
+
+        composed
+    }
+
+    /// Where char position `pos` of the old text is in the new one. Positions
+    /// inside deleted text move to where it was.
+    pub fn map_pos(&self, pos: usize, assoc: Assoc) -> usize {
+        let mut old = 0;
+        let mut new = 0;
+
+        for op in &self.operations {
+            match op {
+                Operation::Retain(n) => {
+                    if pos < old + n {
+                        return new + (pos - old);
+                    }
+                    old += n;
+                    new += n;
+                }
+                Operation::Delete(n) => {
+                    if pos < old + n {
+                        return new;
+                    }
+                    old += n;
+                }
+                Operation::Insert(s) => {
+                    if pos == old && assoc == Assoc::Before {
+                        return new;
+                    }
+                    new += s.chars().count();
+                }
+            }
+        }
+
+        new + pos.saturating_sub(old)
+    }
+}
+
+/// Splits `text` after its first `n` chars.
+fn split_chars(text: &str, n: usize) -> (String, String) {
+    let at = text
+        .char_indices()
+        .nth(n)
+        .map_or(text.len(), |(byte, _)| byte);
+    (text[..at].to_string(), text[at..].to_string())
+}
+
+/// An edit of a buffer, built against its current text and applied, undone
+/// and mapped through as one change.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    changes: ChangeSet,
+}
+
+impl Transaction {
+    /// Replaces each `(from, to)` char range of `text` with the given
+    /// string, or deletes it on `None`. Ranges must be sorted and must not
+    /// overlap.
+    pub fn change<I>(text: &Rope, changes: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, usize, Option<String>)>,
+    {
+        let len = text.len_chars();
+        let mut set = ChangeSet::default();
+        let mut last = 0;
+
+        for (from, to, replacement) in changes {
+            let from = from.clamp(last, len);
+            let to = to.clamp(from, len);
+
+            set.retain(from - last);
+            set.delete(to - from);
+            if let Some(replacement) = replacement {
+                set.insert(replacement);
+            }
+            last = to;
+        }
+        set.retain(len - last);
+
+        Self { changes: set }
+    }
+
+    pub fn insert(text: &Rope, at: usize, s: &str) -> Self {
+        Self::change(text, [(at, at, Some(s.to_string()))])
+    }
+
+    pub fn delete(text: &Rope, range: std::ops::Range<usize>) -> Self {
+        Self::change(text, [(range.start, range.end, None)])
+    }
+
+    pub fn changes(&self) -> &ChangeSet {
+        &self.changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    type Seeds = Vec<(usize, usize, Option<String>)>;
+
+    fn mixed_text(max: usize) -> impl Strategy<Value = String> {
+        let chars = prop::sample::select(vec!['a', ' ', '\n', '日', '本', 'é', '😀', '🎉']);
+        prop::collection::vec(chars, 0..max).prop_map(|chars| chars.into_iter().collect())
+    }
+
+    /// Positions and replacements for up to a few changes, fitted to a text
+    /// by `changes`.
+    fn seeds() -> impl Strategy<Value = Seeds> {
+        prop::collection::vec(
+            (any::<usize>(), 0..4usize, prop::option::of(mixed_text(4))),
+            0..5,
+        )
+    }
+
+    fn changes(text: &Rope, seeds: &Seeds) -> ChangeSet {
+        let len = text.len_chars();
+        let mut ranges: Vec<_> = seeds
+            .iter()
+            .map(|(at, n, replacement)| {
+                let from = at % (len + 1);
+                (from, (from + n).min(len), replacement.clone())
+            })
+            .collect();
+        ranges.sort_by_key(|(from, _, _)| *from);
+        Transaction::change(text, ranges).changes().clone()
+    }
+
+    fn applied(changes: &ChangeSet, text: &Rope) -> Rope {
+        let mut text = text.clone();
+        assert!(changes.apply(&mut text));
+        text
+    }
+
+    proptest! {
+        #[test]
+        fn compose_applies_both_changes(text in mixed_text(30), a in seeds(), b in seeds()) {
+            let text = Rope::from_str(&text);
+            let a = changes(&text, &a);
+            let middle = applied(&a, &text);
+            let b = changes(&middle, &b);
+            let expected = applied(&b, &middle);
+
+            let composed = a.compose(b);
+            prop_assert_eq!(composed.len(), text.len_chars());
+            prop_assert_eq!(composed.len_after, expected.len_chars());
+            prop_assert_eq!(applied(&composed, &text), expected);
+        }
+
+        #[test]
+        fn invert_restores_the_original(text in mixed_text(30), seeds in seeds()) {
+            let text = Rope::from_str(&text);
+            let changes = changes(&text, &seeds);
+            let inverse = changes.invert(&text);
+
+            prop_assert_eq!(applied(&inverse, &applied(&changes, &text)), text);
+        }
+
+        #[test]
+        fn map_pos_keeps_retained_chars(text in mixed_text(30), seeds in seeds()) {
+            let text = Rope::from_str(&text);
+            let changes = changes(&text, &seeds);
+            let new = applied(&changes, &text);
+
+            let mut pos = 0;
+            let mut last = 0;
+            for op in changes.operations() {
+                match op {
+                    Operation::Retain(n) => {
+                        // A char just after an insertion is pushed past it.
+                        for p in pos..pos + n {
+                            let mapped = changes.map_pos(p, Assoc::After);
+                            prop_assert_eq!(new.char(mapped), text.char(p));
+                        }
+                        pos += n;
+                    }
+                    Operation::Delete(n) => pos += n,
+                    Operation::Insert(_) => {}
+                }
+            }
+            for p in 0..=text.len_chars() {
+                let mapped = changes.map_pos(p, Assoc::After);
+                prop_assert!(mapped >= last && mapped <= new.len_chars());
+                last = mapped;
+            }
+        }
+    }
+
+    #[test]
+    fn map_pos_at_an_insertion() {
+        // Inserting two multi-byte chars between 本 and 😀.
+        let text = Rope::from_str("日本😀語");
+        let changes = Transaction::insert(&text, 2, "é🎉").changes().clone();
+
+        assert_eq!(changes.map_pos(1, Assoc::Before), 1);
+        assert_eq!(changes.map_pos(2, Assoc::Before), 2);
+        assert_eq!(changes.map_pos(2, Assoc::After), 4);
+        assert_eq!(changes.map_pos(3, Assoc::Before), 5);
+        assert_eq!(changes.map_pos(4, Assoc::After), 6);
+
+        // At the very end, where nothing follows the insertion.
+        let changes = Transaction::insert(&text, 4, "🎉").changes().clone();
+        assert_eq!(changes.map_pos(4, Assoc::Before), 4);
+        assert_eq!(changes.map_pos(4, Assoc::After), 5);
+    }
+
+    #[test]
+    fn map_pos_at_a_deletion() {
+        // Deleting 本😀.
+        let text = Rope::from_str("日本😀語");
+        let changes = Transaction::delete(&text, 1..3).changes().clone();
+
+        for assoc in [Assoc::Before, Assoc::After] {
+            assert_eq!(changes.map_pos(0, assoc), 0);
+            assert_eq!(changes.map_pos(1, assoc), 1);
+            assert_eq!(changes.map_pos(2, assoc), 1);
+            assert_eq!(changes.map_pos(3, assoc), 1);
+            assert_eq!(changes.map_pos(4, assoc), 2);
+        }
+    }
+
+    #[test]
+    fn map_pos_at_a_replacement() {
+        // Replacing 本😀 with 🎉.
+        let text = Rope::from_str("日本😀語");
+        let changes = Transaction::change(&text, [(1, 3, Some("🎉".to_string()))])
+            .changes()
+            .clone();
+
+        assert_eq!(changes.map_pos(1, Assoc::Before), 1);
+        assert_eq!(changes.map_pos(1, Assoc::After), 2);
+        assert_eq!(changes.map_pos(2, Assoc::Before), 2);
+        assert_eq!(changes.map_pos(3, Assoc::Before), 2);
+        assert_eq!(changes.map_pos(4, Assoc::After), 3);
+    }
+}
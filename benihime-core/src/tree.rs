@@ -5,11 +5,16 @@ use crate::{
     window::{Window, WindowId},
 };
 
+/// Smallest share of its container a window can be resized down to.
+const MIN_WEIGHT_FRACTION: f32 = 0.05;
+
 #[derive(Debug, Clone)]
 pub struct Tree {
     root: WindowId,
     pub focus: WindowId,
     area: Rect,
+    /// While set, only the focused window is shown, using the whole area.
+    zoomed: bool,
 
     nodes: SlotMap<WindowId, Node>,
 }
@@ -19,6 +24,8 @@ pub struct Node {
     parent: WindowId,
     content: Content,
     area: Rect,
+    /// Share of the parent container's space, relative to the other children.
+    weight: f32,
 }
 
 #[derive(Debug, Clone)]
@@ -33,6 +40,7 @@ impl Node {
             parent: WindowId::default(),
             content: Content::Container(Box::new(Container::new(layout))),
             area: Rect::default(),
+            weight: 1.0,
         }
     }
 
@@ -41,6 +49,7 @@ impl Node {
             parent: WindowId::default(),
             content: Content::Window(Box::new(window)),
             area: Rect::default(),
+            weight: 1.0,
         }
     }
 
@@ -58,6 +67,21 @@ pub enum Layout {
     Vertical,
 }
 
+/// A tree's layout without its node ids, used to save and restore sessions.
+#[derive(Debug, Clone)]
+pub enum LayoutSnapshot {
+    Window {
+        window: Window,
+        weight: f32,
+        focused: bool,
+    },
+    Container {
+        layout: Layout,
+        weight: f32,
+        children: Vec<LayoutSnapshot>,
+    },
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Direction {
     Up,
@@ -100,6 +124,7 @@ impl Tree {
             root,
             focus: root,
             area,
+            zoomed: false,
             nodes,
         }
     }
@@ -208,12 +233,123 @@ impl Tree {
 
         self.root = root;
         self.focus = window_id;
+        self.zoomed = false;
         self.nodes = nodes;
         self.recalculate();
 
         window_id
     }
 
+    pub fn snapshot(&self) -> LayoutSnapshot {
+        self.snapshot_node(self.root)
+    }
+
+    fn snapshot_node(&self, id: WindowId) -> LayoutSnapshot {
+        let node = &self.nodes[id];
+        match &node.content {
+            Content::Window(window) => LayoutSnapshot::Window {
+                window: (**window).clone(),
+                weight: node.weight,
+                focused: id == self.focus,
+            },
+            Content::Container(container) => LayoutSnapshot::Container {
+                layout: container.layout,
+                weight: node.weight,
+                children: container
+                    .children
+                    .iter()
+                    .map(|&child| self.snapshot_node(child))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Replaces the layout with `snapshot`, or returns `false` when none of
+    /// its windows remain.
+    pub fn restore(&mut self, snapshot: LayoutSnapshot) -> bool {
+        let snapshot = match snapshot {
+            LayoutSnapshot::Window { .. } => LayoutSnapshot::Container {
+                layout: Layout::Vertical,
+                weight: 1.0,
+                children: vec![snapshot],
+            },
+            container => container,
+        };
+
+        let mut nodes = SlotMap::with_key();
+        let mut focus = None;
+        let Some(root) = Self::restore_node(&mut nodes, snapshot, None, &mut focus) else {
+            return false;
+        };
+        let Some(focus) = focus else {
+            return false;
+        };
+
+        self.root = root;
+        self.focus = focus;
+        self.zoomed = false;
+        self.nodes = nodes;
+        self.recalculate();
+
+        true
+    }
+
+    fn restore_node(
+        nodes: &mut SlotMap<WindowId, Node>,
+        snapshot: LayoutSnapshot,
+        parent: Option<WindowId>,
+        focus: &mut Option<WindowId>,
+    ) -> Option<WindowId> {
+        match snapshot {
+            LayoutSnapshot::Window {
+                window,
+                weight,
+                focused,
+            } => {
+                let mut node = Node::window(window);
+                node.parent = parent?;
+                node.weight = weight;
+                let id = nodes.insert(node);
+
+                if let Content::Window(window) = &mut nodes[id].content {
+                    window.id = id;
+                }
+                if focused || focus.is_none() {
+                    *focus = Some(id);
+                }
+
+                Some(id)
+            }
+            LayoutSnapshot::Container {
+                layout,
+                weight,
+                children,
+            } => {
+                let mut node = Node::container(layout);
+                node.weight = weight;
+                let id = nodes.insert(node);
+                nodes[id].parent = parent.unwrap_or(id);
+
+                let children: Vec<WindowId> = children
+                    .into_iter()
+                    .filter_map(|child| Self::restore_node(nodes, child, Some(id), focus))
+                    .collect();
+
+                if children.is_empty() {
+                    nodes.remove(id);
+                    return None;
+                }
+
+                match &mut nodes[id].content {
+                    Content::Container(container) => container.children = children,
+                    Content::Window(_) => unreachable!(),
+                }
+
+                Some(id)
+            }
+        }
+    }
+
     pub fn split(&mut self, window: Window, layout: Layout) -> WindowId {
         let focus = self.focus;
         let parent = self.nodes[focus].parent;
@@ -277,34 +413,223 @@ impl Tree {
         }
 
         self.focus = node;
+        self.zoomed = false;
 
         self.recalculate();
 
         node
     }
 
+    /// Visible windows with their areas. While zoomed that is only the focused
+    /// window, covering the whole tree.
     pub fn windows(&self) -> impl Iterator<Item = (&Window, Rect, bool)> {
         let focus = self.focus;
+        let zoomed = self.zoomed;
+        let area = self.area;
         self.nodes.iter().filter_map(move |(key, node)| match node {
             Node {
                 content: Content::Window(view),
                 ..
-            } => Some((view.as_ref(), node.area, focus == key)),
+            } if !zoomed || key == focus => {
+                let area = if zoomed { area } else { node.area };
+                Some((view.as_ref(), area, focus == key))
+            }
             _ => None,
         })
     }
 
+    pub fn window_count(&self) -> usize {
+        self.nodes
+            .values()
+            .filter(|node| matches!(node.content, Content::Window(_)))
+            .count()
+    }
+
+    /// Every window, including those hidden while zoomed.
+    pub fn windows_mut(&mut self) -> impl Iterator<Item = &mut Window> {
+        self.nodes
+            .values_mut()
+            .filter_map(|node| match &mut node.content {
+                Content::Window(window) => Some(window.as_mut()),
+                _ => None,
+            })
+    }
+
+    fn container_mut(&mut self, id: WindowId) -> &mut Container {
+        match &mut self.nodes[id] {
+            Node {
+                content: Content::Container(container),
+                ..
+            } => container,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Removes window `id` and returns it. Containers left with a single child
+    /// are replaced by that child. The last window can't be removed.
+    pub fn remove(&mut self, id: WindowId) -> Option<Window> {
+        if self.try_get(id).is_none() || self.window_count() <= 1 {
+            return None;
+        }
+
+        let parent = self.nodes[id].parent;
+        let container = self.container_mut(parent);
+        let pos = container.children.iter().position(|&child| child == id)?;
+        container.children.remove(pos);
+        let sibling = container.children.get(pos.saturating_sub(1)).copied();
+
+        let window = match self.nodes.remove(id)?.content {
+            Content::Window(window) => *window,
+            Content::Container(_) => unreachable!(),
+        };
+
+        self.collapse(parent);
+
+        if self.focus == id {
+            self.focus = match sibling {
+                Some(sibling) => self.find_leaf(sibling, Direction::Left),
+                None => self.find_leaf(self.root, Direction::Left),
+            };
+        }
+        self.zoomed = false;
+        self.recalculate();
+
+        Some(window)
+    }
+
+    fn collapse(&mut self, id: WindowId) {
+        let children = match &self.nodes[id].content {
+            Content::Container(container) => container.children.clone(),
+            Content::Window(_) => return,
+        };
+
+        let parent = self.nodes[id].parent;
+        let is_root = parent == id;
+
+        match children.as_slice() {
+            [] if !is_root => {
+                self.container_mut(parent)
+                    .children
+                    .retain(|&child| child != id);
+                self.nodes.remove(id);
+                self.collapse(parent);
+            }
+            [child] if is_root => {
+                if matches!(self.nodes[*child].content, Content::Container(_)) {
+                    self.nodes[*child].parent = *child;
+                    self.nodes[*child].weight = 1.0;
+                    self.root = *child;
+                    self.nodes.remove(id);
+                }
+            }
+            [child] => {
+                let child = *child;
+                let container = self.container_mut(parent);
+                if let Some(pos) = container.children.iter().position(|&c| c == id) {
+                    container.children[pos] = child;
+                }
+                self.nodes[child].parent = parent;
+                self.nodes[child].weight = self.nodes[id].weight;
+                self.nodes.remove(id);
+            }
+            _ => {}
+        }
+    }
+
+    /// Swaps the focused window with its neighbour in `direction`; focus moves
+    /// along with the window.
+    pub fn swap(&mut self, direction: Direction) -> bool {
+        let Some(target) = self.neighbor(direction) else {
+            return false;
+        };
+
+        let focus = self.focus;
+        let Some([a, b]) = self.nodes.get_disjoint_mut([focus, target]) else {
+            return false;
+        };
+        std::mem::swap(&mut a.content, &mut b.content);
+
+        self.get_mut(focus).id = focus;
+        self.get_mut(target).id = target;
+        self.focus = target;
+        self.zoomed = false;
+        true
+    }
+
+    /// Grows (or shrinks, for a negative `percent`) the focused window along
+    /// `layout` by a percentage of the enclosing container.
+    pub fn resize_window(&mut self, layout: Layout, percent: i64) -> bool {
+        let mut current = self.focus;
+        loop {
+            let parent = self.nodes[current].parent;
+            if parent == current {
+                return false;
+            }
+
+            let (parent_layout, children) = match &self.nodes[parent].content {
+                Content::Container(c) => (c.layout, c.children.clone()),
+                Content::Window(_) => return false,
+            };
+
+            if parent_layout == layout && children.len() > 1 {
+                let total: f32 = children.iter().map(|&id| self.nodes[id].weight).sum();
+                let weight = &mut self.nodes[current].weight;
+                *weight =
+                    (*weight + total * percent as f32 / 100.0).max(total * MIN_WEIGHT_FRACTION);
+
+                self.recalculate();
+                return true;
+            }
+
+            current = parent;
+        }
+    }
+
+    pub fn equalize(&mut self) {
+        for node in self.nodes.values_mut() {
+            node.weight = 1.0;
+        }
+        self.recalculate();
+    }
+
+    /// Flips the focused window's container between side-by-side and stacked.
+    pub fn rotate(&mut self) {
+        let parent = self.nodes[self.focus].parent;
+        let container = self.container_mut(parent);
+        container.layout = match container.layout {
+            Layout::Horizontal => Layout::Vertical,
+            Layout::Vertical => Layout::Horizontal,
+        };
+        self.recalculate();
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoomed
+    }
+
+    pub fn toggle_zoom(&mut self) -> bool {
+        self.zoomed = !self.zoomed && self.window_count() > 1;
+        self.zoomed
+    }
+
     pub fn focus_move(&mut self, direction: Direction) {
+        if let Some(new_focus) = self.neighbor(direction) {
+            self.focus = new_focus;
+            self.zoomed = false;
+        }
+    }
+
+    fn neighbor(&self, direction: Direction) -> Option<WindowId> {
         let mut current = self.focus;
-        let new_focus = loop {
+        loop {
             let parent = match self.nodes.get(current) {
                 Some(node) if node.parent != current => node.parent,
-                _ => return,
+                _ => return None,
             };
 
             let (layout, children) = match &self.nodes[parent].content {
                 Content::Container(c) => (c.layout, c.children.clone()),
-                _ => return,
+                _ => return None,
             };
 
             let moves_in_layout = match (layout, direction) {
@@ -314,10 +639,7 @@ impl Tree {
             };
 
             if moves_in_layout {
-                let pos = match children.iter().position(|&id| id == current) {
-                    Some(p) => p,
-                    None => return,
-                };
+                let pos = children.iter().position(|&id| id == current)?;
 
                 let new_pos = match direction {
                     Direction::Left | Direction::Up => pos.checked_sub(1),
@@ -331,14 +653,12 @@ impl Tree {
                 };
 
                 if let Some(new_pos) = new_pos {
-                    break self.find_leaf(children[new_pos], direction);
+                    return Some(self.find_leaf(children[new_pos], direction));
                 }
             }
 
             current = parent;
-        };
-
-        self.focus = new_focus;
+        }
     }
 
     fn find_leaf(&self, node_id: WindowId, direction: Direction) -> WindowId {
@@ -348,8 +668,7 @@ impl Tree {
                 if c.children.is_empty() {
                     return node_id;
                 }
-                let prefer_first =
-                    matches!(direction, Direction::Right | Direction::Down);
+                let prefer_first = matches!(direction, Direction::Right | Direction::Down);
                 let idx = if prefer_first {
                     0
                 } else {
@@ -400,26 +719,19 @@ impl Tree {
             return;
         }
 
-        let count = children.len() as u16;
+        let weights: Vec<f32> = children.iter().map(|&id| self.nodes[id].weight).collect();
 
         match direction {
             Layout::Vertical => {
-                let base_width = area.width / count;
-                let remainder = area.width % count;
+                let widths = split_length(area.width, &weights);
 
                 let mut current_x = area.x;
 
-                for (i, child_id) in children.iter().enumerate() {
-                    let extra = if i == children.len() - 1 {
-                        remainder
-                    } else {
-                        0
-                    };
-
+                for (child_id, width) in children.iter().zip(widths) {
                     let child_rect = Rect {
                         x: current_x,
                         y: area.y,
-                        width: base_width + extra,
+                        width,
                         height: area.height,
                     };
 
@@ -430,23 +742,16 @@ impl Tree {
             }
 
             Layout::Horizontal => {
-                let base_height = area.height / count;
-                let remainder = area.height % count;
+                let heights = split_length(area.height, &weights);
 
                 let mut current_y = area.y;
 
-                for (i, child_id) in children.iter().enumerate() {
-                    let extra = if i == children.len() - 1 {
-                        remainder
-                    } else {
-                        0
-                    };
-
+                for (child_id, height) in children.iter().zip(heights) {
                     let child_rect = Rect {
                         x: area.x,
                         y: current_y,
                         width: area.width,
-                        height: base_height + extra,
+                        height,
                     };
 
                     current_y += child_rect.height;
@@ -457,3 +762,19 @@ impl Tree {
         }
     }
 }
+
+/// Divides `length` in proportion to `weights`; rounding leftovers go to the last part.
+fn split_length(length: u16, weights: &[f32]) -> Vec<u16> {
+    let total: f32 = weights.iter().sum();
+    let mut lengths: Vec<u16> = weights
+        .iter()
+        .map(|weight| (length as f32 * weight / total).floor() as u16)
+        .collect();
+
+    let used: u16 = lengths.iter().sum();
+    if let Some(last) = lengths.last_mut() {
+        *last += length.saturating_sub(used);
+    }
+
+    lengths
+}
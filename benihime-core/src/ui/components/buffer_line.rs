@@ -1,15 +1,62 @@
-use benihime_renderer::Renderer;
+use benihime_renderer::{
+    Renderer,
+    event::{MouseButton, MouseEvent},
+};
 
 use crate::{
+    buffer::BufferId,
+    editor::Editor,
     graphics::Rect,
-    ui::composer::{Component, Context},
+    ui::composer::{Component, Context, Event, EventResult},
 };
 
-pub struct BufferLine;
+pub struct BufferLine {
+    /// Horizontal extent of each tab in the last frame, used for mouse clicks.
+    tabs: Vec<(BufferId, f32, f32)>,
+    /// Vertical extent of the line in the last frame.
+    rows: (f32, f32),
+}
 
 impl BufferLine {
     pub fn new() -> Self {
-        Self
+        Self {
+            tabs: Vec::new(),
+            rows: (0.0, 0.0),
+        }
+    }
+
+    fn tab_at(&self, (x, y): (f32, f32)) -> Option<BufferId> {
+        if y < self.rows.0 || y >= self.rows.1 {
+            return None;
+        }
+
+        self.tabs
+            .iter()
+            .find(|(_, start, end)| x >= *start && x < *end)
+            .map(|(buf_id, _, _)| *buf_id)
+    }
+
+    fn handle_mouse(&mut self, event: &MouseEvent, editor: &mut Editor) -> EventResult {
+        if !event.pressed {
+            return EventResult::Ignored(None);
+        }
+
+        let Some(buf_id) = self.tab_at(event.position) else {
+            return EventResult::Ignored(None);
+        };
+
+        match event.button {
+            Some(MouseButton::Left) => editor.focus_buf(buf_id),
+            Some(MouseButton::Middle) => {
+                if let Err(e) = editor.close_buffer(buf_id) {
+                    editor.set_error(e.to_string());
+                }
+            }
+            _ => return EventResult::Ignored(None),
+        }
+
+        editor.needs_redraw = true;
+        EventResult::Consumed(None)
     }
 }
 
@@ -20,6 +67,13 @@ impl Default for BufferLine {
 }
 
 impl Component for BufferLine {
+    fn handle_event(&mut self, event: &Event, ctx: &mut Context) -> EventResult {
+        match event {
+            Event::Mouse(mouse) => self.handle_mouse(mouse, ctx.editor),
+            _ => EventResult::Ignored(None),
+        }
+    }
+
     fn render(&mut self, area: Rect, surface: &mut Renderer, ctx: &mut Context) {
         let editor = &ctx.editor;
         let buffers = editor.buffer_line();
@@ -34,9 +88,12 @@ impl Component for BufferLine {
             benihime_renderer::color::Color::rgb(0.2, 0.2, 0.25),
         );
 
+        self.tabs.clear();
+        self.rows = (area.y as f32, (area.y + cell_height) as f32);
+
         let mut x_offset = area.x as f32;
 
-        for (_buf_id, name, is_active, _is_modified) in buffers {
+        for (buf_id, name, is_active, _is_modified) in buffers {
             let label = format!(" {} ", name.as_str());
 
             let color = if is_active {
@@ -55,6 +112,7 @@ impl Component for BufferLine {
             surface.draw_text(section);
 
             let label_width = label.len() as f32 * surface.cell_width();
+            self.tabs.push((buf_id, x_offset, x_offset + label_width));
             x_offset += label_width;
         }
     }
@@ -29,13 +29,11 @@ impl Component for CursorComponent {
         let window_area = editor
             .tree()
             .windows()
-            .find_map(|(_, node_area, is_focus)| {
-                if is_focus {
-                    Some(node_area)
-                } else {
-                    None
-                }
-            })
+            .find_map(
+                |(_, node_area, is_focus)| {
+                    if is_focus { Some(node_area) } else { None }
+                },
+            )
             .unwrap_or(area);
 
         let (window, _buf) = editor.focus_ref();
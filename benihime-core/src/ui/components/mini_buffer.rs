@@ -1,15 +1,60 @@
-use benihime_renderer::Renderer;
+use benihime_renderer::{
+    Renderer,
+    event::{MouseButton, MouseEvent},
+};
 
 use crate::{
+    editor::Editor,
     graphics::Rect,
-    ui::composer::{Component, Context},
+    ui::composer::{Component, Context, Event, EventResult},
 };
 
-pub struct MiniBufferComponent;
+/// Where the last frame drew the minibuffer, used for mouse clicks.
+struct Layout {
+    /// Vertical extent of the candidate list and input line together.
+    top: f32,
+    bottom: f32,
+    candidates_top: f32,
+    cell_height: f32,
+    /// Index of the first visible candidate and how many are shown.
+    offset: usize,
+    visible: usize,
+}
+
+pub struct MiniBufferComponent {
+    layout: Option<Layout>,
+}
 
 impl MiniBufferComponent {
     pub fn new() -> Self {
-        Self
+        Self { layout: None }
+    }
+
+    fn handle_mouse(&mut self, event: &MouseEvent, editor: &mut Editor) -> EventResult {
+        let (Some(layout), Some(mini)) = (&self.layout, &mut editor.minibuffer_manager.current)
+        else {
+            return EventResult::Ignored(None);
+        };
+
+        let (_, y) = event.position;
+        if y < layout.top || y >= layout.bottom {
+            return EventResult::Ignored(None);
+        }
+
+        if event.button != Some(MouseButton::Left) || !event.pressed {
+            return EventResult::Consumed(None);
+        }
+
+        let row = ((y - layout.candidates_top) / layout.cell_height).floor();
+        if row < 0.0 || row as usize >= layout.visible {
+            return EventResult::Consumed(None);
+        }
+
+        let candidate = layout.offset + row as usize;
+        mini.move_focus(candidate as isize - mini.index() as isize);
+
+        let _ = editor.exec("minibuffer-accept", None);
+        EventResult::Consumed(None)
     }
 }
 
@@ -20,10 +65,18 @@ impl Default for MiniBufferComponent {
 }
 
 impl Component for MiniBufferComponent {
+    fn handle_event(&mut self, event: &Event, ctx: &mut Context) -> EventResult {
+        match event {
+            Event::Mouse(mouse) => self.handle_mouse(mouse, ctx.editor),
+            _ => EventResult::Ignored(None),
+        }
+    }
+
     fn render(&mut self, area: Rect, surface: &mut Renderer, ctx: &mut Context) {
         let minibuffer_manager = &ctx.editor.minibuffer_manager;
 
         let Some(mini) = &minibuffer_manager.current else {
+            self.layout = None;
             return;
         };
 
@@ -52,10 +105,19 @@ impl Component for MiniBufferComponent {
         );
 
         let input_y = minibuffer_y;
+        let candidate_area_start = input_y.saturating_sub(max_visible as u16 * cell_height);
+
+        self.layout = Some(Layout {
+            top: candidate_area_start as f32,
+            bottom: (input_y + cell_height) as f32,
+            candidates_top: candidate_area_start as f32,
+            cell_height: cell_height as f32,
+            offset,
+            visible: max_visible.min(total_candidates.saturating_sub(offset)),
+        });
 
         if total_candidates > 0 {
             let candidate_area_height = max_visible as u16 * cell_height;
-            let candidate_area_start = input_y.saturating_sub(candidate_area_height);
 
             surface.with_overlay_region(
                 area.x as f32,
@@ -2,3 +2,5 @@ pub mod buffer_line;
 pub mod cursor;
 pub mod mini_buffer;
 pub mod status_line;
+pub mod tab_bar;
+pub mod which_key;
@@ -0,0 +1,137 @@
+use benihime_renderer::{
+    Renderer,
+    event::{MouseButton, MouseEvent},
+};
+
+use crate::{
+    editor::Editor,
+    graphics::Rect,
+    ui::composer::{Component, Context, Event, EventResult},
+};
+
+/// Tab pages of the current project, drawn right-aligned on the buffer line
+/// row. Hidden while the project has a single tab.
+pub struct TabBar {
+    /// Horizontal extent of each tab in the last frame, used for mouse clicks.
+    tabs: Vec<(usize, f32, f32)>,
+    /// Vertical extent of the bar in the last frame.
+    rows: (f32, f32),
+}
+
+impl TabBar {
+    pub fn new() -> Self {
+        Self {
+            tabs: Vec::new(),
+            rows: (0.0, 0.0),
+        }
+    }
+
+    fn tab_at(&self, (x, y): (f32, f32)) -> Option<usize> {
+        if y < self.rows.0 || y >= self.rows.1 {
+            return None;
+        }
+
+        self.tabs
+            .iter()
+            .find(|(_, start, end)| x >= *start && x < *end)
+            .map(|(idx, _, _)| *idx)
+    }
+
+    fn handle_mouse(&mut self, event: &MouseEvent, editor: &mut Editor) -> EventResult {
+        if !event.pressed || event.button != Some(MouseButton::Left) {
+            return EventResult::Ignored(None);
+        }
+
+        let Some(idx) = self.tab_at(event.position) else {
+            return EventResult::Ignored(None);
+        };
+
+        if let Err(e) = editor.project_manager.current_mut().select_tab(idx) {
+            editor.set_error(e.to_string());
+        }
+        editor.sync_focused_buffer();
+
+        editor.needs_redraw = true;
+        EventResult::Consumed(None)
+    }
+}
+
+impl Default for TabBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component for TabBar {
+    fn handle_event(&mut self, event: &Event, ctx: &mut Context) -> EventResult {
+        match event {
+            Event::Mouse(mouse) => self.handle_mouse(mouse, ctx.editor),
+            _ => EventResult::Ignored(None),
+        }
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Renderer, ctx: &mut Context) {
+        self.tabs.clear();
+
+        let tabs = ctx.editor.tab_line();
+        if tabs.len() < 2 {
+            return;
+        }
+
+        let cell_width = surface.cell_width();
+        let cell_height = surface.cell_height();
+        self.rows = (area.y as f32, area.y as f32 + cell_height);
+
+        let labels: Vec<String> = tabs
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| format!(" {}:{} ", i + 1, name))
+            .collect();
+        let total_width: f32 = labels
+            .iter()
+            .map(|label| label.chars().count() as f32 * cell_width)
+            .sum();
+
+        let mut x_offset = (area.x + area.width) as f32 - total_width;
+
+        surface.draw_rect(
+            x_offset,
+            area.y as f32,
+            total_width,
+            cell_height,
+            benihime_renderer::color::Color::rgb(0.15, 0.15, 0.2),
+        );
+
+        for (idx, (label, (_, is_current))) in labels.iter().zip(&tabs).enumerate() {
+            let label_width = label.chars().count() as f32 * cell_width;
+
+            if *is_current {
+                surface.draw_rect(
+                    x_offset,
+                    area.y as f32,
+                    label_width,
+                    cell_height,
+                    benihime_renderer::color::Color::rgb(0.3, 0.3, 0.4),
+                );
+            }
+
+            let color = if *is_current {
+                benihime_renderer::color::Color::rgb(0.9, 0.9, 0.9)
+            } else {
+                benihime_renderer::color::Color::rgb(0.5, 0.5, 0.5)
+            };
+
+            let section = benihime_renderer::text::TextSection::simple(
+                x_offset,
+                area.y as f32,
+                label.as_str(),
+                surface.font_size(),
+                color,
+            );
+            surface.draw_text(section);
+
+            self.tabs.push((idx, x_offset, x_offset + label_width));
+            x_offset += label_width;
+        }
+    }
+}
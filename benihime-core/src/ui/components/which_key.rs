@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use benihime_renderer::Renderer;
+
+use crate::{
+    graphics::Rect,
+    keymap::KeySequence,
+    ui::composer::{Component, Context},
+};
+
+const COLUMN_GAP: usize = 4;
+
+/// Shows the keys that can follow a pending prefix once the user has paused
+/// for `which_key_delay_ms`.
+pub struct WhichKey {
+    pending: Option<(KeySequence, Instant)>,
+}
+
+impl WhichKey {
+    pub fn new() -> Self {
+        Self { pending: None }
+    }
+}
+
+impl Default for WhichKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component for WhichKey {
+    fn render(&mut self, area: Rect, surface: &mut Renderer, ctx: &mut Context) {
+        let editor = &ctx.editor;
+        let prefix = &editor.keymap.buffer;
+
+        if prefix.chords.is_empty() {
+            self.pending = None;
+            return;
+        }
+
+        let since = match &self.pending {
+            Some((seq, since)) if seq == prefix => *since,
+            _ => {
+                let now = Instant::now();
+                self.pending = Some((prefix.clone(), now));
+                now
+            }
+        };
+
+        if since.elapsed() < Duration::from_millis(editor.config.which_key_delay_ms) {
+            return;
+        }
+
+        let entries: Vec<String> = editor
+            .keymap
+            .continuations(editor.focus_ref().1.id, editor.mode(), prefix)
+            .into_iter()
+            .map(|(chord, label)| format!("{} → {}", chord.to_string(), label))
+            .collect();
+
+        if entries.is_empty() {
+            return;
+        }
+
+        let cell_width = surface.cell_width();
+        let cell_height = surface.cell_height() as u16;
+
+        let column_chars = entries
+            .iter()
+            .map(|entry| entry.chars().count())
+            .max()
+            .unwrap_or(0)
+            + COLUMN_GAP;
+        let column_width = column_chars as f32 * cell_width;
+        let columns = ((area.width as f32 / column_width) as usize).max(1);
+        let rows = entries.len().div_ceil(columns);
+
+        let title = format!("{} -", prefix.to_string());
+        let height = (rows as u16 + 1) * cell_height;
+        let bottom = area.y + area.height.saturating_sub(cell_height);
+        let y = bottom.saturating_sub(height);
+
+        surface.with_overlay_region(
+            area.x as f32,
+            y as f32,
+            area.width as f32,
+            height as f32,
+            |f| {
+                f.draw_rect(
+                    area.x as f32,
+                    y as f32,
+                    area.width as f32,
+                    height as f32,
+                    benihime_renderer::color::Color::rgb(0.1, 0.1, 0.15),
+                );
+
+                let section = benihime_renderer::text::TextSection::simple(
+                    area.x as f32,
+                    y as f32,
+                    title,
+                    f.font_size(),
+                    benihime_renderer::color::Color::rgb(0.9, 0.6, 0.4),
+                );
+                f.draw_text(section);
+
+                for (i, entry) in entries.iter().enumerate() {
+                    let row = i % rows;
+                    let column = i / rows;
+
+                    let section = benihime_renderer::text::TextSection::simple(
+                        area.x as f32 + column as f32 * column_width,
+                        (y + (row as u16 + 1) * cell_height) as f32,
+                        entry.as_str(),
+                        f.font_size(),
+                        benihime_renderer::color::Color::rgb(0.8, 0.8, 0.8),
+                    );
+                    f.draw_text(section);
+                }
+            },
+        );
+    }
+}
@@ -39,27 +39,6 @@ pub struct Context<'a> {
     pub dt: f32,
 }
 
-impl Context<'_> {
-    pub fn block_try_flush_writes(&mut self) -> anyhow::Result<()> {
-        {
-            let editor = &mut *self.editor;
-            let jobs = &mut *self.jobs;
-            tokio::task::block_in_place(move || {
-                tokio::runtime::Handle::current().block_on(jobs.finish(editor, None))
-            })?;
-        }
-
-        {
-            let editor = &mut *self.editor;
-            tokio::task::block_in_place(move || {
-                tokio::runtime::Handle::current().block_on(editor.flush_writes())
-            })?
-        }
-
-        Ok(())
-    }
-}
-
 pub trait Component: Any + AnyComponent {
     fn handle_event(&mut self, _event: &Event, _ctx: &mut Context) -> EventResult {
         EventResult::Ignored(None)
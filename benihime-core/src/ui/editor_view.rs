@@ -1,20 +1,216 @@
-use benihime_renderer::{Renderer, color::Color};
+use std::time::{Duration, Instant};
+
+use benihime_renderer::{
+    Renderer,
+    color::Color,
+    event::{MouseButton, MouseEvent},
+};
 
 use crate::{
-    buffer::Buffer,
-    editor::Editor,
+    buffer::{Buffer, Position, Selection},
+    chars::categorize_char,
+    editor::{AutoSave, Editor, Mode},
     graphics::Rect,
-    ui::composer::{Component, Context},
-    window::Window,
+    hex,
+    ui::composer::{Component, Context, Event, EventResult},
+    window::{Window, WindowId},
 };
 
+const GUTTER_WIDTH_CHARS: f32 = 4.0;
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+struct Click {
+    at: Instant,
+    window: WindowId,
+    pos: Position,
+    count: u8,
+}
+
 pub struct EditorView {
     scroll_offset: usize,
+    /// Cell size of the last frame, needed to map mouse positions to text.
+    cell_size: Option<(f32, f32)>,
+    /// Set while the left button is held after a click in a window.
+    drag: Option<(WindowId, Position)>,
+    last_click: Option<Click>,
 }
 
 impl EditorView {
     pub fn new() -> Self {
-        Self { scroll_offset: 0 }
+        Self {
+            scroll_offset: 0,
+            cell_size: None,
+            drag: None,
+            last_click: None,
+        }
+    }
+
+    /// The window under `(x, y)` and the buffer position there, clamped to the
+    /// text. `None` over the gutter when `text_only` is set, or outside any window.
+    pub fn position_at(
+        &self,
+        editor: &Editor,
+        (x, y): (f32, f32),
+        text_only: bool,
+    ) -> Option<(WindowId, Position)> {
+        let (cell_width, cell_height) = self.cell_size?;
+        let cell_height_u16 = cell_height as u16;
+
+        let minibuffer_height = if editor.minibuffer_manager.current.is_some() {
+            cell_height_u16
+        } else {
+            0
+        };
+
+        editor.tree().windows().find_map(|(window, area, _)| {
+            let top = (area.y + cell_height_u16) as f32;
+            let height =
+                area.height
+                    .saturating_sub(2 * cell_height_u16 + minibuffer_height) as f32;
+            let left = area.x as f32;
+            let text_left = left + (GUTTER_WIDTH_CHARS * cell_width).ceil();
+
+            if x < left || x >= left + area.width as f32 || y < top || y >= top + height {
+                return None;
+            }
+            if text_only && x < text_left {
+                return None;
+            }
+
+            let buffer = editor.buf(window.buffer_id)?;
+            let row = (window.scroll_offset + ((y - top) / cell_height) as usize)
+                .min(buffer.line_count().saturating_sub(1));
+            let col = (window.scroll_left + ((x - text_left).max(0.0) / cell_width) as usize)
+                .min(buffer.line_len(row));
+
+            Some((window.id, Position { row, col }))
+        })
+    }
+
+    fn click(&mut self, editor: &mut Editor, window: WindowId, pos: Position) {
+        let count = match &self.last_click {
+            Some(last)
+                if last.window == window
+                    && last.pos == pos
+                    && last.at.elapsed() < MULTI_CLICK_INTERVAL =>
+            {
+                last.count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some(Click {
+            at: Instant::now(),
+            window,
+            pos,
+            count,
+        });
+
+        editor.focus_window(window);
+        let base_mode = editor.base_mode();
+        editor.mark_undo_cursor();
+        let (window, buf) = editor.focus();
+
+        if window.mode == Mode::Insert {
+            buf.undo_tree.commit_group();
+        }
+
+        match count {
+            2 => {
+                let (start, end) = word_bounds(buf, pos);
+                buf.selection = Some(Selection {
+                    start: Position {
+                        row: pos.row,
+                        col: start,
+                    },
+                });
+                window.cursor = Position {
+                    row: pos.row,
+                    col: end,
+                };
+                window.mode = Mode::Visual;
+            }
+            3 => {
+                buf.selection = Some(Selection {
+                    start: Position {
+                        row: pos.row,
+                        col: 0,
+                    },
+                });
+                window.cursor = if pos.row + 1 < buf.line_count() {
+                    Position {
+                        row: pos.row + 1,
+                        col: 0,
+                    }
+                } else {
+                    Position {
+                        row: pos.row,
+                        col: buf.line_len(pos.row),
+                    }
+                };
+                window.mode = Mode::Visual;
+            }
+            _ => {
+                if window.mode == Mode::Visual {
+                    buf.selection = None;
+                    window.mode = base_mode;
+                }
+                window.cursor = pos;
+            }
+        }
+
+        editor.update_scroll();
+    }
+
+    fn drag_to(&mut self, editor: &mut Editor, pos: Position) {
+        let Some((_, anchor)) = self.drag else {
+            return;
+        };
+
+        let (window, buf) = editor.focus();
+        if window.mode != Mode::Visual {
+            if pos == anchor {
+                return;
+            }
+            buf.selection = Some(Selection { start: anchor });
+            window.mode = Mode::Visual;
+        }
+        window.cursor = pos;
+
+        editor.update_scroll();
+    }
+
+    fn handle_mouse(&mut self, event: &MouseEvent, editor: &mut Editor) -> EventResult {
+        if !matches!(editor.mode(), Mode::Normal | Mode::Insert | Mode::Visual) {
+            return EventResult::Ignored(None);
+        }
+
+        match (event.button, event.pressed) {
+            (Some(MouseButton::Left), true) => {
+                let Some((window, pos)) = self.position_at(editor, event.position, false) else {
+                    return EventResult::Ignored(None);
+                };
+                self.click(editor, window, pos);
+                self.drag = Some((window, pos));
+            }
+            (Some(MouseButton::Left), false) => {
+                if self.drag.take().is_none() {
+                    return EventResult::Ignored(None);
+                }
+            }
+            (None, _) => {
+                let Some((drag_window, _)) = self.drag else {
+                    return EventResult::Ignored(None);
+                };
+                match self.position_at(editor, event.position, false) {
+                    Some((window, pos)) if window == drag_window => self.drag_to(editor, pos),
+                    _ => return EventResult::Ignored(None),
+                }
+            }
+            _ => return EventResult::Ignored(None),
+        }
+
+        editor.needs_redraw = true;
+        EventResult::Consumed(None)
     }
 
     pub fn render_view(
@@ -40,8 +236,7 @@ impl EditorView {
             0
         };
 
-        let gutter_width_chars = 4;
-        let gutter_width = (gutter_width_chars as f32 * cell_width).ceil();
+        let gutter_width = (GUTTER_WIDTH_CHARS * cell_width).ceil();
         let gutter_width_u16 = gutter_width as u16;
         let editor_start_x = area.x + gutter_width_u16;
 
@@ -77,10 +272,14 @@ impl EditorView {
 
         for (row, line_idx) in (start_line..end_line).enumerate() {
             let line = buffer.line(line_idx);
-            let line_str = line.to_string();
 
             let y = y_offset as f32 + (row as f32 * cell_height);
 
+            if buffer.is_hex() {
+                self.render_hex_row(surface, window, buffer, line_idx, editor_start_x as f32, y);
+                continue;
+            }
+
             let is_current_line = line_idx == cursor_row;
             let line_num_str = format!("{:>4}", line_idx + 1);
 
@@ -143,11 +342,15 @@ impl EditorView {
                 }
             }
 
-            if !line_str.is_empty() {
+            let line_len = line.len_bytes();
+            if line_len > 0 {
                 let start_col = scroll_left;
-                if start_col < line_str.len() {
-                    let end_col = (start_col + visible_cols).min(line_str.len());
-                    let visible_text = &line_str[start_col..end_col];
+                if start_col < line_len {
+                    let end_col = (start_col + visible_cols).min(line_len);
+                    // Only the visible part is copied, as lines can be huge.
+                    let visible_text = line
+                        .slice(line.byte_to_char(start_col)..line.byte_to_char(end_col))
+                        .to_string();
                     let x_pos = editor_start_x as f32 + (scroll_left as f32 * cell_width);
 
                     let section = benihime_renderer::text::TextSection::simple(
@@ -162,6 +365,99 @@ impl EditorView {
             }
         }
     }
+
+    /// Draws row `line_idx` of a hex buffer from `x`, marking the byte under
+    /// the cursor.
+    fn render_hex_row(
+        &self,
+        surface: &mut Renderer,
+        window: &Window,
+        buffer: &Buffer,
+        line_idx: usize,
+        x: f32,
+        y: f32,
+    ) {
+        let cell_width = surface.cell_width();
+        let cell_height = surface.cell_height();
+        let scroll_left = window.scroll_left;
+        let col_x = |col: usize| x + (col - scroll_left) as f32 * cell_width;
+        let len = buffer.bytes().map_or(0, <[u8]>::len);
+
+        if window.cursor.row == line_idx
+            && let Some((byte, _)) = hex::byte_at(window.cursor, len)
+            && byte / hex::BYTES_PER_ROW == line_idx
+        {
+            let hex_col = hex::position(byte, 0).col;
+            let ascii_col = hex::ASCII_START + byte % hex::BYTES_PER_ROW;
+            for (col, width) in [(hex_col, 2.0), (ascii_col, 1.0)] {
+                if col >= scroll_left {
+                    surface.draw_rect(
+                        col_x(col),
+                        y,
+                        width * cell_width,
+                        cell_height,
+                        Color::rgb(0.3, 0.25, 0.2),
+                    );
+                }
+            }
+        }
+
+        // Dumps are plain ASCII, so columns are byte offsets.
+        let line: String = buffer
+            .line(line_idx)
+            .chars()
+            .take(buffer.line_len(line_idx))
+            .collect();
+        let segments = [
+            (0, hex::HEX_START, Color::rgb(0.4, 0.4, 0.45)),
+            (hex::HEX_START, hex::ASCII_START - 1, Color::WHITE),
+            (
+                hex::ASCII_START - 1,
+                line.len(),
+                Color::rgb(0.6, 0.75, 0.55),
+            ),
+        ];
+        for (start, end, color) in segments {
+            let start = start.max(scroll_left);
+            let end = end.min(line.len());
+            if start >= end {
+                continue;
+            }
+            let section = benihime_renderer::text::TextSection::simple(
+                col_x(start),
+                y,
+                &line[start..end],
+                surface.font_size(),
+                color,
+            );
+            surface.draw_text(section);
+        }
+    }
+}
+
+/// The run of same-category characters around `pos`, as a half-open column range.
+fn word_bounds(buffer: &Buffer, pos: Position) -> (usize, usize) {
+    let line: Vec<char> = buffer
+        .line(pos.row)
+        .chars()
+        .take(buffer.line_len(pos.row))
+        .collect();
+
+    let Some(&ch) = line.get(pos.col) else {
+        return (pos.col, pos.col);
+    };
+    let category = categorize_char(ch);
+
+    let start = line[..pos.col]
+        .iter()
+        .rposition(|&c| categorize_char(c) != category)
+        .map_or(0, |i| i + 1);
+    let end = line[pos.col..]
+        .iter()
+        .position(|&c| categorize_char(c) != category)
+        .map_or(line.len(), |i| pos.col + i);
+
+    (start, end)
 }
 
 impl Default for EditorView {
@@ -171,7 +467,24 @@ impl Default for EditorView {
 }
 
 impl Component for EditorView {
+    fn handle_event(&mut self, event: &Event, ctx: &mut Context) -> EventResult {
+        match event {
+            Event::Mouse(mouse) => self.handle_mouse(mouse, ctx.editor),
+            Event::FocusGained => {
+                ctx.editor.check_disk_changes();
+                EventResult::Consumed(None)
+            }
+            Event::FocusLost => {
+                ctx.editor.auto_save(AutoSave::FocusLost);
+                EventResult::Consumed(None)
+            }
+            _ => EventResult::Ignored(None),
+        }
+    }
+
     fn render(&mut self, _area: Rect, surface: &mut Renderer, ctx: &mut Context) {
+        self.cell_size = Some((surface.cell_width(), surface.cell_height()));
+
         let editor = &ctx.editor;
 
         for (window, node_area, _is_focus) in editor.tree().windows() {
@@ -0,0 +1,88 @@
+use std::{path::PathBuf, time::Duration};
+
+use benihime_event::{AsyncHook, send_blocking};
+use tokio::{sync::mpsc::Sender, time::Instant};
+
+use crate::{
+    buffer::BufferId,
+    ui::job::{self, Callback, Job},
+    writer::{DiskState, FileStamp},
+};
+
+/// A file-backed buffer to compare with its file.
+pub struct WatchedFile {
+    pub buf_id: BufferId,
+    pub path: PathBuf,
+    pub stamp: FileStamp,
+}
+
+pub struct DiskCheck {
+    pub file: WatchedFile,
+    pub state: DiskState,
+}
+
+/// Compares buffers with their files in a background job.
+pub struct FileWatcher {
+    tx: Sender<Vec<WatchedFile>>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self {
+            tx: WatchHook { files: Vec::new() }.spawn(),
+        }
+    }
+
+    pub fn check(&self, files: Vec<WatchedFile>) {
+        send_blocking(&self.tx, files);
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct WatchHook {
+    files: Vec<WatchedFile>,
+}
+
+impl AsyncHook for WatchHook {
+    type Event = Vec<WatchedFile>;
+
+    fn handle_event(&mut self, files: Vec<WatchedFile>, _: Option<Instant>) -> Option<Instant> {
+        // A newer list covers the one still waiting.
+        self.files = files;
+        Some(Instant::now())
+    }
+
+    fn finish_debounce(&mut self) {
+        let files = std::mem::take(&mut self.files);
+        Job::with_callback(async move {
+            let checks = tokio::task::spawn_blocking(move || {
+                files
+                    .into_iter()
+                    .map(|file| DiskCheck {
+                        state: file.stamp.check(&file.path),
+                        file,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await?;
+            Ok(Callback::Editor(Box::new(move |editor| {
+                editor.apply_disk_checks(checks)
+            })))
+        })
+        .spawn();
+    }
+}
+
+/// Has the editor check its files every `interval`.
+pub async fn tick(interval: Duration) -> anyhow::Result<()> {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        ticks.tick().await;
+        job::dispatch(|editor, _| editor.check_disk_changes()).await;
+    }
+}
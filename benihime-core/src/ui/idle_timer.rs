@@ -0,0 +1,45 @@
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Instant,
+};
+
+/// Wakes the render loop once a deadline passes without being rescheduled.
+/// Used to resolve pending key sequences and to auto-save when the user stops
+/// typing.
+pub struct IdleTimer {
+    tx: mpsc::Sender<Instant>,
+}
+
+impl IdleTimer {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel::<Instant>();
+
+        std::thread::spawn(move || {
+            while let Ok(mut deadline) = rx.recv() {
+                loop {
+                    let wait = deadline.saturating_duration_since(Instant::now());
+                    match rx.recv_timeout(wait) {
+                        Ok(next) => deadline = next,
+                        Err(RecvTimeoutError::Timeout) => {
+                            benihime_event::request_redraw();
+                            break;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+            }
+        });
+
+        Self { tx }
+    }
+
+    pub fn schedule(&self, deadline: Instant) {
+        let _ = self.tx.send(deadline);
+    }
+}
+
+impl Default for IdleTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
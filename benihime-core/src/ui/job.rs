@@ -22,6 +22,7 @@ runtime_local! {
 
 pub async fn dispatch_callback(job: Callback) {
     let _ = JOB_QUEUE.wait().send(job).await;
+    benihime_event::request_redraw();
 }
 
 pub async fn dispatch(job: impl FnOnce(&mut Editor, &mut Composer) + Send + 'static) {
@@ -29,11 +30,13 @@ pub async fn dispatch(job: impl FnOnce(&mut Editor, &mut Composer) + Send + 'sta
         .wait()
         .send(Callback::EditorCompositor(Box::new(job)))
         .await;
+    benihime_event::request_redraw();
 }
 
 pub fn dispatch_blocking(job: impl FnOnce(&mut Editor, &mut Composer) + Send + 'static) {
     let jobs = JOB_QUEUE.wait();
-    send_blocking(jobs, Callback::EditorCompositor(Box::new(job)))
+    send_blocking(jobs, Callback::EditorCompositor(Box::new(job)));
+    benihime_event::request_redraw();
 }
 
 pub enum Callback {
@@ -87,6 +90,17 @@ impl Job {
         self.wait = true;
         self
     }
+
+    /// Runs the job in the background, without waiting for it on exit.
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            match self.future.await {
+                Ok(Some(cb)) => dispatch_callback(cb).await,
+                Ok(None) => (),
+                Err(err) => benihime_event::status::report(err).await,
+            }
+        });
+    }
 }
 
 impl LocalJob {
@@ -188,13 +202,7 @@ impl Jobs {
         if j.wait {
             self.wait_futures.push(j.future);
         } else {
-            tokio::spawn(async move {
-                match j.future.await {
-                    Ok(Some(cb)) => dispatch_callback(cb).await,
-                    Ok(None) => (),
-                    Err(err) => benihime_event::status::report(err).await,
-                }
-            });
+            j.spawn();
         }
     }
 
@@ -1,4 +1,6 @@
 pub mod components;
 pub mod composer;
 pub mod editor_view;
+pub mod file_watcher;
+pub mod idle_timer;
 pub mod job;
@@ -0,0 +1,86 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use toml::{Table, Value};
+
+use crate::undotree::UndoTree;
+
+const VERSION: i64 = 2;
+
+/// Undo history of a file, kept in `<cache>/undo/` under a name derived from
+/// the file's canonical path. The history is only restored while the file on
+/// disk still hashes to the contents it was saved with.
+pub struct UndoFile {
+    path: PathBuf,
+    file: PathBuf,
+}
+
+impl UndoFile {
+    pub fn new(dir: &Path, file: &Path) -> Self {
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        let name = format!("{:016x}.toml", hash(file.to_string_lossy().as_bytes()));
+
+        Self {
+            path: dir.join(name),
+            file,
+        }
+    }
+
+    pub fn save(&self, tree: &UndoTree, contents: &str) -> Result<()> {
+        let mut table = tree.to_toml();
+        table.insert("version".to_string(), Value::Integer(VERSION));
+        table.insert(
+            "file".to_string(),
+            Value::String(self.file.display().to_string()),
+        );
+        table.insert(
+            "hash".to_string(),
+            Value::String(format!("{:016x}", hash(contents.as_bytes()))),
+        );
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, toml::to_string(&table)?)?;
+
+        Ok(())
+    }
+
+    /// Reads the saved history, or `None` when there is none or it was saved
+    /// for different contents.
+    pub fn load(&self, contents: &str) -> Result<Option<UndoTree>> {
+        if !self.path.is_file() {
+            return Ok(None);
+        }
+
+        let table: Table = toml::from_str(&fs::read_to_string(&self.path)?)?;
+
+        if table.get("version").and_then(Value::as_integer) != Some(VERSION) {
+            return Err(anyhow!("unsupported undo file {}", self.path.display()));
+        }
+        if table.get("file").and_then(Value::as_str) != Some(&*self.file.to_string_lossy()) {
+            return Ok(None);
+        }
+        let expected = format!("{:016x}", hash(contents.as_bytes()));
+        if table.get("hash").and_then(Value::as_str) != Some(expected.as_str()) {
+            return Ok(None);
+        }
+
+        UndoTree::from_toml(&table).map(Some)
+    }
+}
+
+/// 64-bit FNV-1a. Stable across builds, unlike the std hashers.
+pub fn hash(bytes: &[u8]) -> u64 {
+    hash_more(0xcbf2_9ce4_8422_2325, bytes)
+}
+
+/// Continues `hash` with more bytes, for data hashed in chunks.
+pub fn hash_more(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
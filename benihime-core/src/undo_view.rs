@@ -0,0 +1,207 @@
+use anyhow::{Result, anyhow};
+
+use crate::{
+    buffer::{BufferId, Position},
+    editor::Editor,
+    tree::Layout,
+    undotree::TimeOffset,
+    window::WindowId,
+};
+
+const VIEW_NAME: &str = "*undo-tree*";
+/// Lines above the tree in the view.
+const HEADER_LINES: usize = 2;
+
+/// The interactive `*undo-tree*` view, shown in a split next to the buffer
+/// whose history it displays. Moving the selection previews that buffer at
+/// the selected node; accepting keeps it there, cancelling returns to the
+/// node the view was opened on.
+#[derive(Debug, Clone)]
+pub struct UndoView {
+    pub target: BufferId,
+    pub view: BufferId,
+    origin: usize,
+    selected: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UndoMotion {
+    Older,
+    Newer,
+    PreviousBranch,
+    NextBranch,
+}
+
+pub fn open(editor: &mut Editor) -> Result<()> {
+    if editor.undo_view.is_some() {
+        cancel(editor)?;
+    }
+
+    let target = editor.focus_ref().1.id;
+    let buf = editor
+        .buf_mut(target)
+        .ok_or_else(|| anyhow!("Buffer not found"))?;
+    buf.undo_tree.commit_group();
+    let seq = buf.undo_tree.current_seq();
+
+    let view = editor.new_special_buffer(VIEW_NAME, "");
+    editor.focus_buf(target);
+    editor.split_with_buffer(view, Layout::Vertical);
+    editor.keymap.push_layer(view, "undo-tree-mode")?;
+
+    editor.undo_view = Some(UndoView {
+        target,
+        view,
+        origin: seq,
+        selected: seq,
+    });
+    refresh(editor);
+
+    Ok(())
+}
+
+pub fn select(editor: &mut Editor, motion: UndoMotion) -> Result<()> {
+    let view = active(editor)?;
+    let tree = &editor
+        .buf(view.target)
+        .ok_or_else(|| anyhow!("Buffer not found"))?
+        .undo_tree;
+
+    let next = match motion {
+        UndoMotion::Older => tree.parent_seq(view.selected),
+        UndoMotion::Newer => tree.active_child_seq(view.selected),
+        UndoMotion::PreviousBranch => tree.sibling_seq(view.selected, -1),
+        UndoMotion::NextBranch => tree.sibling_seq(view.selected, 1),
+    };
+
+    if let Some(seq) = next {
+        preview(editor, view.target, seq);
+    }
+    Ok(())
+}
+
+/// Keeps the buffer at the selected node and closes the view.
+pub fn accept(editor: &mut Editor) -> Result<()> {
+    let view = active(editor)?;
+    close(editor, &view)?;
+    editor.message = Some(format!("Undo state #{}", view.selected));
+    Ok(())
+}
+
+/// Returns the buffer to the node the view was opened on and closes it.
+pub fn cancel(editor: &mut Editor) -> Result<()> {
+    let view = active(editor)?;
+    preview(editor, view.target, view.origin);
+    close(editor, &view)
+}
+
+/// Moves the focused buffer through its states in the order they were
+/// created, across branches.
+pub fn travel(editor: &mut Editor, offset: TimeOffset) -> Result<()> {
+    let target = editor.focus_ref().1.id;
+    let seq = editor
+        .buf(target)
+        .ok_or_else(|| anyhow!("Buffer not found"))?
+        .undo_tree
+        .travel_target(offset);
+
+    preview(editor, target, seq);
+    editor.message = Some(format!("Undo state #{}", seq));
+    Ok(())
+}
+
+/// The open view, dropping it if either of its buffers has gone away.
+fn active(editor: &mut Editor) -> Result<UndoView> {
+    let view = editor
+        .undo_view
+        .clone()
+        .ok_or_else(|| anyhow!("No undo tree view open"))?;
+
+    if editor.buf(view.target).is_none() || editor.buf(view.view).is_none() {
+        editor.undo_view = None;
+        return Err(anyhow!("No undo tree view open"));
+    }
+
+    Ok(view)
+}
+
+fn preview(editor: &mut Editor, target: BufferId, seq: usize) {
+    let cursor = editor.buf_mut(target).and_then(|buf| buf.undo_goto(seq));
+    editor.sync_changes(None);
+    if let Some(cursor) = cursor
+        && let Some(window) = find_window(editor, target)
+    {
+        editor.restore_undo_cursor(window, cursor);
+    }
+
+    if let Some(view) = editor.undo_view.as_mut().filter(|v| v.target == target) {
+        view.selected = seq;
+        refresh(editor);
+    }
+}
+
+fn refresh(editor: &mut Editor) {
+    let Some(view) = editor.undo_view.clone() else {
+        return;
+    };
+    let Some(target) = editor.buf(view.target) else {
+        return;
+    };
+
+    let lines = target.undo_tree.render_lines();
+    let row = lines
+        .iter()
+        .position(|(seq, _)| *seq == view.selected)
+        .unwrap_or(0)
+        + HEADER_LINES;
+
+    let mut text = format!("Undo tree of {}\n\n", target.name);
+    for (_, line) in &lines {
+        text.push_str(line);
+        text.push('\n');
+    }
+
+    if let Some(buf) = editor.buf_mut(view.view) {
+        buf.set_text(&text);
+    }
+
+    let windows: Vec<WindowId> = editor
+        .tree()
+        .windows()
+        .filter(|(window, _, _)| window.buffer_id == view.view)
+        .map(|(window, _, _)| window.id)
+        .collect();
+    for id in windows {
+        editor.tree_mut().get_mut(id).cursor = Position { row, col: 0 };
+    }
+
+    if editor.focus_ref().1.id == view.view {
+        editor.update_scroll();
+    }
+}
+
+fn close(editor: &mut Editor, view: &UndoView) -> Result<()> {
+    editor.undo_view = None;
+
+    let window = find_window(editor, view.view);
+    if let Some(id) = window
+        && editor.tree().window_count() > 1
+    {
+        editor.focus_window(id);
+        editor.close_window()?;
+    }
+    editor.kill_buffer(view.view)?;
+
+    if let Some(id) = find_window(editor, view.target) {
+        editor.focus_window(id);
+    }
+    Ok(())
+}
+
+fn find_window(editor: &Editor, buf_id: BufferId) -> Option<WindowId> {
+    editor
+        .tree()
+        .windows()
+        .find(|(window, _, _)| window.buffer_id == buf_id)
+        .map(|(window, _, _)| window.id)
+}
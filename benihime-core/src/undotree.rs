@@ -1,27 +1,134 @@
 use std::{
     cell::RefCell,
+    collections::{HashMap, HashSet},
     rc::{Rc, Weak},
+    str::FromStr,
+    time::{Duration, SystemTime},
 };
 
-#[derive(Clone, Debug)]
-pub enum Edit {
-    Insert { at: usize, text: String },
-    Delete { at: usize, text: String },
+use anyhow::{Result, anyhow};
+use toml::{Table, Value};
+
+use crate::{
+    buffer::Position,
+    transaction::{ChangeSet, Operation},
+};
+
+/// The changes of one undo step and the changes that revert them.
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub changes: ChangeSet,
+    pub inverse: ChangeSet,
 }
 
+/// Where the cursor and the selection anchor of the window making a change
+/// were, recorded on both sides of every undo node.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UndoCursor {
+    pub cursor: Position,
+    pub anchor: Option<Position>,
+}
+
+/// A change to apply to the text to move between two nodes of the tree,
+/// and where the cursor goes once it is applied.
 #[derive(Debug, Clone)]
-pub enum UndoEntry {
-    Single(Edit),
-    Group(Vec<Edit>),
+pub enum UndoStep {
+    Undo(UndoEntry, UndoCursor),
+    Redo(UndoEntry, UndoCursor),
+}
+
+impl UndoStep {
+    pub fn cursor(&self) -> UndoCursor {
+        match self {
+            UndoStep::Undo(_, cursor) | UndoStep::Redo(_, cursor) => *cursor,
+        }
+    }
+}
+
+/// How far `undo-earlier` and `undo-later` travel through a buffer's states,
+/// which are ordered by when they were created regardless of branch.
+#[derive(Debug, Clone, Copy)]
+pub enum TimeOffset {
+    Steps(isize),
+    Seconds(i64),
+}
+
+impl TimeOffset {
+    pub fn reversed(self) -> Self {
+        match self {
+            TimeOffset::Steps(steps) => TimeOffset::Steps(steps.saturating_neg()),
+            TimeOffset::Seconds(seconds) => TimeOffset::Seconds(seconds.saturating_neg()),
+        }
+    }
+}
+
+/// Parses a step count (`"3"`) or a duration with an `s`, `m`, `h` or `d`
+/// suffix (`"10m"`).
+impl FromStr for TimeOffset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let invalid = || {
+            anyhow!(
+                "Invalid undo offset '{}': expected a count or a duration like 10m",
+                s
+            )
+        };
+
+        if let Ok(steps) = s.parse::<isize>() {
+            return Ok(TimeOffset::Steps(steps));
+        }
+
+        let split = s
+            .len()
+            .checked_sub(1)
+            .filter(|i| s.is_char_boundary(*i))
+            .ok_or_else(invalid)?;
+        let (amount, unit) = s.split_at(split);
+        let amount: i64 = amount.parse().map_err(|_| invalid())?;
+        let unit = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+
+        amount
+            .checked_mul(unit)
+            .map(TimeOffset::Seconds)
+            .ok_or_else(invalid)
+    }
 }
 
 impl UndoEntry {
+    /// The first text the step inserted or deleted, shown in the tree view.
     fn label(&self) -> String {
-        match self {
-            UndoEntry::Single(edit) => format!("{edit:?}"),
-            UndoEntry::Group(edits) => {
-                format!("Group({} edits)", edits.len())
-            }
+        const MAX_CHARS: usize = 24;
+
+        let inserted = self.changes.operations().iter().map(|op| ('+', op));
+        let deleted = self.inverse.operations().iter().map(|op| ('-', op));
+        let texts: Vec<(char, &String)> = inserted
+            .chain(deleted)
+            .filter_map(|(sign, op)| match op {
+                Operation::Insert(text) => Some((sign, text)),
+                _ => None,
+            })
+            .collect();
+
+        let Some((sign, text)) = texts.first() else {
+            return "(empty)".to_string();
+        };
+
+        let mut shown: String = text.chars().take(MAX_CHARS).collect();
+        if text.chars().count() > MAX_CHARS {
+            shown.push('…');
+        }
+
+        match texts.len() {
+            1 => format!("{sign}{shown:?}"),
+            n => format!("{sign}{shown:?} (+{} more)", n - 1),
         }
     }
 }
@@ -30,25 +137,50 @@ pub type NodeId = Rc<RefCell<UndoNode>>;
 
 #[derive(Debug)]
 pub struct UndoNode {
+    /// Creation order within the tree; the root is 0.
+    pub seq: usize,
+    pub time: SystemTime,
     pub entry: Option<UndoEntry>,
+    /// Cursor before and after the change.
+    pub before: UndoCursor,
+    pub after: UndoCursor,
     pub parent: Option<Weak<RefCell<UndoNode>>>,
     pub children: Vec<NodeId>,
     pub active_child: usize,
 }
 
+impl UndoNode {
+    fn undo_step(&self) -> Option<UndoStep> {
+        Some(UndoStep::Undo(self.entry.clone()?, self.before))
+    }
+
+    fn redo_step(&self) -> Option<UndoStep> {
+        Some(UndoStep::Redo(self.entry.clone()?, self.after))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UndoTree {
     root: NodeId,
     current: NodeId,
 
-    current_group: Vec<Edit>,
+    current_group: Option<UndoEntry>,
     grouping_enabled: bool,
+    next_seq: usize,
+
+    /// Cursor when the pending group started, and the latest one seen.
+    group_before: UndoCursor,
+    cursor: UndoCursor,
 }
 
 impl UndoTree {
     pub fn new() -> Self {
         let root = Rc::new(RefCell::new(UndoNode {
+            seq: 0,
+            time: SystemTime::now(),
             entry: None,
+            before: UndoCursor::default(),
+            after: UndoCursor::default(),
             parent: None,
             children: Vec::new(),
             active_child: 0,
@@ -57,18 +189,27 @@ impl UndoTree {
         Self {
             root: root.clone(),
             current: root,
-            current_group: Vec::new(),
+            current_group: None,
             grouping_enabled: true,
+            next_seq: 1,
+            group_before: UndoCursor::default(),
+            cursor: UndoCursor::default(),
         }
     }
     fn push_node(&mut self, entry: UndoEntry) {
         let new_node = Rc::new(RefCell::new(UndoNode {
+            seq: self.next_seq,
+            time: SystemTime::now(),
             entry: Some(entry),
+            before: self.group_before,
+            after: self.cursor,
             parent: Some(Rc::downgrade(&self.current)),
             children: Vec::new(),
             active_child: 0,
         }));
 
+        self.next_seq += 1;
+
         let mut cur = self.current.borrow_mut();
 
         // Branching happens here
@@ -80,30 +221,42 @@ impl UndoTree {
         self.current = new_node;
     }
 
+    /// Tells the tree where the cursor is. The first call after a commit
+    /// records where the next group starts; the last one before the commit
+    /// where it ends.
+    pub fn set_cursor(&mut self, cursor: UndoCursor) {
+        if self.current_group.is_none() {
+            self.group_before = cursor;
+        }
+        self.cursor = cursor;
+    }
+
+    /// Closes the pending edits into one node, so a single undo reverts
+    /// them together. Called after each command and when an Insert mode
+    /// session ends.
     pub fn commit_group(&mut self) {
-        if self.current_group.is_empty() {
-            return;
+        if let Some(entry) = self.current_group.take() {
+            self.push_node(entry);
         }
+    }
 
-        let entry = if self.current_group.len() == 1 {
-            UndoEntry::Single(self.current_group.remove(0))
-        } else {
-            UndoEntry::Group(std::mem::take(&mut self.current_group))
+    /// Records `changes` and their `inverse` into the pending group.
+    pub fn record(&mut self, changes: ChangeSet, inverse: ChangeSet) {
+        let entry = match self.current_group.take() {
+            Some(group) => UndoEntry {
+                changes: group.changes.compose(changes),
+                inverse: inverse.compose(group.inverse),
+            },
+            None => UndoEntry { changes, inverse },
         };
 
-        self.push_node(entry);
-    }
-
-    pub fn record(&mut self, edit: Edit) {
+        self.current_group = Some(entry);
         if !self.grouping_enabled {
-            self.push_node(UndoEntry::Single(edit));
-            return;
+            self.commit_group();
         }
-
-        self.current_group.push(edit);
     }
 
-    pub fn undo(&mut self) -> Option<UndoEntry> {
+    pub fn undo(&mut self) -> Option<UndoStep> {
         self.commit_group();
 
         let parent = {
@@ -111,12 +264,12 @@ impl UndoTree {
             cur.parent.as_ref()?.upgrade()
         };
 
-        let entry = self.current.borrow().entry.clone();
+        let step = self.current.borrow().undo_step();
         self.current = parent.unwrap();
-        entry
+        step
     }
 
-    pub fn redo(&mut self) -> Option<UndoEntry> {
+    pub fn redo(&mut self) -> Option<UndoStep> {
         let child = {
             let cur = self.current.borrow();
             if cur.children.is_empty() {
@@ -125,21 +278,354 @@ impl UndoTree {
             cur.children[cur.active_child].clone()
         };
 
-        let entry = child.borrow().entry.clone();
+        let step = child.borrow().redo_step();
         self.current = child;
-        entry
+        step
+    }
+    /// Every node, parents before their children and siblings in order.
+    fn nodes(&self) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![self.root.clone()];
+
+        while let Some(node) = stack.pop() {
+            stack.extend(node.borrow().children.iter().rev().cloned());
+            nodes.push(node);
+        }
+
+        nodes
+    }
+
+    /// Drops the oldest history until at most `limit` edits remain, by moving
+    /// the root down towards (but never past) the current node. Branches that
+    /// split off above the new root are lost.
+    pub fn truncate(&mut self, limit: usize) {
+        let nodes = self.nodes();
+        if nodes.len() - 1 <= limit {
+            return;
+        }
+
+        let mut sizes: HashMap<*const RefCell<UndoNode>, usize> = HashMap::new();
+        for node in nodes.iter().rev() {
+            let size = 1 + node
+                .borrow()
+                .children
+                .iter()
+                .map(|child| sizes[&Rc::as_ptr(child)])
+                .sum::<usize>();
+            sizes.insert(Rc::as_ptr(node), size);
+        }
+
+        let mut path = vec![self.current.clone()];
+        while let Some(parent) = path.last().and_then(|node| {
+            let node = node.borrow();
+            node.parent.as_ref().and_then(Weak::upgrade)
+        }) {
+            path.push(parent);
+        }
+
+        for node in path.into_iter().rev() {
+            if sizes[&Rc::as_ptr(&node)] - 1 <= limit || Rc::ptr_eq(&node, &self.current) {
+                let mut root = node.borrow_mut();
+                root.entry = None;
+                root.parent = None;
+                drop(root);
+
+                self.root = node;
+                break;
+            }
+        }
     }
-    pub fn render(&self) -> String {
-        let mut out = String::new();
-        out.push_str("Undo Tree\n");
-        out.push_str("=========\n");
 
-        self.render_node(&self.root, &mut out, "", true);
+    /// The whole tree, for saving it to an undo file. Edits that haven't
+    /// been committed to a group yet are not included.
+    pub fn to_toml(&self) -> Table {
+        let nodes = self.nodes();
+        let index: HashMap<*const RefCell<UndoNode>, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (Rc::as_ptr(node), i))
+            .collect();
+
+        let nodes: Vec<Value> = nodes
+            .iter()
+            .map(|node| {
+                let node = node.borrow();
+                let mut table = Table::new();
+
+                table.insert("seq".to_string(), Value::Integer(node.seq as i64));
+                let time = node
+                    .time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default();
+                table.insert("time".to_string(), Value::Integer(time.as_secs() as i64));
+
+                if let Some(parent) = node.parent.as_ref().and_then(Weak::upgrade) {
+                    table.insert(
+                        "parent".to_string(),
+                        Value::Integer(index[&Rc::as_ptr(&parent)] as i64),
+                    );
+                }
+                table.insert(
+                    "active".to_string(),
+                    Value::Integer(node.active_child as i64),
+                );
+
+                if let Some(entry) = &node.entry {
+                    table.insert("changes".to_string(), changes_to_toml(&entry.changes));
+                    table.insert("inverse".to_string(), changes_to_toml(&entry.inverse));
+                    table.insert("before".to_string(), cursor_to_toml(&node.before));
+                    table.insert("after".to_string(), cursor_to_toml(&node.after));
+                }
+
+                Value::Table(table)
+            })
+            .collect();
+
+        let mut table = Table::new();
+        table.insert(
+            "current".to_string(),
+            Value::Integer(index[&Rc::as_ptr(&self.current)] as i64),
+        );
+        table.insert("nodes".to_string(), Value::Array(nodes));
+        table
+    }
+
+    pub fn from_toml(table: &Table) -> Result<Self> {
+        let saved = table
+            .get("nodes")
+            .and_then(Value::as_array)
+            .filter(|nodes| !nodes.is_empty())
+            .ok_or_else(|| anyhow!("undo tree has no nodes"))?;
+
+        let mut nodes: Vec<NodeId> = Vec::with_capacity(saved.len());
+
+        for (i, saved) in saved.iter().enumerate() {
+            let parent = match saved.get("parent").and_then(Value::as_integer) {
+                Some(parent) if (parent as usize) < i => Some(nodes[parent as usize].clone()),
+                Some(parent) => return Err(anyhow!("node {} has invalid parent {}", i, parent)),
+                None if i == 0 => None,
+                None => return Err(anyhow!("node {} has no parent", i)),
+            };
+
+            let entry = match parent {
+                Some(_) => Some(UndoEntry {
+                    changes: changes_from_toml(saved.get("changes"))
+                        .ok_or_else(|| anyhow!("node {} has invalid changes", i))?,
+                    inverse: changes_from_toml(saved.get("inverse"))
+                        .ok_or_else(|| anyhow!("node {} has invalid inverse changes", i))?,
+                }),
+                None => None,
+            };
+
+            let seq = saved
+                .get("seq")
+                .and_then(Value::as_integer)
+                .map_or(i, |seq| seq.max(0) as usize);
+            let time = saved
+                .get("time")
+                .and_then(Value::as_integer)
+                .map_or_else(SystemTime::now, |secs| {
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+                });
+
+            let cursor = |key: &str| saved.get(key).map(cursor_from_toml).unwrap_or_default();
+
+            let node = Rc::new(RefCell::new(UndoNode {
+                seq,
+                time,
+                entry,
+                before: cursor("before"),
+                after: cursor("after"),
+                parent: parent.as_ref().map(Rc::downgrade),
+                children: Vec::new(),
+                active_child: saved.get("active").and_then(Value::as_integer).unwrap_or(0) as usize,
+            }));
+
+            if let Some(parent) = parent {
+                parent.borrow_mut().children.push(node.clone());
+            }
+            nodes.push(node);
+        }
+
+        for node in &nodes {
+            let mut node = node.borrow_mut();
+            node.active_child = node.active_child.min(node.children.len().saturating_sub(1));
+        }
+
+        let current = table
+            .get("current")
+            .and_then(Value::as_integer)
+            .and_then(|current| nodes.get(current as usize))
+            .ok_or_else(|| anyhow!("undo tree has no valid current node"))?
+            .clone();
+
+        let next_seq = nodes
+            .iter()
+            .map(|node| node.borrow().seq)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        Ok(Self {
+            root: nodes[0].clone(),
+            current,
+            current_group: None,
+            grouping_enabled: true,
+            next_seq,
+            group_before: UndoCursor::default(),
+            cursor: UndoCursor::default(),
+        })
+    }
+
+    pub fn current_seq(&self) -> usize {
+        self.current.borrow().seq
+    }
+
+    fn find(&self, seq: usize) -> Option<NodeId> {
+        self.nodes()
+            .into_iter()
+            .find(|node| node.borrow().seq == seq)
+    }
+
+    pub fn parent_seq(&self, seq: usize) -> Option<usize> {
+        let node = self.find(seq)?;
+        let parent = node.borrow().parent.as_ref()?.upgrade()?;
+        let seq = parent.borrow().seq;
+        Some(seq)
+    }
+
+    /// The child `redo` would follow from `seq`.
+    pub fn active_child_seq(&self, seq: usize) -> Option<usize> {
+        let node = self.find(seq)?;
+        let node = node.borrow();
+        let child = node.children.get(node.active_child)?;
+        Some(child.borrow().seq)
+    }
 
-        out
+    /// The sibling `offset` places away from `seq`, not wrapping around.
+    pub fn sibling_seq(&self, seq: usize, offset: isize) -> Option<usize> {
+        let node = self.find(seq)?;
+        let parent = node.borrow().parent.as_ref()?.upgrade()?;
+        let parent = parent.borrow();
+
+        let index = parent
+            .children
+            .iter()
+            .position(|child| Rc::ptr_eq(child, &node))?;
+        let sibling = parent.children.get(index.checked_add_signed(offset)?)?;
+        Some(sibling.borrow().seq)
     }
 
-    fn render_node(&self, node: &NodeId, out: &mut String, prefix: &str, is_last: bool) {
+    /// Makes node `seq` current, returning the changes that take the text
+    /// there from the current node: undoing up to the common ancestor, then
+    /// redoing down the target's branch, which becomes the active one.
+    pub fn goto(&mut self, seq: usize) -> Option<Vec<UndoStep>> {
+        self.commit_group();
+
+        let target = self.find(seq)?;
+
+        let mut branch = vec![target.clone()];
+        while let Some(parent) = {
+            let node = branch.last()?.borrow();
+            node.parent.as_ref().and_then(Weak::upgrade)
+        } {
+            branch.push(parent);
+        }
+        let on_branch: HashSet<*const RefCell<UndoNode>> = branch.iter().map(Rc::as_ptr).collect();
+
+        let mut steps = Vec::new();
+        let mut node = self.current.clone();
+        while !on_branch.contains(&Rc::as_ptr(&node)) {
+            let parent = {
+                let node = node.borrow();
+                steps.extend(node.undo_step());
+                node.parent.as_ref().and_then(Weak::upgrade)?
+            };
+            node = parent;
+        }
+
+        let ancestor = branch.iter().position(|branch| Rc::ptr_eq(branch, &node))?;
+        for pair in branch[..=ancestor].windows(2).rev() {
+            let (child, parent) = (&pair[0], &pair[1]);
+            let mut parent = parent.borrow_mut();
+            if let Some(index) = parent.children.iter().position(|c| Rc::ptr_eq(c, child)) {
+                parent.active_child = index;
+            }
+            steps.extend(child.borrow().redo_step());
+        }
+
+        self.current = target;
+        Some(steps)
+    }
+
+    /// The node `offset` away from the current one in creation order. Time
+    /// offsets land on the newest node created at or before the current
+    /// node's time plus the offset, or the oldest node when there is none.
+    pub fn travel_target(&self, offset: TimeOffset) -> usize {
+        let mut states: Vec<(usize, SystemTime)> = self
+            .nodes()
+            .iter()
+            .map(|node| {
+                let node = node.borrow();
+                (node.seq, node.time)
+            })
+            .collect();
+        states.sort_by_key(|(seq, _)| *seq);
+
+        let (current_seq, current_time) = {
+            let current = self.current.borrow();
+            (current.seq, current.time)
+        };
+
+        match offset {
+            TimeOffset::Steps(steps) => {
+                let index = states
+                    .iter()
+                    .position(|(seq, _)| *seq == current_seq)
+                    .unwrap_or(0);
+                let index = index.saturating_add_signed(steps).min(states.len() - 1);
+                states[index].0
+            }
+            TimeOffset::Seconds(seconds) => {
+                let offset = Duration::from_secs(seconds.unsigned_abs());
+                let time = if seconds < 0 {
+                    current_time
+                        .checked_sub(offset)
+                        .unwrap_or(SystemTime::UNIX_EPOCH)
+                } else if let Some(time) = current_time.checked_add(offset) {
+                    time
+                } else {
+                    // Later than any time can be, so past every state.
+                    return states[states.len() - 1].0;
+                };
+
+                states
+                    .iter()
+                    .rev()
+                    .find(|(_, state)| *state <= time)
+                    .unwrap_or(&states[0])
+                    .0
+            }
+        }
+    }
+
+    /// One line per node, parents above their children, with the node's
+    /// sequence number.
+    pub fn render_lines(&self) -> Vec<(usize, String)> {
+        let mut lines = Vec::new();
+        let now = SystemTime::now();
+        self.render_node(&self.root, &mut lines, "", true, now);
+        lines
+    }
+
+    fn render_node(
+        &self,
+        node: &NodeId,
+        lines: &mut Vec<(usize, String)>,
+        prefix: &str,
+        is_last: bool,
+        now: SystemTime,
+    ) {
         let node_ref = node.borrow();
 
         let marker = if Rc::ptr_eq(node, &self.current) {
@@ -158,10 +644,14 @@ impl UndoTree {
 
         let label = match &node_ref.entry {
             Some(entry) => entry.label(),
-            None => "root".to_string(),
+            None => "original".to_string(),
         };
+        let age = format_age(now.duration_since(node_ref.time).unwrap_or_default());
 
-        out.push_str(&format!("{prefix}{branch}{marker} {label}\n"));
+        lines.push((
+            node_ref.seq,
+            format!("{prefix}{branch}{marker} #{} {label}  {age}", node_ref.seq),
+        ));
 
         let new_prefix = if prefix.is_empty() {
             "   ".to_string()
@@ -173,7 +663,140 @@ impl UndoTree {
 
         let len = node_ref.children.len();
         for (i, child) in node_ref.children.iter().enumerate() {
-            self.render_node(child, out, &new_prefix, i + 1 == len);
+            self.render_node(child, lines, &new_prefix, i + 1 == len, now);
+        }
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{}s ago", secs),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Positive integers retain, negative ones delete and strings insert.
+fn changes_to_toml(changes: &ChangeSet) -> Value {
+    let operations = changes
+        .operations()
+        .iter()
+        .map(|op| match op {
+            Operation::Retain(n) => Value::Integer(*n as i64),
+            Operation::Delete(n) => Value::Integer(-(*n as i64)),
+            Operation::Insert(text) => Value::String(text.clone()),
+        })
+        .collect();
+    Value::Array(operations)
+}
+
+fn changes_from_toml(value: Option<&Value>) -> Option<ChangeSet> {
+    let mut changes = ChangeSet::default();
+
+    for op in value?.as_array()? {
+        match op {
+            Value::Integer(n) if *n > 0 => changes.retain(*n as usize),
+            Value::Integer(n) if *n < 0 => changes.delete(n.unsigned_abs() as usize),
+            Value::String(text) => changes.insert(text.clone()),
+            _ => return None,
+        }
+    }
+
+    Some(changes)
+}
+
+/// `[row, col]`, followed by the anchor's row and column when there was a
+/// selection.
+fn cursor_to_toml(cursor: &UndoCursor) -> Value {
+    let mut values = vec![cursor.cursor.row, cursor.cursor.col];
+    if let Some(anchor) = cursor.anchor {
+        values.extend([anchor.row, anchor.col]);
+    }
+    Value::Array(
+        values
+            .into_iter()
+            .map(|n| Value::Integer(n as i64))
+            .collect(),
+    )
+}
+
+fn cursor_from_toml(value: &Value) -> UndoCursor {
+    let values: Vec<usize> = value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_integer)
+        .map(|n| n.max(0) as usize)
+        .collect();
+    let position = |i: usize| {
+        Some(Position {
+            row: *values.get(i)?,
+            col: *values.get(i + 1)?,
+        })
+    };
+
+    UndoCursor {
+        cursor: position(0).unwrap_or_default(),
+        anchor: position(2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey::Rope;
+
+    use super::*;
+    use crate::transaction::Transaction;
+
+    /// A tree holding `count` single-character insertions, at its newest state.
+    fn tree_with_edits(count: usize) -> UndoTree {
+        let mut tree = UndoTree::new();
+        let mut text = Rope::new();
+        for _ in 0..count {
+            let changes = Transaction::insert(&text, 0, "a").changes().clone();
+            let inverse = changes.invert(&text);
+            changes.apply(&mut text);
+            tree.record(changes, inverse);
+            tree.commit_group();
+        }
+        tree
+    }
+
+    #[test]
+    fn parses_steps_and_durations() {
+        assert!(matches!("3".parse(), Ok(TimeOffset::Steps(3))));
+        assert!(matches!("-2".parse(), Ok(TimeOffset::Steps(-2))));
+        assert!(matches!("10m".parse(), Ok(TimeOffset::Seconds(600))));
+        assert!(matches!("2d".parse(), Ok(TimeOffset::Seconds(172_800))));
+        assert!("10x".parse::<TimeOffset>().is_err());
+        assert!("".parse::<TimeOffset>().is_err());
+    }
+
+    #[test]
+    fn overflowing_durations_are_invalid() {
+        for s in [
+            "9223372036854775807d",
+            "9223372036854775807h",
+            "-9223372036854775807m",
+        ] {
+            assert!(s.parse::<TimeOffset>().is_err(), "{}", s);
         }
     }
+
+    #[test]
+    fn huge_offsets_clamp_to_the_oldest_and_newest_states() {
+        let mut tree = tree_with_edits(3);
+        tree.goto(1);
+
+        assert_eq!(tree.travel_target(TimeOffset::Seconds(i64::MAX)), 3);
+        assert_eq!(tree.travel_target(TimeOffset::Seconds(i64::MIN)), 0);
+        assert_eq!(tree.travel_target(TimeOffset::Steps(isize::MAX)), 3);
+        assert_eq!(tree.travel_target(TimeOffset::Steps(isize::MIN)), 0);
+        assert!(matches!(
+            TimeOffset::Seconds(i64::MIN).reversed(),
+            TimeOffset::Seconds(i64::MAX)
+        ));
+    }
 }
@@ -0,0 +1,310 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::SystemTime,
+};
+
+use anyhow::{Result, anyhow};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::{buffer::BufferId, undo_file::hash};
+
+/// Numbers temporary files, so writes from this process never share one.
+static TEMP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// What a file looked like when it was last read or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+    hash: u64,
+}
+
+/// How a file compares with the stamp taken when it was last read.
+pub enum DiskState {
+    Unchanged,
+    /// Written again with the same contents.
+    Touched(FileStamp),
+    /// Changed, with its new contents.
+    Changed(Vec<u8>, FileStamp),
+    Missing,
+}
+
+impl FileStamp {
+    pub fn new(metadata: &fs::Metadata, bytes: &[u8]) -> FileStamp {
+        FileStamp::with_hash(metadata, hash(bytes))
+    }
+
+    /// A stamp for contents hashed with `undo_file::hash`.
+    pub fn with_hash(metadata: &fs::Metadata, hash: u64) -> FileStamp {
+        FileStamp {
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            len: metadata.len(),
+            hash,
+        }
+    }
+
+    /// Reads the file at `path`, following symlinks, and stamps it.
+    pub fn read(path: &Path) -> io::Result<(Vec<u8>, FileStamp)> {
+        let metadata = fs::metadata(path)?;
+        let bytes = fs::read(path)?;
+        let stamp = FileStamp::new(&metadata, &bytes);
+        Ok((bytes, stamp))
+    }
+
+    /// Compares the file at `path` with this stamp, reading it only when its
+    /// modification time or size differ.
+    pub fn check(&self, path: &Path) -> DiskState {
+        let Ok(metadata) = fs::metadata(path) else {
+            return DiskState::Missing;
+        };
+        if self.matches(&metadata) {
+            return DiskState::Unchanged;
+        }
+
+        match FileStamp::read(path) {
+            Ok((_, stamp)) if stamp.hash == self.hash && stamp.len == self.len => {
+                DiskState::Touched(stamp)
+            }
+            Ok((bytes, stamp)) => DiskState::Changed(bytes, stamp),
+            Err(_) => DiskState::Missing,
+        }
+    }
+
+    /// Whether the file at `path` has another modification time or size.
+    pub fn is_outdated(&self, path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|metadata| !self.matches(&metadata))
+    }
+
+    fn matches(&self, metadata: &fs::Metadata) -> bool {
+        metadata.modified().ok() == Some(self.modified) && metadata.len() == self.len
+    }
+}
+
+pub struct WriteRequest {
+    pub buf_id: BufferId,
+    /// The buffer's version when its text was taken.
+    pub version: u64,
+    pub path: PathBuf,
+    pub bytes: Vec<u8>,
+    /// Where to copy the file's previous contents before replacing them.
+    pub backup_dir: Option<PathBuf>,
+    /// The buffer's stamp before the write, restored if it fails.
+    pub previous_stamp: Option<FileStamp>,
+}
+
+pub struct WriteResult {
+    pub buf_id: BufferId,
+    pub version: u64,
+    pub path: PathBuf,
+    pub previous_stamp: Option<FileStamp>,
+    pub result: Result<FileStamp>,
+}
+
+/// Runs queued writes one at a time, in order.
+pub struct Writer {
+    tx: mpsc::Sender<WriteRequest>,
+    results: UnboundedReceiver<WriteResult>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel::<WriteRequest>();
+        let (results_tx, results) = unbounded_channel();
+
+        std::thread::spawn(move || run(rx, results_tx));
+
+        Self { tx, results }
+    }
+
+    pub fn queue(&self, request: WriteRequest) -> Result<()> {
+        self.tx
+            .send(request)
+            .map_err(|_| anyhow!("The file writer has stopped"))
+    }
+
+    /// A write that has finished, if any, without waiting.
+    pub fn try_recv(&mut self) -> Option<WriteResult> {
+        self.results.try_recv().ok()
+    }
+
+    /// Waits for the next write to finish.
+    pub async fn recv(&mut self) -> Option<WriteResult> {
+        self.results.recv().await
+    }
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn run(rx: mpsc::Receiver<WriteRequest>, results: UnboundedSender<WriteResult>) {
+    while let Ok(request) = rx.recv() {
+        let result = write(&request);
+        let done = WriteResult {
+            buf_id: request.buf_id,
+            version: request.version,
+            path: request.path,
+            previous_stamp: request.previous_stamp,
+            result,
+        };
+        if results.send(done).is_err() {
+            return;
+        }
+        benihime_event::request_redraw();
+    }
+}
+
+fn write(request: &WriteRequest) -> Result<FileStamp> {
+    let path = &request.path;
+    // Write through symlinks to the file they point at, leaving the link.
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+    let metadata = fs::metadata(&target).ok();
+
+    if let (Some(dir), Some(_)) = (&request.backup_dir, &metadata) {
+        backup(&target, dir).map_err(|e| anyhow!("Can't back up {}: {}", path.display(), e))?;
+    }
+
+    write_atomic(&target, &request.bytes, metadata.as_ref())
+        .map_err(|e| anyhow!("Can't write {}: {}", path.display(), e))?;
+
+    let metadata = fs::metadata(&target)
+        .map_err(|e| anyhow!("Can't read {} after writing it: {}", path.display(), e))?;
+    Ok(FileStamp::new(&metadata, &request.bytes))
+}
+
+/// Replaces `target` with `bytes` through a temporary file beside it.
+pub fn write_atomic(
+    target: &Path,
+    bytes: &[u8],
+    metadata: Option<&fs::Metadata>,
+) -> io::Result<()> {
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file name"))?;
+
+    let (temp, mut file) = loop {
+        let temp = dir.join(format!(
+            ".{}.{}-{}.benihime~",
+            name.to_string_lossy(),
+            std::process::id(),
+            TEMP_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => break (temp, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!(
+                        "{} isn't writable; save the file elsewhere with save-as",
+                        dir.display()
+                    ),
+                ));
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    let written = (|| {
+        file.write_all(bytes)?;
+        if let Some(metadata) = metadata {
+            file.set_permissions(metadata.permissions())?;
+            keep_owner(&file, metadata);
+        }
+        file.sync_all()?;
+        fs::rename(&temp, target)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written?;
+
+    // Make the rename itself durable.
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// Gives the new file the owner and group of the one it replaces.
+#[cfg(unix)]
+fn keep_owner(file: &File, metadata: &fs::Metadata) {
+    use std::os::unix::fs::{MetadataExt, fchown};
+
+    if fchown(file, Some(metadata.uid()), Some(metadata.gid())).is_err() {
+        let _ = fchown(file, None, Some(metadata.gid()));
+    }
+}
+
+#[cfg(not(unix))]
+fn keep_owner(_file: &File, _metadata: &fs::Metadata) {}
+
+/// Copies `path` into `dir`, named after its full path.
+fn backup(path: &Path, dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let name = path
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "%");
+    fs::copy(path, dir.join(format!("{}~", name)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("benihime-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_and_leaves_other_temp_files() {
+        let dir = test_dir("write-atomic");
+        let target = dir.join("file.txt");
+        fs::write(&target, "old").unwrap();
+        // Another editor's write in progress.
+        let other = dir.join(".file.txt.1-0.benihime~");
+        fs::write(&other, "theirs").unwrap();
+
+        let metadata = fs::metadata(&target).unwrap();
+        write_atomic(&target, b"new", Some(&metadata)).unwrap();
+        write_atomic(&target, b"newer", Some(&metadata)).unwrap();
+
+        assert_eq!(fs::read(&target).unwrap(), b"newer");
+        assert_eq!(fs::read(&other).unwrap(), b"theirs");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn outdated_stamps_are_found_without_reading() {
+        let dir = test_dir("stamp");
+        let path = dir.join("file.txt");
+        fs::write(&path, "one").unwrap();
+        let (_, stamp) = FileStamp::read(&path).unwrap();
+        assert!(!stamp.is_outdated(&path));
+
+        fs::write(&path, "three").unwrap();
+        assert!(stamp.is_outdated(&path));
+        assert!(!stamp.is_outdated(&dir.join("missing")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -71,7 +71,11 @@ impl KeyModifiers {
 
 #[derive(Debug, Clone)]
 pub struct KeyPress {
+    /// The key as produced by the active keyboard layout.
     pub code: Key,
+    /// The key at the same position on a US QWERTY layout, regardless of the
+    /// active layout or of characters composed with Option/AltGr.
+    pub physical: Key,
     pub pressed: bool,
     pub modifier: KeyModifiers,
 }
@@ -108,4 +112,6 @@ pub enum InputEvent {
     Mouse(MouseEvent),
     Text(String),
     Scroll(ScrollDelta),
+    /// The window gained (`true`) or lost keyboard focus.
+    Focus(bool),
 }
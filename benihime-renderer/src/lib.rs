@@ -13,6 +13,7 @@ use crate::{
 };
 pub use renderer::Renderer;
 use std::sync::Arc;
+pub use winit::window::CursorIcon;
 use winit::window::WindowId;
 
 use crate::event::InputEvent;
@@ -42,6 +43,14 @@ pub trait Application {
 
     fn resize(&mut self, width: u32, height: u32, renderer: &mut Renderer);
 
+    fn close_requested(&mut self, _renderer: &mut Renderer) -> bool {
+        true
+    }
+
+    fn should_exit(&self) -> bool {
+        false
+    }
+
     fn wants_redraw(&self) -> bool {
         false
     }
@@ -269,6 +278,12 @@ pub fn run<A: Application + 'static>(window_config: WindowConfig, app: A) -> Res
             }
         }
 
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            if self.app.should_exit() {
+                event_loop.exit();
+            }
+        }
+
         fn window_event(
             &mut self,
             event_loop: &ActiveEventLoop,
@@ -276,9 +291,16 @@ pub fn run<A: Application + 'static>(window_config: WindowConfig, app: A) -> Res
             event: WindowEvent,
         ) {
             match event {
-                WindowEvent::CloseRequested => {
-                    event_loop.exit();
-                }
+                WindowEvent::CloseRequested => match &mut self.renderer {
+                    Some(renderer) => {
+                        if self.app.close_requested(renderer) {
+                            event_loop.exit();
+                        } else if let Some(win) = &self.window {
+                            win.request_redraw();
+                        }
+                    }
+                    None => event_loop.exit(),
+                },
                 WindowEvent::KeyboardInput {
                     event:
                         KeyEvent {
@@ -312,6 +334,7 @@ pub fn run<A: Application + 'static>(window_config: WindowConfig, app: A) -> Res
                             let code = map_winit_key(&logical_key, &physical_key);
                             let mut key_press = KeyPress {
                                 code,
+                                physical: map_physical_key(&physical_key),
                                 pressed: state == ElementState::Pressed,
                                 modifier: event::KeyModifiers {
                                     shift: modifiers.shift_key(),
@@ -472,6 +495,14 @@ pub fn run<A: Application + 'static>(window_config: WindowConfig, app: A) -> Res
                         }
                     }
                 }
+                WindowEvent::Focused(focused) => {
+                    if let Some(renderer) = &mut self.renderer
+                        && self.app.handle_event(InputEvent::Focus(focused), renderer)
+                        && let Some(win) = &self.window
+                    {
+                        win.request_redraw();
+                    }
+                }
                 WindowEvent::ScaleFactorChanged { .. } => {}
                 WindowEvent::RedrawRequested => {
                     if let Some(renderer) = &mut self.renderer {